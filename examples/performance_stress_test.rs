@@ -1,6 +1,7 @@
 use anyhow::Result;
 use chrono::{Duration, Utc};
 use clauditor::scanner::SessionScanner;
+use clauditor::config::Config;
 use clauditor::coordinator::load_and_group_sessions;
 use std::fs;
 use std::time::Instant;
@@ -88,7 +89,7 @@ fn main() -> Result<()> {
     println!("\nMeasuring full pipeline performance with large data...");
     
     let start_full = Instant::now();
-    let window = load_and_group_sessions()?;
+    let window = load_and_group_sessions(&Config::default())?;
     let full_duration = start_full.elapsed();
     
     let window_count = if window.is_some() { 1 } else { 0 };
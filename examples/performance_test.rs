@@ -1,6 +1,7 @@
 use anyhow::Result;
 use chrono::{Duration, Utc};
 use clauditor::scanner::SessionScanner;
+use clauditor::config::Config;
 use clauditor::coordinator::load_and_group_sessions;
 use std::fs;
 use std::time::Instant;
@@ -85,7 +86,7 @@ fn main() -> Result<()> {
     println!("\nMeasuring full pipeline performance (scan + window grouping)...");
     
     let start_full = Instant::now();
-    let window = load_and_group_sessions()?;
+    let window = load_and_group_sessions(&Config::default())?;
     let full_duration = start_full.elapsed();
     
     let window_count = if window.is_some() { 1 } else { 0 };
@@ -120,13 +121,13 @@ fn main() -> Result<()> {
         println!("  ✗ FAIL: Estimated memory usage exceeds 50MB");
     }
     
-    // Test incremental scan performance
-    println!("\nTesting incremental scan (should be minimal)...");
+    // Test rescan performance; entries come from the entry cache
+    println!("\nTesting cached rescan (should be minimal)...");
     let start_inc = Instant::now();
-    let incremental_sessions = scanner.load_sessions_incremental()?;
+    let rescanned_sessions = scanner.load_sessions()?;
     let inc_duration = start_inc.elapsed();
-    println!("  - Incremental scan found {} new entries", incremental_sessions.len());
-    println!("  - Incremental scan time: {:.2}ms", inc_duration.as_millis());
+    println!("  - Cached rescan found {} sessions", rescanned_sessions.len());
+    println!("  - Cached rescan time: {:.2}ms", inc_duration.as_millis());
     
     // Restore original HOME
     env::set_var("HOME", original_home);
@@ -139,7 +140,7 @@ fn generate_session_entries(project_idx: usize, session_idx: usize) -> Vec<serde
     let now = Utc::now();
     
     // Determine if this session is active (30% chance)
-    let is_active = (project_idx + session_idx) % 3 == 0;
+    let is_active = (project_idx + session_idx).is_multiple_of(3);
     
     // Start time depends on whether session is active
     let start_offset = if is_active {
@@ -164,7 +165,7 @@ fn generate_session_entries(project_idx: usize, session_idx: usize) -> Vec<serde
                 "id": format!("msg_{}_{}_{}", project_idx, session_idx, i),
                 "type": "message",
                 "role": "assistant",
-                "model": if project_idx % 2 == 0 { "claude-opus-4-20250514" } else { "claude-sonnet-4-20250514" },
+                "model": if project_idx.is_multiple_of(2) { "claude-opus-4-20250514" } else { "claude-sonnet-4-20250514" },
                 "usage": {
                     "input_tokens": 100 + (i % 50),
                     "output_tokens": 200 + (i % 100),
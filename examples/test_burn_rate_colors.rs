@@ -28,7 +28,9 @@ fn main() {
                     cache_creation_tokens: 0,
                     cache_read_tokens: 0,
                 },
+                cost_usd: 0.0,
                 entry_count: 100,
+                sessions: Vec::new(),
             }],
             token_counts: TokenCounts {
                 input_tokens: 30000,
@@ -36,6 +38,8 @@ fn main() {
                 cache_creation_tokens: 0,
                 cache_read_tokens: 0,
             },
+            cost_usd: 0.0,
+            models: vec![],
             is_active: true,
         };
     display_active_window(Some(&window_normal));
@@ -54,7 +58,9 @@ fn main() {
                     cache_creation_tokens: 0,
                     cache_read_tokens: 0,
                 },
+                cost_usd: 0.0,
                 entry_count: 50,
+                sessions: Vec::new(),
             }],
             token_counts: TokenCounts {
                 input_tokens: 2000000,
@@ -62,6 +68,8 @@ fn main() {
                 cache_creation_tokens: 0,
                 cache_read_tokens: 0,
             },
+            cost_usd: 0.0,
+            models: vec![],
             is_active: true,
         };
     display_active_window(Some(&window_high));
@@ -80,7 +88,9 @@ fn main() {
                     cache_creation_tokens: 250000,
                     cache_read_tokens: 100000,
                 },
+                cost_usd: 0.0,
                 entry_count: 20,
+                sessions: Vec::new(),
             }],
             token_counts: TokenCounts {
                 input_tokens: 1500000,
@@ -88,6 +98,8 @@ fn main() {
                 cache_creation_tokens: 250000,
                 cache_read_tokens: 100000,
             },
+            cost_usd: 0.0,
+            models: vec![],
             is_active: true,
         };
     display_active_window(Some(&window_extreme));
@@ -19,7 +19,9 @@ fn main() {
                 cache_creation_tokens: 0,
                 cache_read_tokens: 0,
             },
+            cost_usd: 0.0,
             entry_count: 50,
+            sessions: Vec::new(),
         }],
         token_counts: TokenCounts {
             input_tokens: 10000,
@@ -27,6 +29,8 @@ fn main() {
             cache_creation_tokens: 0,
             cache_read_tokens: 0,
         },
+        cost_usd: 0.0,
+        models: vec![],
         is_active: true,
     };
     display_active_window(Some(&single_window));
@@ -45,7 +49,9 @@ fn main() {
                         cache_creation_tokens: 1000,
                         cache_read_tokens: 500,
                     },
+                    cost_usd: 0.0,
                     entry_count: 100,
+                    sessions: Vec::new(),
                 },
                 ProjectUsage {
                     name: "project-beta".to_string(),
@@ -55,7 +61,9 @@ fn main() {
                         cache_creation_tokens: 0,
                         cache_read_tokens: 0,
                     },
+                    cost_usd: 0.0,
                     entry_count: 25,
+                    sessions: Vec::new(),
                 },
                 ProjectUsage {
                     name: "project-gamma".to_string(),
@@ -65,7 +73,9 @@ fn main() {
                         cache_creation_tokens: 500,
                         cache_read_tokens: 200,
                     },
+                    cost_usd: 0.0,
                     entry_count: 40,
+                    sessions: Vec::new(),
                 },
             ],
             token_counts: TokenCounts {
@@ -74,6 +84,8 @@ fn main() {
                 cache_creation_tokens: 500,
                 cache_read_tokens: 200,
             },
+            cost_usd: 0.0,
+            models: vec![],
             is_active: true,
         };
     display_active_window(Some(&window_with_projects));
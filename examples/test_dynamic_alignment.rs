@@ -19,7 +19,9 @@ fn main() {
                     cache_creation_tokens: 0,
                     cache_read_tokens: 0,
                 },
+                cost_usd: 0.0,
                 entry_count: 50,
+                sessions: Vec::new(),
             },
             ProjectUsage {
                 name: "medium-length-project".to_string(),
@@ -29,7 +31,9 @@ fn main() {
                     cache_creation_tokens: 0,
                     cache_read_tokens: 0,
                 },
+                cost_usd: 0.0,
                 entry_count: 25,
+                sessions: Vec::new(),
             },
             ProjectUsage {
                 name: "very-long-project-name-that-might-need-truncation-in-narrow-terminals".to_string(),
@@ -39,7 +43,9 @@ fn main() {
                     cache_creation_tokens: 0,
                     cache_read_tokens: 0,
                 },
+                cost_usd: 0.0,
                 entry_count: 10,
+                sessions: Vec::new(),
             },
             ProjectUsage {
                 name: "another-project-with-moderate-length-name".to_string(),
@@ -49,7 +55,9 @@ fn main() {
                     cache_creation_tokens: 0,
                     cache_read_tokens: 0,
                 },
+                cost_usd: 0.0,
                 entry_count: 5,
+                sessions: Vec::new(),
             },
         ],
         token_counts: TokenCounts {
@@ -58,6 +66,8 @@ fn main() {
             cache_creation_tokens: 0,
             cache_read_tokens: 0,
         },
+        cost_usd: 0.0,
+        models: vec![],
         is_active: true,
     };
     
@@ -13,6 +13,7 @@ fn main() {
         .map(|e| EntryWithProject {
             entry: e,
             project: "test-project".to_string(),
+            session_id: "session".to_string(),
         })
         .collect();
     
@@ -29,7 +29,9 @@ fn main() {
                     cache_creation_tokens: 0,
                     cache_read_tokens: 0,
                 },
+                cost_usd: 0.0,
                 entry_count: 100,
+                sessions: Vec::new(),
             }],
             token_counts: TokenCounts {
                 input_tokens: 450000,
@@ -37,6 +39,8 @@ fn main() {
                 cache_creation_tokens: 0,
                 cache_read_tokens: 0,
             },
+            cost_usd: 0.0,
+            models: vec![],
             is_active: true,
         };
     display_active_window(Some(&window_urgent));
@@ -55,7 +59,9 @@ fn main() {
                     cache_creation_tokens: 0,
                     cache_read_tokens: 0,
                 },
+                cost_usd: 0.0,
                 entry_count: 75,
+                sessions: Vec::new(),
             }],
             token_counts: TokenCounts {
                 input_tokens: 300000,
@@ -63,6 +69,8 @@ fn main() {
                 cache_creation_tokens: 0,
                 cache_read_tokens: 0,
             },
+            cost_usd: 0.0,
+            models: vec![],
             is_active: true,
         };
     display_active_window(Some(&window_warning));
@@ -81,7 +89,9 @@ fn main() {
                     cache_creation_tokens: 0,
                     cache_read_tokens: 0,
                 },
+                cost_usd: 0.0,
                 entry_count: 25,
+                sessions: Vec::new(),
             }],
             token_counts: TokenCounts {
                 input_tokens: 100000,
@@ -89,6 +99,8 @@ fn main() {
                 cache_creation_tokens: 0,
                 cache_read_tokens: 0,
             },
+            cost_usd: 0.0,
+            models: vec![],
             is_active: true,
         };
     display_active_window(Some(&window_comfortable));
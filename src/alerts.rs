@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::coordinator::ActiveWindowSummary;
+use crate::plan::Plan;
+use crate::state::{read_state, write_state, StateLock};
+use crate::types::SessionBlock;
+
+mod actions;
+mod rules;
+
+use actions::run_actions;
+#[allow(unused_imports)]
+pub use rules::{Comparison, Metric, Rule, HYSTERESIS};
+
+/// Alert state file name in the state directory
+const ALERTS_FILE: &str = "alerts.json";
+
+/// One `[[alerts]]` entry of the config file: a rule and what to do when it fires
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertConfig {
+    pub when: Rule,
+    /// Ring the terminal bell
+    #[serde(default)]
+    pub bell: bool,
+    /// Show a desktop notification with `notify-send`
+    #[serde(default)]
+    pub notify: bool,
+    /// Shell command run with the alert as JSON on stdin
+    #[serde(default)]
+    pub command: Option<String>,
+    /// `http://` or `https://` URL the alert is POSTed to as JSON
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+impl AlertConfig {
+    /// Check that the alert does something, and can measure what it asks for
+    pub fn validate(&self, plan: Option<Plan>) -> Result<()> {
+        if !self.bell && !self.notify && self.command.is_none() && self.webhook.is_none() {
+            bail!("alert '{}' has no action (set bell, notify, command or webhook)", self.when);
+        }
+        if matches!(self.when.metric, Metric::PercentOfLimit) && plan.is_none() {
+            bail!("alert '{}' needs a plan to measure against", self.when);
+        }
+        if let Some(url) = &self.webhook {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                bail!("webhook '{}' must be an http:// or https:// URL", url);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A rule that fired, as passed to commands and webhooks
+#[derive(Debug, Serialize)]
+pub struct AlertEvent {
+    pub rule: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub message: String,
+    pub fired_at: DateTime<Utc>,
+    pub window: ActiveWindowSummary,
+}
+
+/// Alert state location in the configured state directory
+pub fn state_path(config: &Config) -> Option<PathBuf> {
+    config.state_file(ALERTS_FILE)
+}
+
+/// Evaluates the configured rules against the live window and runs their actions
+///
+/// Each rule fires at most once per window, when its condition first holds.
+/// After firing it stays disarmed until its value is back past the threshold
+/// by `HYSTERESIS` (or no window is active), so a new window doesn't alert
+/// again while a value hovers around the threshold. Which window each rule
+/// last fired in is kept in the state directory, so watch mode, the daemon and
+/// `serve` running side by side alert once between them.
+pub struct AlertEngine {
+    alerts: Vec<AlertConfig>,
+    armed: Vec<bool>,
+    plan: Option<Plan>,
+    state_file: Option<PathBuf>,
+    /// Window start each rule last fired in
+    fired: HashMap<String, DateTime<Utc>>,
+    failures_tx: Sender<String>,
+    failures: Receiver<String>,
+}
+
+impl AlertEngine {
+    /// An engine for the config's alerts, remembering firings in `state_file`
+    pub fn new(config: &Config, state_file: Option<PathBuf>) -> Self {
+        let (failures_tx, failures) = mpsc::channel();
+        Self {
+            alerts: config.alerts.clone(),
+            armed: vec![true; config.alerts.len()],
+            plan: config.plan,
+            state_file,
+            fired: HashMap::new(),
+            failures_tx,
+            failures,
+        }
+    }
+
+    /// Rules that fire for this window, recording that they did
+    pub fn check(&mut self, window: Option<&SessionBlock>, now: DateTime<Utc>) -> Vec<(usize, AlertEvent)> {
+        let Some(window) = window else {
+            self.armed.iter_mut().for_each(|armed| *armed = true);
+            return Vec::new();
+        };
+
+        let mut events = Vec::new();
+        for (index, alert) in self.alerts.iter().enumerate() {
+            let Some(value) = alert.when.value(window, self.plan, now) else {
+                continue;
+            };
+            if alert.when.is_clear(value) {
+                self.armed[index] = true;
+            }
+            if !self.armed[index] || !alert.when.is_met(value) {
+                continue;
+            }
+
+            self.armed[index] = false;
+            if self.fired.get(&alert.when.text) == Some(&window.start_time) {
+                continue;
+            }
+            self.fired.insert(alert.when.text.clone(), window.start_time);
+            match claim(self.state_file.as_ref(), &alert.when.text, window.start_time) {
+                Ok(false) => continue,
+                Ok(true) => {}
+                // Better to alert twice than not at all
+                Err(e) => {
+                    let _ = self.failures_tx.send(format!("{:#}", e));
+                }
+            }
+
+            events.push((index, AlertEvent {
+                rule: alert.when.to_string(),
+                value,
+                threshold: alert.when.threshold,
+                message: format!("{} (now {})", alert.when, alert.when.format_value(value)),
+                fired_at: now,
+                window: ActiveWindowSummary::from_window(Some(window), now).with_plan(Some(window), self.plan),
+            }));
+        }
+        events
+    }
+
+    /// Check the rules and run the actions of those that fire
+    ///
+    /// Actions other than the bell run on a background thread; their errors
+    /// are collected for `take_failures`.
+    pub fn evaluate(&mut self, window: Option<&SessionBlock>, now: DateTime<Utc>) {
+        for (index, event) in self.check(window, now) {
+            let alert = self.alerts[index].clone();
+            if alert.bell {
+                eprint!("\x07");
+            }
+            let failures = self.failures_tx.clone();
+            std::thread::spawn(move || {
+                for error in run_actions(&alert, &event) {
+                    let _ = failures.send(format!("Alert '{}': {:#}", event.rule, error));
+                }
+            });
+        }
+    }
+
+    /// Errors from actions that ran since the last call
+    pub fn take_failures(&self) -> Vec<String> {
+        self.failures.try_iter().collect()
+    }
+}
+
+/// Record that a rule fired in the window starting at `window_start`
+///
+/// Returns false if another clauditor process already did.
+fn claim(state_file: Option<&PathBuf>, rule: &str, window_start: DateTime<Utc>) -> Result<bool> {
+    let Some(state_file) = state_file else {
+        return Ok(true);
+    };
+    let _lock = StateLock::acquire(state_file)?;
+    let mut fired: HashMap<String, DateTime<Utc>> = read_state(state_file)
+        .unwrap_or_default()
+        .unwrap_or_default();
+    if fired.get(rule) == Some(&window_start) {
+        return Ok(false);
+    }
+    fired.insert(rule.to_string(), window_start);
+    write_state(state_file, &fired)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,84 @@
+use std::io::Write;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use super::{AlertConfig, AlertEvent};
+
+/// How long a webhook may take to connect and answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run an alert's notification, command and webhook, returning what failed
+pub(super) fn run_actions(alert: &AlertConfig, event: &AlertEvent) -> Vec<anyhow::Error> {
+    let mut errors = Vec::new();
+    if alert.notify {
+        let output = Command::new("notify-send")
+            .args(["--app-name=clauditor", "clauditor alert", &event.message])
+            .stdin(Stdio::null())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => errors.push(exit_error("notify-send", output.status, &output.stderr)),
+            Err(e) => errors.push(anyhow!(e).context("Failed to run notify-send")),
+        }
+    }
+    let body = match serde_json::to_vec(event) {
+        Ok(body) => body,
+        Err(e) => {
+            errors.push(e.into());
+            return errors;
+        }
+    };
+    if let Some(command) = &alert.command {
+        if let Err(e) = run_command(command, &body) {
+            errors.push(e);
+        }
+    }
+    if let Some(url) = &alert.webhook {
+        if let Err(e) = post_json(url, &body) {
+            errors.push(e);
+        }
+    }
+    errors
+}
+
+/// Run a shell command with `input` on stdin and wait for it
+pub(super) fn run_command(command: &str, input: &[u8]) -> Result<()> {
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run `{}`", command))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that ignores its input may exit before reading it
+        let _ = stdin.write_all(input);
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(exit_error(&format!("`{}`", command), output.status, &output.stderr));
+    }
+    Ok(())
+}
+
+/// A failed command, with what it printed on stderr rather than on the terminal
+fn exit_error(name: &str, status: ExitStatus, stderr: &[u8]) -> anyhow::Error {
+    match String::from_utf8_lossy(stderr).trim() {
+        "" => anyhow!("{} exited with {}", name, status),
+        stderr => anyhow!("{} exited with {}: {}", name, status, stderr),
+    }
+}
+
+/// POST a JSON body to a webhook and check for a 2xx answer
+fn post_json(url: &str, body: &[u8]) -> Result<()> {
+    let agent = ureq::AgentBuilder::new().timeout(WEBHOOK_TIMEOUT).build();
+    match agent.post(url).set("Content-Type", "application/json").send_bytes(body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => {
+            bail!("Webhook {} answered: {} {}", url, code, response.status_text())
+        }
+        Err(e) => Err(anyhow!(e).context(format!("Failed to reach webhook: {}", url))),
+    }
+}
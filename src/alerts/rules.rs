@@ -0,0 +1,211 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use globset::{Glob, GlobMatcher};
+use serde::{Deserialize, Deserializer};
+
+use crate::display::{format_cost, format_duration_compact, format_number_compact};
+use crate::plan::{LimitStatus, Plan};
+use crate::types::SessionBlock;
+
+/// How far past its threshold a value must fall back before its rule is armed again
+pub const HYSTERESIS: f64 = 0.1;
+
+/// What a rule measures in the active window
+#[derive(Debug, Clone)]
+pub enum Metric {
+    /// Seconds until the window ends
+    TimeRemaining,
+    /// Percent of the plan's token limit used
+    PercentOfLimit,
+    /// Tokens per minute
+    BurnRate,
+    /// Tokens used in the window
+    Tokens,
+    /// Estimated dollars spent in the window
+    Cost,
+    /// Tokens used by projects whose path matches a glob, as in `[filter]`
+    Project(GlobMatcher),
+}
+
+/// Which side of the threshold a rule fires on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+/// A condition on the active window, e.g. `burn_rate > 500k/min`
+///
+/// Thresholds are kept in base units: seconds, percent, tokens per minute,
+/// tokens and dollars.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub metric: Metric,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    /// The rule as written, used to name it in alerts and state
+    pub(super) text: String,
+}
+
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    /// Parse `<metric> <|> <value>`, where the metric is `time_remaining`,
+    /// `percent_of_limit`, `burn_rate`, `tokens`, `cost` or `project <glob>`
+    fn from_str(s: &str) -> Result<Self> {
+        let text = s.split_whitespace().collect::<Vec<_>>().join(" ");
+        let (at, comparison) = text.char_indices()
+            .find_map(|(i, c)| match c {
+                '>' => Some((i, Comparison::Above)),
+                '<' => Some((i, Comparison::Below)),
+                _ => None,
+            })
+            .with_context(|| format!("alert rule '{}' needs a < or > comparison", text))?;
+        let subject = text[..at].trim();
+        let value = text[at + 1..].trim();
+
+        let metric = match subject.split_once(' ') {
+            Some(("project", pattern)) => {
+                let matcher = Glob::new(pattern.trim().trim_matches('"'))
+                    .with_context(|| format!("Invalid project pattern in alert rule '{}'", text))?
+                    .compile_matcher();
+                Metric::Project(matcher)
+            }
+            _ => match subject {
+                "time_remaining" => Metric::TimeRemaining,
+                "percent_of_limit" => Metric::PercentOfLimit,
+                "burn_rate" => Metric::BurnRate,
+                "tokens" => Metric::Tokens,
+                "cost" => Metric::Cost,
+                other => bail!(
+                    "unknown alert metric '{}' (expected time_remaining, percent_of_limit, burn_rate, tokens, cost or project <glob>)",
+                    other
+                ),
+            },
+        };
+
+        let threshold = match metric {
+            Metric::TimeRemaining => parse_duration_secs(value),
+            Metric::PercentOfLimit => parse_plain(value.strip_suffix('%').unwrap_or(value)),
+            Metric::BurnRate => parse_count(value.strip_suffix("/min").unwrap_or(value)),
+            Metric::Tokens | Metric::Project(_) => {
+                parse_count(value.strip_suffix("tokens").unwrap_or(value).trim_end())
+            }
+            Metric::Cost => parse_plain(value.strip_prefix('$').unwrap_or(value)),
+        }
+        .with_context(|| format!("Invalid value '{}' in alert rule '{}'", value, text))?;
+
+        Ok(Self { metric, comparison, threshold, text })
+    }
+}
+
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(|e: anyhow::Error| serde::de::Error::custom(format!("{:#}", e)))
+    }
+}
+
+/// A non-negative number such as `80` or `2.5`
+fn parse_plain(value: &str) -> Result<f64> {
+    match value.trim().replace('_', "").parse::<f64>() {
+        Ok(number) if number.is_finite() && number >= 0.0 => Ok(number),
+        _ => bail!("expected a non-negative number"),
+    }
+}
+
+/// A token count with an optional `k`, `M` or `B` suffix, e.g. `500k`
+fn parse_count(value: &str) -> Result<f64> {
+    let value = value.trim();
+    let (number, scale) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1e3),
+        Some((i, 'm' | 'M')) => (&value[..i], 1e6),
+        Some((i, 'b' | 'B')) => (&value[..i], 1e9),
+        _ => (value, 1.0),
+    };
+    Ok(parse_plain(number)? * scale)
+}
+
+/// A duration such as `30m`, `1h30m` or `90s`, in seconds
+fn parse_duration_secs(value: &str) -> Result<f64> {
+    let mut seconds = 0.0;
+    let mut number = String::new();
+    for c in value.chars().filter(|c| !c.is_whitespace()) {
+        let unit = match c {
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            _ => {
+                number.push(c);
+                continue;
+            }
+        };
+        seconds += parse_plain(&number)? * unit;
+        number.clear();
+    }
+    if !number.is_empty() || value.trim().is_empty() {
+        bail!("expected a duration such as 30m or 1h30m");
+    }
+    Ok(seconds)
+}
+
+impl Rule {
+    /// The measured value for a window
+    ///
+    /// `None` for `percent_of_limit` without a plan.
+    pub fn value(&self, window: &SessionBlock, plan: Option<Plan>, now: DateTime<Utc>) -> Option<f64> {
+        Some(match &self.metric {
+            Metric::TimeRemaining => window.time_remaining(now).num_seconds().max(0) as f64,
+            Metric::PercentOfLimit => LimitStatus::check(window, plan?, now).percent_used,
+            Metric::BurnRate => window.burn_rate(),
+            Metric::Tokens => window.token_counts.total() as f64,
+            Metric::Cost => window.cost_usd,
+            Metric::Project(matcher) => window.projects.iter()
+                .filter(|p| matcher.is_match(&p.name))
+                .map(|p| p.token_counts.total())
+                .sum::<u64>() as f64,
+        })
+    }
+
+    /// True if the value is past the threshold
+    pub fn is_met(&self, value: f64) -> bool {
+        match self.comparison {
+            Comparison::Above => value > self.threshold,
+            Comparison::Below => value < self.threshold,
+        }
+    }
+
+    /// True if the value is back past the threshold by more than `HYSTERESIS`
+    pub(super) fn is_clear(&self, value: f64) -> bool {
+        match self.comparison {
+            Comparison::Above => value < self.threshold * (1.0 - HYSTERESIS),
+            Comparison::Below => value > self.threshold * (1.0 + HYSTERESIS),
+        }
+    }
+
+    /// A value in the rule's units, for messages
+    pub(super) fn format_value(&self, value: f64) -> String {
+        match &self.metric {
+            Metric::TimeRemaining => format_duration_compact(Duration::seconds(value as i64)),
+            Metric::PercentOfLimit => format!("{:.0}%", value),
+            Metric::BurnRate => format!("{}/min", format_number_compact(value as u64)),
+            Metric::Tokens | Metric::Project(_) => format!("{} tokens", format_number_compact(value as u64)),
+            Metric::Cost => format_cost(value),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use super::*;
+use super::actions::run_command;
+use chrono::{Duration, TimeZone};
+use crate::types::{ProjectUsage, TokenCounts};
+
+fn window(start_hour: u32, tokens: u64) -> SessionBlock {
+    let start = Utc.with_ymd_and_hms(2025, 1, 12, start_hour, 0, 0).unwrap();
+    let counts = TokenCounts { input_tokens: tokens, ..TokenCounts::default() };
+    SessionBlock {
+        start_time: start,
+        end_time: start + Duration::hours(5),
+        last_activity: start + Duration::minutes(10),
+        projects: vec![ProjectUsage {
+            name: "/home/me/client-a".to_string(),
+            token_counts: counts.clone(),
+            cost_usd: 0.0,
+            entry_count: 1,
+            sessions: Vec::new(),
+        }],
+        models: Vec::new(),
+        token_counts: counts,
+        cost_usd: 0.0,
+        is_active: true,
+    }
+}
+
+fn engine(rules: &str) -> AlertEngine {
+    let config = Config::from_toml(rules).unwrap();
+    AlertEngine::new(&config, None)
+}
+
+#[test]
+fn test_parse_rules() {
+    let rule: Rule = "burn_rate > 500k/min".parse().unwrap();
+    assert_eq!((rule.comparison, rule.threshold), (Comparison::Above, 500_000.0));
+    assert_eq!("time_remaining < 1h30m".parse::<Rule>().unwrap().threshold, 5_400.0);
+    assert_eq!("percent_of_limit > 80%".parse::<Rule>().unwrap().threshold, 80.0);
+    assert_eq!("cost > $12.50".parse::<Rule>().unwrap().threshold, 12.5);
+
+    let rule: Rule = "project *client-a* > 2M tokens".parse().unwrap();
+    assert_eq!(rule.threshold, 2_000_000.0);
+    assert_eq!(rule.value(&window(14, 3_000_000), None, Utc::now()), Some(3_000_000.0));
+
+    for bad in ["burn_rate 500k", "speed > 5", "tokens > lots", "time_remaining < 30"] {
+        assert!(bad.parse::<Rule>().is_err(), "{:?} parsed", bad);
+    }
+}
+
+#[test]
+fn test_invalid_alerts_are_rejected() {
+    let cases = [
+        ("[[alerts]]\nwhen = \"tokens > 1M\"", "no action"),
+        ("[[alerts]]\nwhen = \"percent_of_limit > 80%\"\nbell = true", "needs a plan"),
+        ("[[alerts]]\nwhen = \"tokens > 1M\"\nwebhook = \"ftp://example.com\"", "https://"),
+        ("[[alerts]]\nwhen = \"tokns > 1M\"\nbell = true", "unknown alert metric"),
+    ];
+    for (toml, expected) in cases {
+        let err = format!("{:#}", Config::from_toml(toml).unwrap_err());
+        assert!(err.contains(expected), "{:?} gave {:?}", toml, err);
+    }
+}
+
+#[test]
+fn test_fires_once_per_window_with_hysteresis() {
+    let mut engine = engine("[[alerts]]\nwhen = \"tokens > 1M\"\nbell = true");
+    let now = Utc.with_ymd_and_hms(2025, 1, 12, 15, 0, 0).unwrap();
+
+    assert!(engine.check(Some(&window(14, 500_000)), now).is_empty());
+    let fired = engine.check(Some(&window(14, 1_200_000)), now);
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].1.message, "tokens > 1M (now 1.2M tokens)");
+    assert!(engine.check(Some(&window(14, 1_300_000)), now).is_empty());
+
+    // Dipping just under the threshold doesn't re-arm it
+    assert!(engine.check(Some(&window(14, 950_000)), now).is_empty());
+    assert!(engine.check(Some(&window(19, 1_100_000)), now).is_empty());
+
+    // A new window that starts clear of the threshold fires again
+    assert!(engine.check(Some(&window(19, 100)), now).is_empty());
+    assert_eq!(engine.check(Some(&window(19, 1_100_000)), now).len(), 1);
+}
+
+#[test]
+fn test_state_file_dedupes_across_engines() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config::from_toml("[[alerts]]\nwhen = \"burn_rate > 1k/min\"\nbell = true").unwrap();
+    let state = Some(dir.path().join(ALERTS_FILE));
+    let now = Utc.with_ymd_and_hms(2025, 1, 12, 15, 0, 0).unwrap();
+
+    let mut first = AlertEngine::new(&config, state.clone());
+    let mut second = AlertEngine::new(&config, state);
+    assert_eq!(first.check(Some(&window(14, 50_000)), now).len(), 1);
+    assert!(second.check(Some(&window(14, 50_000)), now).is_empty());
+}
+
+#[test]
+fn test_command_and_webhook_receive_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("alert.json");
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let port = server.server_addr().to_ip().unwrap().port();
+    let receiver = std::thread::spawn(move || {
+        let mut request = server.recv().unwrap();
+        let mut body = String::new();
+        request.as_reader().read_to_string(&mut body).unwrap();
+        let url = request.url().to_string();
+        request.respond(tiny_http::Response::empty(204)).unwrap();
+        (url, body)
+    });
+
+    let mut engine = engine(&format!(
+        "[[alerts]]\nwhen = \"tokens > 1k\"\ncommand = \"cat > {}\"\nwebhook = \"http://127.0.0.1:{}/hook\"",
+        output.display(), port
+    ));
+    let now = Utc.with_ymd_and_hms(2025, 1, 12, 15, 0, 0).unwrap();
+    let (index, event) = engine.check(Some(&window(14, 5_000)), now).remove(0);
+    assert!(run_actions(&engine.alerts[index], &event).is_empty());
+
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(written["rule"], "tokens > 1k");
+    assert_eq!(written["window"]["total_tokens"], 5_000);
+    let (url, body) = receiver.join().unwrap();
+    assert_eq!(url, "/hook");
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap(), written);
+}
+
+#[test]
+fn test_failures_include_stderr() {
+    let err = run_command("echo 'no such host' >&2; exit 3", b"{}").unwrap_err();
+    assert!(err.to_string().contains("exit status: 3: no such host"), "{}", err);
+    assert!(run_command("true", b"{}").is_ok());
+}
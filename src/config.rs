@@ -0,0 +1,267 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::Duration;
+use serde::{Deserialize, Deserializer};
+
+use crate::alerts::AlertConfig;
+use crate::filter::{FilterConfig, ProjectFilter};
+use crate::plan::Plan;
+use crate::state::default_state_dir;
+use crate::statusline::StatuslineConfig;
+
+/// Default number of hours of session files to scan
+pub const DEFAULT_HOURS_BACK: i64 = 10;
+
+/// Default length of a billing window
+pub const DEFAULT_SESSION_DURATION_HOURS: i64 = 5;
+
+/// Settings resolved from the config file, with CLI overrides applied on top
+///
+/// Every field has a default, so the config file is optional and may set any
+/// subset of keys. Unknown keys are rejected to catch typos.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Claude data directories; each one's `projects` subdirectory is scanned
+    pub claude_paths: Vec<PathBuf>,
+    /// Further data directories scanned in addition to `claude_paths`
+    pub extra_claude_paths: Vec<PathBuf>,
+    /// Only scan session files modified within this many hours
+    pub hours_back: i64,
+    /// Length of a billing window
+    pub session_duration_hours: i64,
+    /// Plan to track usage against
+    #[serde(deserialize_with = "deserialize_plan")]
+    pub plan: Option<Plan>,
+    pub burn_rate: BurnRateThresholds,
+    pub time_remaining: TimeRemainingThresholds,
+    pub filter: FilterConfig,
+    pub statusline: StatuslineConfig,
+    /// Rules checked against the live window, and what to do when they fire
+    pub alerts: Vec<AlertConfig>,
+    /// Where caches, the daemon socket and alert state are kept; `None` keeps nothing
+    pub state_dir: Option<PathBuf>,
+}
+
+/// Burn rates (tokens/min) at which the display changes color
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BurnRateThresholds {
+    pub green_below: f64,
+    pub yellow_above: f64,
+    pub orange_above: f64,
+    pub red_above: f64,
+}
+
+/// Minutes left in the window at which the display changes color
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeRemainingThresholds {
+    pub red_within: i64,
+    pub yellow_within: i64,
+    pub green_beyond: i64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            claude_paths: default_claude_paths(),
+            extra_claude_paths: Vec::new(),
+            hours_back: DEFAULT_HOURS_BACK,
+            session_duration_hours: DEFAULT_SESSION_DURATION_HOURS,
+            plan: None,
+            burn_rate: BurnRateThresholds::default(),
+            time_remaining: TimeRemainingThresholds::default(),
+            filter: FilterConfig::default(),
+            statusline: StatuslineConfig::default(),
+            alerts: Vec::new(),
+            state_dir: default_state_dir(),
+        }
+    }
+}
+
+impl Default for BurnRateThresholds {
+    fn default() -> Self {
+        Self {
+            green_below: 50_000.0,
+            yellow_above: 100_000.0,
+            orange_above: 500_000.0,
+            red_above: 1_000_000.0,
+        }
+    }
+}
+
+impl Default for TimeRemainingThresholds {
+    fn default() -> Self {
+        Self {
+            red_within: 30,
+            yellow_within: 60,
+            green_beyond: 120,
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file at `path`, or the default location if `None`
+    ///
+    /// A missing file at the default location just yields the defaults; a
+    /// missing file that was asked for explicitly is an error.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_config_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        if !explicit && !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        Self::from_toml(&contents)
+            .with_context(|| format!("Invalid config file: {}", path.display()))
+    }
+
+    /// Parse and validate a config document
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let mut config: Self = toml::from_str(contents)?;
+        config.claude_paths = config.claude_paths.iter().map(|p| expand_home(p)).collect();
+        config.extra_claude_paths = config.extra_claude_paths.iter().map(|p| expand_home(p)).collect();
+        config.state_dir = config.state_dir.as_deref().map(expand_home);
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check that the settings make sense together
+    pub fn validate(&self) -> Result<()> {
+        if self.claude_paths.is_empty() {
+            bail!("claude_paths must list at least one directory");
+        }
+        if self.hours_back <= 0 {
+            bail!("hours_back must be greater than zero (got {})", self.hours_back);
+        }
+        if !(1..=24).contains(&self.session_duration_hours) {
+            bail!(
+                "session_duration_hours must be between 1 and 24 (got {})",
+                self.session_duration_hours
+            );
+        }
+
+        let b = &self.burn_rate;
+        if !(b.green_below >= 0.0
+            && b.green_below <= b.yellow_above
+            && b.yellow_above < b.orange_above
+            && b.orange_above < b.red_above)
+        {
+            bail!(
+                "burn_rate thresholds must increase: green_below ({}) <= yellow_above ({}) < orange_above ({}) < red_above ({})",
+                b.green_below, b.yellow_above, b.orange_above, b.red_above
+            );
+        }
+
+        let t = &self.time_remaining;
+        if !(t.red_within >= 0 && t.red_within < t.yellow_within && t.yellow_within <= t.green_beyond) {
+            bail!(
+                "time_remaining thresholds must increase: red_within ({}) < yellow_within ({}) <= green_beyond ({})",
+                t.red_within, t.yellow_within, t.green_beyond
+            );
+        }
+
+        ProjectFilter::from_config(&self.filter)?;
+        self.statusline.validate()?;
+        for alert in &self.alerts {
+            alert.validate(self.plan)?;
+        }
+
+        Ok(())
+    }
+
+    /// Length of a billing window
+    pub fn session_duration(&self) -> Duration {
+        Duration::hours(self.session_duration_hours)
+    }
+
+    /// Path of a file in the state directory
+    pub fn state_file(&self, name: &str) -> Option<PathBuf> {
+        self.state_dir.as_ref().map(|dir| dir.join(name))
+    }
+
+    /// Every data directory to scan and watch: `claude_paths`, then
+    /// `extra_claude_paths`, without duplicates
+    pub fn claude_roots(&self) -> Vec<PathBuf> {
+        let mut roots: Vec<PathBuf> = Vec::new();
+        for path in self.claude_paths.iter().chain(&self.extra_claude_paths) {
+            if !roots.contains(path) {
+                roots.push(path.clone());
+            }
+        }
+        roots
+    }
+
+    /// Fingerprint of the settings that decide which usage is counted and how
+    /// it is grouped, for telling whether results computed elsewhere apply
+    pub fn usage_key(&self) -> String {
+        format!(
+            "{:?}",
+            (self.claude_roots(), self.hours_back, self.session_duration_hours, self.plan, &self.filter)
+        )
+    }
+}
+
+/// Claude data directories used when none are configured
+///
+/// `CLAUDE_CONFIG_DIR` takes the place of the usual `~/.claude` and
+/// `~/.config/claude`, as it does for Claude Code itself. Like Claude Code, it
+/// is read as a single directory.
+pub fn default_claude_paths() -> Vec<PathBuf> {
+    claude_paths_from_env(std::env::var_os("CLAUDE_CONFIG_DIR").as_deref())
+}
+
+fn claude_paths_from_env(claude_config_dir: Option<&OsStr>) -> Vec<PathBuf> {
+    if let Some(dir) = claude_config_dir.filter(|dir| !dir.is_empty()) {
+        return vec![expand_home(Path::new(dir))];
+    }
+
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    vec![
+        PathBuf::from(&home).join(".claude"),
+        PathBuf::from(&home).join(".config/claude"),
+    ]
+}
+
+/// `$XDG_CONFIG_HOME/clauditor/config.toml`, falling back to `~/.config`
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("clauditor").join("config.toml"))
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn deserialize_plan<'de, D>(deserializer: D) -> Result<Option<Plan>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    value
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,83 @@
+use super::*;
+
+#[test]
+fn test_empty_config_uses_defaults() {
+    let config = Config::from_toml("").unwrap();
+    assert_eq!(config, Config::default());
+    assert_eq!(config.session_duration(), Duration::hours(5));
+}
+
+#[test]
+fn test_parse_full_config() {
+    let config = Config::from_toml(r#"
+        claude_paths = ["/data/claude"]
+        state_dir = "~/state"
+        hours_back = 24
+        session_duration_hours = 4
+        plan = "max5"
+
+        [burn_rate]
+        red_above = 2000000
+
+        [time_remaining]
+        red_within = 15
+    "#).unwrap();
+
+    assert_eq!(config.claude_paths, vec![PathBuf::from("/data/claude")]);
+    let state_file = config.state_file("alerts.json").unwrap();
+    assert!(state_file.ends_with("state/alerts.json") && !state_file.starts_with("~"));
+    assert_eq!(config.hours_back, 24);
+    assert_eq!(config.session_duration(), Duration::hours(4));
+    assert_eq!(config.plan, Some(Plan::Max5));
+    assert_eq!(config.burn_rate.red_above, 2_000_000.0);
+    assert_eq!(config.burn_rate.orange_above, 500_000.0);
+    assert_eq!(config.time_remaining.red_within, 15);
+}
+
+#[test]
+fn test_invalid_config_is_rejected() {
+    let cases = [
+        ("session_duration_hours = 0", "session_duration_hours"),
+        ("hours_back = -1", "hours_back"),
+        ("[burn_rate]\norange_above = 2000000", "burn_rate thresholds"),
+        ("[time_remaining]\nred_within = 90", "time_remaining thresholds"),
+        ("plan = \"enterprise\"", "unknown plan"),
+        ("hour_back = 5", "unknown field"),
+        ("[filter]\ninclude = [\"[a\"]", "Invalid project pattern"),
+        ("[statusline]\ntemplate = \"{tokns}\"", "unknown placeholder"),
+    ];
+
+    for (toml, expected) in cases {
+        let err = format!("{:#}", Config::from_toml(toml).unwrap_err());
+        assert!(err.contains(expected), "{:?} gave {:?}", toml, err);
+    }
+}
+
+#[test]
+fn test_missing_explicit_file_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(Config::load(Some(&dir.path().join("missing.toml"))).is_err());
+}
+
+#[test]
+fn test_claude_config_dir_replaces_defaults() {
+    assert_eq!(
+        claude_paths_from_env(Some(OsStr::new("/data/a,b"))),
+        vec![PathBuf::from("/data/a,b")]
+    );
+    assert_eq!(claude_paths_from_env(Some(OsStr::new(""))).len(), 2);
+    assert!(claude_paths_from_env(None)[0].ends_with(".claude"));
+}
+
+#[test]
+fn test_extra_paths_are_added_to_roots() {
+    let config = Config::from_toml(r#"
+        claude_paths = ["/data/claude"]
+        extra_claude_paths = ["/mnt/work/claude", "/data/claude"]
+    "#).unwrap();
+
+    assert_eq!(
+        config.claude_roots(),
+        vec![PathBuf::from("/data/claude"), PathBuf::from("/mnt/work/claude")]
+    );
+}
@@ -1,149 +1,334 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 
+use crate::config::Config;
+use crate::diagnostics::usage_unparseable;
+use crate::entry_cache::EntryCache;
+use crate::filter::ProjectFilter;
+use crate::history::HistoricalWindow;
+use crate::parser::ParseErrors;
+use crate::plan::{LimitStatus, Plan};
 use crate::scanner::SessionScanner;
-use crate::window::{group_into_single_window_with_projects, is_window_active, find_active_window_period};
-use crate::types::{SessionBlock, EntryWithProject};
+use crate::session_history::SessionHistory;
+use crate::usage_state::UsageState;
+use crate::window::{
+    group_into_single_window_with_duration, group_subset_into_account_window, is_window_active,
+};
+use crate::types::{SessionBlock, SessionFile, EntryWithProject, ModelUsage, ProjectUsage, TokenCounts, UsageEntry};
+use crate::watcher::ChangeSet;
 
 /// Load all sessions and group them into a single account-wide billing window
-pub fn load_and_group_sessions() -> Result<Option<SessionBlock>> {
-    // eprintln!("[DEBUG] load_and_group_sessions: Starting");
-    
-    let mut scanner = SessionScanner::new();
-    let sessions = scanner.load_sessions()?;
+/// 
+/// Project filters are applied before grouping, so the window is computed from
+/// matching projects only - unless `account_window` is set, in which case the
+/// boundaries come from every project and only the totals are filtered.
+pub fn load_and_group_sessions(config: &Config) -> Result<Option<SessionBlock>> {
+    let entries_with_projects = load_entries(config)?;
+    group_entries(entries_with_projects, config)
+}
+
+/// Group loaded entries into the billing window, applying the configured project filter
+fn group_entries(entries_with_projects: Vec<EntryWithProject>, config: &Config) -> Result<Option<SessionBlock>> {
+    let filter = ProjectFilter::from_config(&config.filter)?;
+    let now = Utc::now();
     
-    // eprintln!("[DEBUG] load_and_group_sessions: Loaded {} sessions", sessions.len());
+    // Group into single window
+    let window = if config.filter.account_window {
+        group_subset_into_account_window(entries_with_projects, now, config.session_duration(), |e| {
+            filter.matches(&e.project)
+        })
+    } else {
+        group_into_single_window_with_duration(filter.apply(entries_with_projects), now, config.session_duration())
+    };
     
-    // Convert sessions to entries with project info
+    Ok(window)
+}
+
+/// Load every session file the configured scanner can see, with duplicates removed
+pub fn load_entries(config: &Config) -> Result<Vec<EntryWithProject>> {
+    let mut scanner = SessionScanner::from_config(config);
+    load_entries_with_projects(&mut scanner)
+}
+
+/// Load every session the scanner can see and tag each entry with its project
+fn load_entries_with_projects(scanner: &mut SessionScanner) -> Result<Vec<EntryWithProject>> {
+    let sessions = scanner.load_sessions()?;
+    Ok(dedup_entries(tag_with_projects(sessions)))
+}
+
+/// Flatten sessions into entries tagged with their project
+pub(crate) fn tag_with_projects(sessions: impl IntoIterator<Item = SessionFile>) -> Vec<EntryWithProject> {
     let mut entries_with_projects = Vec::new();
     for session in sessions {
-        let _entry_count = session.entries.len();
-        // eprintln!("[DEBUG] load_and_group_sessions: Session {} has {} entries", session.project, entry_count);
-        
         for entry in session.entries {
             entries_with_projects.push(EntryWithProject {
                 entry,
                 project: session.project.clone(),
+                session_id: session.session_id.clone(),
             });
         }
     }
+    entries_with_projects
+}
+
+/// Session files held in memory between reloads, as watch mode uses them
+/// 
+/// After the first scan only the files named in a `ChangeSet` are read again.
+/// Entries appended to a session are fed to a `UsageState`, so the window is
+/// updated without regrouping; if a session shrank, was replaced or removed,
+/// the state is rebuilt from the sessions in memory.
+pub struct LiveSessions {
+    scanner: SessionScanner,
+    /// Keyed by path so rebuilds see sessions in a stable order
+    sessions: BTreeMap<PathBuf, SessionFile>,
+    usage: UsageState,
+}
+
+impl LiveSessions {
+    /// Scan every session file the configured scanner can see
+    pub fn load(config: &Config) -> Result<Self> {
+        // Only runs that scan everything anyway pay for the one-time migration
+        let _ = EntryCache::migrate_legacy_positions(config);
+        let mut live = Self {
+            scanner: SessionScanner::from_config(config),
+            sessions: BTreeMap::new(),
+            usage: UsageState::from_config(config)?,
+        };
+        live.rescan()?;
+        Ok(live)
+    }
     
-    // eprintln!("[DEBUG] load_and_group_sessions: Total {} entries with projects", entries_with_projects.len());
+    /// Scan every session file again, e.g. when file events aren't available
+    pub fn rescan(&mut self) -> Result<()> {
+        self.sessions = self.scanner.load_sessions()?
+            .into_iter()
+            .map(|session| (PathBuf::from(&session.path), session))
+            .collect();
+        self.rebuild_usage();
+        Ok(())
+    }
     
-    // Group into single window
-    let window = group_into_single_window_with_projects(entries_with_projects);
+    /// Re-read updated files and forget removed ones
+    pub fn apply(&mut self, changes: &ChangeSet) -> Result<()> {
+        let mut rebuild = false;
+        for path in changes.removed() {
+            self.scanner.forget_file(path);
+            rebuild |= self.sessions.remove(path).is_some();
+        }
+        
+        let updated: Vec<PathBuf> = changes.updated()
+            .filter(|path| path.is_file())
+            .map(Path::to_path_buf)
+            .collect();
+        let mut appended = Vec::new();
+        let mut reloaded = HashSet::new();
+        for session in self.scanner.load_session_files(&updated)? {
+            let path = PathBuf::from(&session.path);
+            match self.sessions.get(&path) {
+                Some(old) => match appended_entries(old, &session) {
+                    Some(new_entries) => appended.extend(tag_entries(new_entries, &session)),
+                    None => rebuild = true,
+                },
+                None => appended.extend(tag_entries(&session.entries, &session)),
+            }
+            reloaded.insert(path.clone());
+            self.sessions.insert(path, session);
+        }
+        
+        // Updated files that are gone or now empty
+        for path in changes.updated().filter(|path| !reloaded.contains(*path)) {
+            if !path.is_file() {
+                self.scanner.forget_file(path);
+            }
+            rebuild |= self.sessions.remove(path).is_some();
+        }
+        
+        if rebuild {
+            self.rebuild_usage();
+        } else {
+            self.usage.add_entries(appended);
+        }
+        Ok(())
+    }
     
-    match &window {
-        Some(_w) => {}, // eprintln!("[DEBUG] load_and_group_sessions: Window created with {} projects", w.projects.len()),
-        None => {}, // eprintln!("[DEBUG] load_and_group_sessions: No window created"),
+    /// The active billing window as of now
+    pub fn active_window(&mut self) -> Option<SessionBlock> {
+        self.usage.window(Utc::now()).filter(is_window_active)
     }
     
-    Ok(window)
-}
-
-/// Load sessions incrementally and group them into a single account-wide billing window
-/// 
-/// This function now checks if there's an active window period and loads ALL data
-/// for that window, not just incremental updates. This ensures all projects are included.
-pub fn load_and_group_sessions_incremental(scanner: &mut SessionScanner) -> Result<Option<SessionBlock>> {
-    // eprintln!("[DEBUG] load_and_group_sessions_incremental: Starting incremental load");
-    
-    // First, try incremental load to check for new activity
-    let incremental_sessions = scanner.load_sessions_incremental()?;
-    
-    // eprintln!("[DEBUG] load_and_group_sessions_incremental: Found {} incremental sessions", incremental_sessions.len());
-    
-    // If no new data, check if we still have an active window from previous data
-    if incremental_sessions.is_empty() {
-        // eprintln!("[DEBUG] load_and_group_sessions_incremental: No new data, checking for active window via full load");
-        // Load all sessions to check for active window
-        return get_active_billing_window();
-    }
-    
-    // We have new data - check if there's an active window period
-    let mut all_entries = Vec::new();
-    for session in &incremental_sessions {
-        // eprintln!("[DEBUG] load_and_group_sessions_incremental: Session {} has {} new entries", session.project, session.entries.len());
-        for entry in &session.entries {
-            all_entries.push(EntryWithProject {
-                entry: entry.clone(),
-                project: session.project.clone(),
-            });
-        }
+    /// Tokens used in each of the last `minutes` minutes, oldest first
+    pub fn tokens_per_minute(&self, minutes: usize) -> Vec<u64> {
+        self.usage.tokens_per_minute(Utc::now(), minutes)
     }
     
-    // eprintln!("[DEBUG] load_and_group_sessions_incremental: Total {} new entries across all sessions", all_entries.len());
+    /// Lines of the loaded session files that couldn't be parsed
+    pub fn parse_errors(&self) -> ParseErrors {
+        self.scanner.parse_errors()
+    }
     
-    let now = Utc::now();
-    // eprintln!("[DEBUG] load_and_group_sessions_incremental: Current time: {}", now);
+    /// True if the session files have usage lines but none could be parsed
+    pub fn usage_unparseable(&self) -> bool {
+        let entries = self.sessions.values().map(|session| session.entries.len()).sum();
+        usage_unparseable(entries, self.parse_errors().schema_mismatches)
+    }
     
-    // Check if there's an active window based on the new entries
-    if let Some(_window_period) = find_active_window_period(&all_entries, now) {
-        // eprintln!("[DEBUG] load_and_group_sessions_incremental: Active window detected: {} to {}, doing FULL reload", window_period.0, window_period.1);
-        // Active window detected - do a FULL reload to get all projects
-        return get_active_billing_window();
+    fn rebuild_usage(&mut self) {
+        self.usage.clear();
+        self.usage.add_entries(tag_with_projects(self.sessions.values().cloned()));
     }
+}
+
+/// The entries `new` adds to `old`, or `None` if it doesn't just extend it
+fn appended_entries<'a>(old: &SessionFile, new: &'a SessionFile) -> Option<&'a [UsageEntry]> {
+    let same = |a: &UsageEntry, b: &UsageEntry| {
+        a.timestamp == b.timestamp && a.message.id == b.message.id && a.request_id == b.request_id
+    };
+    let last_old = old.entries.len().checked_sub(1)?;
+    match (old.entries.last(), new.entries.get(last_old)) {
+        (Some(a), Some(b)) if same(a, b) => Some(&new.entries[old.entries.len()..]),
+        _ => None,
+    }
+}
+
+fn tag_entries<'a>(entries: &'a [UsageEntry], session: &'a SessionFile) -> impl Iterator<Item = EntryWithProject> + 'a {
+    entries.iter().map(move |entry| EntryWithProject {
+        entry: entry.clone(),
+        project: session.project.clone(),
+        session_id: session.session_id.clone(),
+    })
+}
+
+/// Drop entries whose message id and request id have already been seen
+/// 
+/// Resumed and forked sessions copy earlier messages into the new session
+/// file, so one API response can appear in several files. The first
+/// occurrence is kept. Entries without a message id are never treated as
+/// duplicates; `clauditor doctor` reports how many were dropped.
+pub fn dedup_entries(entries: Vec<EntryWithProject>) -> Vec<EntryWithProject> {
+    let mut seen: HashSet<(String, String)> = HashSet::with_capacity(entries.len());
     
-    // eprintln!("[DEBUG] load_and_group_sessions_incremental: No active window found based on new entries");
-    // No active window found
-    Ok(None)
+    entries
+        .into_iter()
+        .filter(|e| {
+            e.entry.message.id.is_empty()
+                || seen.insert((e.entry.message.id.clone(), e.entry.request_id.clone()))
+        })
+        .collect()
+}
+
+/// Reconstruct every billing window that overlaps `[since, until)`
+pub fn load_history(config: &Config, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<HistoricalWindow>> {
+    SessionHistory::new(config)?.windows(since, until)
 }
 
 /// Get the currently active billing window (if any)
 /// 
 /// This loads ALL sessions and finds the currently active window.
 /// Used for full reloads when we need complete window data.
-pub fn get_active_billing_window() -> Result<Option<SessionBlock>> {
-    // eprintln!("[DEBUG] get_active_billing_window: Starting");
-    
-    let window = load_and_group_sessions()?;
-    
-    match &window {
-        Some(w) => {
-            // eprintln!("[DEBUG] get_active_billing_window: Window exists, is_active: {}", w.is_active);
-            let _active_check = is_window_active(w);
-            // eprintln!("[DEBUG] get_active_billing_window: is_window_active() returned: {}", active_check);
-        }
-        None => {
-            // eprintln!("[DEBUG] get_active_billing_window: No window returned from load_and_group_sessions");
-        }
-    }
+pub fn get_active_billing_window(config: &Config) -> Result<Option<SessionBlock>> {
+    let window = load_and_group_sessions(config)?;
     
     // Return the window only if it's active
-    let result = window.filter(|w| is_window_active(w));
-    
-    match &result {
-        Some(_) => {} ,// eprintln!("[DEBUG] get_active_billing_window: Returning active window"),
-        None => {}, // eprintln!("[DEBUG] get_active_billing_window: Returning None (no active window)"),
-    }
-    
-    Ok(result)
+    Ok(window.filter(is_window_active))
 }
 
-/// Get summary statistics for the active window
-#[allow(dead_code)]
+/// Version of the machine-readable summary format
+///
+/// Bump this whenever a field is renamed or removed so scripts can detect it.
+pub const SUMMARY_SCHEMA_VERSION: u32 = 1;
+
+/// Machine-readable summary of the active window, emitted by `--format json`
+#[derive(Debug, Serialize)]
 pub struct ActiveWindowSummary {
+    pub schema_version: u32,
+    pub generated_at: DateTime<Utc>,
     pub has_active_window: bool,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub last_activity: Option<DateTime<Utc>>,
+    pub time_remaining_seconds: i64,
     pub total_tokens: u64,
+    pub token_counts: TokenCounts,
     pub burn_rate: f64,
+    pub cost_usd: f64,
+    /// Estimated spend in USD per hour
+    pub cost_burn_rate: f64,
+    pub projects: Vec<ProjectUsage>,
+    pub models: Vec<ModelUsage>,
+    /// Usage against the configured plan, or null when no plan is set
+    pub limit: Option<LimitStatus>,
 }
 
 impl ActiveWindowSummary {
-    #[allow(dead_code)]
-    pub fn from_window(window: Option<&SessionBlock>) -> Self {
+    pub fn from_window(window: Option<&SessionBlock>, now: DateTime<Utc>) -> Self {
         match window {
-            Some(w) => Self {
-                has_active_window: true,
-                total_tokens: w.token_counts.total(),
-                burn_rate: w.burn_rate(),
-            },
+            Some(w) => {
+                // Highest usage first, matching the text display
+                let mut projects = w.projects.clone();
+                projects.sort_by(|a, b| {
+                    b.token_counts.total().cmp(&a.token_counts.total())
+                        .then_with(|| a.name.cmp(&b.name))
+                });
+                let mut models = w.models.clone();
+                models.sort_by(|a, b| {
+                    b.token_counts.total().cmp(&a.token_counts.total())
+                        .then_with(|| a.model.cmp(&b.model))
+                });
+                
+                Self {
+                    schema_version: SUMMARY_SCHEMA_VERSION,
+                    generated_at: now,
+                    has_active_window: true,
+                    start_time: Some(w.start_time),
+                    end_time: Some(w.end_time),
+                    last_activity: Some(w.last_activity),
+                    time_remaining_seconds: w.time_remaining(now).num_seconds().max(0),
+                    total_tokens: w.token_counts.total(),
+                    token_counts: w.token_counts.clone(),
+                    burn_rate: w.burn_rate(),
+                    cost_usd: w.cost_usd,
+                    cost_burn_rate: w.cost_burn_rate(),
+                    projects,
+                    models,
+                    limit: None,
+                }
+            }
             None => Self {
+                schema_version: SUMMARY_SCHEMA_VERSION,
+                generated_at: now,
                 has_active_window: false,
+                start_time: None,
+                end_time: None,
+                last_activity: None,
+                time_remaining_seconds: 0,
                 total_tokens: 0,
+                token_counts: TokenCounts::default(),
                 burn_rate: 0.0,
+                cost_usd: 0.0,
+                cost_burn_rate: 0.0,
+                projects: Vec::new(),
+                models: Vec::new(),
+                limit: None,
             },
         }
     }
+    
+    /// Attach plan usage for the summarized window
+    pub fn with_plan(mut self, window: Option<&SessionBlock>, plan: Option<Plan>) -> Self {
+        if let (Some(w), Some(plan)) = (window, plan) {
+            self.limit = Some(LimitStatus::check(w, plan, self.generated_at));
+        }
+        self
+    }
+    
+    /// Serialize as a single line of JSON (one NDJSON record)
+    pub fn to_json_line(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
 }
 
 #[cfg(test)]
@@ -171,14 +356,190 @@ mod tests {
             cost_usd: None,
             request_id: "req_test".to_string(),
             version: "1.0.51".to_string(),
+            unknown: Default::default(),
         };
         
         let entry_with_project = EntryWithProject {
             entry: entry.clone(),
             project: "test-project".to_string(),
+            session_id: "session".to_string(),
         };
         
         assert_eq!(entry_with_project.project, "test-project");
         assert_eq!(entry_with_project.entry.message.id, "test");
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_dedup_entries() {
+        let entry = |message_id: &str, request_id: &str, project: &str| EntryWithProject {
+            entry: crate::types::UsageEntry {
+                timestamp: Utc::now(),
+                message: crate::types::Message {
+                    id: message_id.to_string(),
+                    msg_type: "message".to_string(),
+                    role: "assistant".to_string(),
+                    model: "claude-opus-4-20250514".to_string(),
+                    usage: None,
+                },
+                cost_usd: None,
+                request_id: request_id.to_string(),
+                version: "1.0.51".to_string(),
+                unknown: Default::default(),
+            },
+            project: project.to_string(),
+            session_id: "session".to_string(),
+        };
+        
+        let entries = dedup_entries(vec![
+            entry("msg_1", "req_1", "first"),
+            entry("msg_1", "req_1", "second"),
+            entry("msg_1", "req_2", "first"),
+            entry("", "req_3", "first"),
+            entry("", "req_3", "first"),
+        ]);
+        
+        assert_eq!(entries.len(), 4);
+        // The first copy wins
+        assert_eq!(entries[0].project, "first");
+    }
+    
+    #[test]
+    fn test_active_window_summary_json() {
+        let now: DateTime<Utc> = "2025-01-13T15:00:00Z".parse().unwrap();
+        let window = SessionBlock {
+            start_time: "2025-01-13T14:00:00Z".parse().unwrap(),
+            end_time: "2025-01-13T19:00:00Z".parse().unwrap(),
+            last_activity: "2025-01-13T14:30:00Z".parse().unwrap(),
+            projects: vec![
+                ProjectUsage {
+                    name: "small".to_string(),
+                    token_counts: TokenCounts { input_tokens: 10, ..Default::default() },
+                    cost_usd: 0.0,
+                    entry_count: 1,
+                    sessions: Vec::new(),
+                },
+                ProjectUsage {
+                    name: "large".to_string(),
+                    token_counts: TokenCounts { input_tokens: 290, ..Default::default() },
+                    cost_usd: 0.0,
+                    entry_count: 2,
+                    sessions: Vec::new(),
+                },
+            ],
+            token_counts: TokenCounts { input_tokens: 300, ..Default::default() },
+            cost_usd: 1.5,
+            models: vec![],
+            is_active: true,
+        };
+        
+        let summary = ActiveWindowSummary::from_window(Some(&window), now);
+        let json: serde_json::Value = serde_json::from_str(&summary.to_json_line().unwrap()).unwrap();
+        
+        assert_eq!(json["schema_version"], SUMMARY_SCHEMA_VERSION);
+        assert_eq!(json["has_active_window"], true);
+        assert_eq!(json["start_time"], "2025-01-13T14:00:00Z");
+        assert_eq!(json["time_remaining_seconds"], 4 * 3600);
+        assert_eq!(json["total_tokens"], 300);
+        assert_eq!(json["burn_rate"], 10.0);
+        assert_eq!(json["cost_usd"], 1.5);
+        assert_eq!(json["cost_burn_rate"], 3.0);
+        assert_eq!(json["token_counts"]["input_tokens"], 300);
+        assert_eq!(json["projects"][0]["name"], "large");
+        assert_eq!(json["projects"][1]["name"], "small");
+        assert!(json["limit"].is_null());
+        
+        let summary = ActiveWindowSummary::from_window(Some(&window), now)
+            .with_plan(Some(&window), Some(Plan::Custom(1000)));
+        let json: serde_json::Value = serde_json::from_str(&summary.to_json_line().unwrap()).unwrap();
+        assert_eq!(json["limit"]["token_limit"], 1000);
+        assert_eq!(json["limit"]["tokens_remaining"], 700);
+    }
+    
+    #[test]
+    fn test_active_window_summary_without_window() {
+        let summary = ActiveWindowSummary::from_window(None, Utc::now());
+        let line = summary.to_json_line().unwrap();
+        
+        assert!(!line.contains('\n'), "Summary must fit on a single NDJSON line");
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["has_active_window"], false);
+        assert!(json["start_time"].is_null());
+        assert_eq!(json["projects"].as_array().unwrap().len(), 0);
+    }
+    
+    #[test]
+    fn test_live_sessions_apply_changes() {
+        use crate::watcher::FileEvent;
+        use std::fs;
+        
+        let line = |id: &str, tokens: u64| format!(
+            r#"{{"timestamp":"{}","message":{{"id":"{}","model":"claude-opus-4-20250514","usage":{{"input_tokens":{},"output_tokens":0}}}},"requestId":"req_{}"}}"#,
+            Utc::now().to_rfc3339(), id, tokens, id
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-home-me-proj");
+        fs::create_dir_all(&project_dir).unwrap();
+        let first = project_dir.join("first.jsonl");
+        let second = project_dir.join("second.jsonl");
+        fs::write(&first, format!("{}\n", line("msg_1", 100))).unwrap();
+        fs::write(&second, format!("{}\n", line("msg_2", 200))).unwrap();
+        
+        let config = Config {
+            claude_paths: vec![dir.path().to_path_buf()],
+            state_dir: Some(dir.path().join("state")),
+            ..Config::default()
+        };
+        let mut live = LiveSessions::load(&config).unwrap();
+        let total = |live: &mut LiveSessions| live.active_window()
+            .map(|w| w.token_counts.total())
+            .unwrap_or(0);
+        assert_eq!(total(&mut live), 300);
+        
+        // Appends to an existing file and a new session
+        let third = project_dir.join("third.jsonl");
+        fs::write(&first, format!("{}\n{}\n", line("msg_1", 100), line("msg_3", 1000))).unwrap();
+        fs::write(&third, format!("{}\n", line("msg_4", 5000))).unwrap();
+        let mut changes = ChangeSet::default();
+        changes.add(FileEvent::Modified(first.clone()));
+        changes.add(FileEvent::Created(third.clone()));
+        live.apply(&changes).unwrap();
+        assert_eq!(total(&mut live), 6300);
+        
+        // A removed session no longer counts
+        fs::remove_file(&second).unwrap();
+        let mut changes = ChangeSet::default();
+        changes.add(FileEvent::Removed(second));
+        live.apply(&changes).unwrap();
+        assert_eq!(total(&mut live), 6100);
+        
+        // A rewritten session replaces what it held before
+        fs::write(&first, format!("{}\n", line("msg_5", 50))).unwrap();
+        let mut changes = ChangeSet::default();
+        changes.add(FileEvent::Modified(first.clone()));
+        live.apply(&changes).unwrap();
+        assert_eq!(total(&mut live), 5050);
+        assert!(!live.usage_unparseable());
+    }
+    
+    #[test]
+    fn test_live_sessions_notice_unparseable_usage() {
+        use std::fs;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-home-me-proj");
+        fs::create_dir_all(&project_dir).unwrap();
+        // What a renamed usage field would look like
+        fs::write(project_dir.join("session.jsonl"), format!(
+            "{{\"timestamp\":\"{}\",\"message\":{{\"usage\":{{\"input\":100,\"output\":50}}}}}}\n",
+            Utc::now().to_rfc3339()
+        )).unwrap();
+        
+        let config = Config {
+            claude_paths: vec![dir.path().to_path_buf()],
+            state_dir: Some(dir.path().join("state")),
+            ..Config::default()
+        };
+        let live = LiveSessions::load(&config).unwrap();
+        assert!(live.usage_unparseable());
+    }
+}
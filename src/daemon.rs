@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use crate::config::Config;
+
+mod protocol;
+#[cfg(unix)]
+mod client;
+#[cfg(unix)]
+mod server;
+/// Without Unix domain sockets there is never a daemon to talk to
+#[cfg(not(unix))]
+mod unsupported;
+
+pub use protocol::{Request, Response, WindowUpdate};
+#[cfg(unix)]
+#[allow(unused_imports)]
+pub use client::{forward_updates, Client, Subscription};
+#[cfg(unix)]
+pub use server::Server;
+#[cfg(not(unix))]
+#[allow(unused_imports)]
+pub use unsupported::{forward_updates, Client, Server, Subscription};
+
+/// Socket file name in the state directory
+const SOCKET_FILE: &str = "daemon.sock";
+
+/// Socket location in the configured state directory
+pub fn socket_path(config: &Config) -> Option<PathBuf> {
+    config.state_file(SOCKET_FILE)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn config(dir: &std::path::Path) -> Config {
+        Config {
+            claude_paths: vec![dir.join("claude")],
+            state_dir: Some(dir.join("state")),
+            ..Config::default()
+        }
+    }
+
+    fn update(tokens: u64) -> WindowUpdate {
+        WindowUpdate {
+            window: None,
+            tokens_per_minute: vec![tokens],
+            usage_unparseable: false,
+        }
+    }
+
+    #[test]
+    fn test_requests_are_answered_from_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("state").join(SOCKET_FILE);
+        let config = config(dir.path());
+        let server = Server::bind(&socket, &config, update(1)).unwrap();
+
+        let mut client = Client::connect_to(&socket, &config).unwrap();
+        assert_eq!(client.window().unwrap().tokens_per_minute, [1]);
+        server.publish(update(2));
+        assert_eq!(client.window().unwrap().tokens_per_minute, [2]);
+
+        let now = chrono::Utc::now();
+        assert!(client.history(now - chrono::Duration::days(1), now).unwrap().is_empty());
+
+        // Settings that would count usage differently
+        let other = Config { hours_back: 48, ..config.clone() };
+        let err = Client::connect_to(&socket, &other).unwrap().window().unwrap_err();
+        assert!(err.to_string().contains("different settings"), "{}", err);
+    }
+
+    #[test]
+    fn test_subscribers_get_every_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join(SOCKET_FILE);
+        let config = config(dir.path());
+        let server = Server::bind(&socket, &config, update(1)).unwrap();
+
+        let (first, mut subscription) = Client::connect_to(&socket, &config).unwrap().subscribe().unwrap();
+        assert_eq!(first.tokens_per_minute, [1]);
+        server.publish(update(2));
+        server.publish(update(3));
+        assert_eq!(subscription.next().unwrap().tokens_per_minute, [2]);
+        assert_eq!(subscription.next().unwrap().tokens_per_minute, [3]);
+
+        // The socket goes away with the server, ending the subscription
+        drop(server);
+        assert!(subscription.next().is_none());
+        assert!(!socket.exists());
+        assert!(Client::connect_to(&socket, &config).is_none());
+    }
+
+    #[test]
+    fn test_stale_socket_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join(SOCKET_FILE);
+        let config = config(dir.path());
+
+        // Left behind by a daemon that was killed
+        drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+        assert!(socket.exists());
+        let server = Server::bind(&socket, &config, update(1)).unwrap();
+        assert!(Server::bind(&socket, &config, update(1)).is_err());
+
+        let mut client = Client::connect_to(&socket, &config).unwrap();
+        assert_eq!(client.window().unwrap().tokens_per_minute, [1]);
+        drop(server);
+    }
+}
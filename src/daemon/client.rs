@@ -0,0 +1,149 @@
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::{socket_path, Request, Response, WindowUpdate};
+use crate::config::Config;
+use crate::events::WatchEvent;
+use crate::history::HistoricalWindow;
+use crate::window::is_window_active;
+
+/// How long the client waits for an answer served from memory
+const WINDOW_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long the client waits for history, which may mean reading old sessions
+const HISTORY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A connection to a running daemon
+pub struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    config_key: String,
+}
+
+impl Client {
+    /// Connect to the daemon at the configured socket, if one is running
+    pub fn connect(config: &Config) -> Option<Self> {
+        Self::connect_to(&socket_path(config)?, config)
+    }
+
+    /// Connect to the daemon listening on `socket_path`, if there is one
+    pub fn connect_to(socket_path: &Path, config: &Config) -> Option<Self> {
+        let stream = UnixStream::connect(socket_path).ok()?;
+        Some(Self {
+            writer: stream.try_clone().ok()?,
+            reader: BufReader::new(stream),
+            config_key: config.usage_key(),
+        })
+    }
+
+    /// The daemon's active window, if it is still active
+    pub fn window(&mut self) -> Result<WindowUpdate> {
+        let request = Request::Window { config_key: self.config_key.clone() };
+        match self.request(&request, WINDOW_TIMEOUT)? {
+            Response::Window(mut update) => {
+                // The daemon only re-checks once a minute
+                update.window = update.window.filter(is_window_active);
+                Ok(update)
+            }
+            response => unexpected(response),
+        }
+    }
+
+    /// Every billing window overlapping `[since, until)`
+    pub fn history(&mut self, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<HistoricalWindow>> {
+        let request = Request::History { config_key: self.config_key.clone(), since, until };
+        match self.request(&request, HISTORY_TIMEOUT)? {
+            Response::History { windows } => Ok(windows),
+            response => unexpected(response),
+        }
+    }
+
+    /// The active window now, and a stream of updates to it
+    pub fn subscribe(mut self) -> Result<(WindowUpdate, Subscription)> {
+        let request = Request::Subscribe { config_key: self.config_key.clone() };
+        match self.request(&request, WINDOW_TIMEOUT)? {
+            Response::Window(update) => {
+                self.reader.get_ref().set_read_timeout(None)?;
+                Ok((update, Subscription { reader: self.reader }))
+            }
+            response => unexpected(response),
+        }
+    }
+
+    fn request(&mut self, request: &Request, timeout: Duration) -> Result<Response> {
+        self.writer.write_all(request_line(request).as_bytes())
+            .context("Failed to send a request to the daemon")?;
+        self.reader.get_ref().set_read_timeout(Some(timeout))?;
+        read_response(&mut self.reader)?
+            .context("The daemon closed the connection")
+    }
+}
+
+fn request_line(request: &impl Serialize) -> String {
+    let mut line = serde_json::to_string(request).expect("requests serialize");
+    line.push('\n');
+    line
+}
+
+/// The next response on a connection, or `None` once it is closed
+fn read_response(reader: &mut BufReader<UnixStream>) -> Result<Option<Response>> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => Ok(None),
+        Ok(_) => Ok(Some(serde_json::from_str(&line).context("Invalid response from the daemon")?)),
+        Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            bail!("The daemon didn't answer in time")
+        }
+        Err(e) => Err(e).context("Failed to read from the daemon"),
+    }
+}
+
+fn unexpected<T>(response: Response) -> Result<T> {
+    match response {
+        Response::Error { message } => bail!("Daemon error: {}", message),
+        response => bail!("Unexpected response from the daemon: {:?}", response),
+    }
+}
+
+/// Updates pushed by the daemon after subscribing
+pub struct Subscription {
+    reader: BufReader<UnixStream>,
+}
+
+impl Iterator for Subscription {
+    type Item = WindowUpdate;
+
+    /// The next update, or `None` once the daemon has gone away
+    fn next(&mut self) -> Option<WindowUpdate> {
+        match read_response(&mut self.reader) {
+            Ok(Some(Response::Window(update))) => Some(update),
+            _ => None,
+        }
+    }
+}
+
+/// Pass each update to `on_update` and wake the watch loop, on a background thread
+///
+/// `DaemonGone` is sent once the daemon stops.
+pub fn forward_updates(
+    subscription: Subscription,
+    events: Sender<WatchEvent>,
+    on_update: impl Fn(WindowUpdate) + Send + 'static,
+) {
+    std::thread::spawn(move || {
+        for update in subscription {
+            on_update(update);
+            if events.send(WatchEvent::DaemonUpdate).is_err() {
+                return;
+            }
+        }
+        let _ = events.send(WatchEvent::DaemonGone);
+    });
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::history::HistoricalWindow;
+use crate::types::SessionBlock;
+
+/// The daemon's view of the active window, sent for `window` and `subscribe`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WindowUpdate {
+pub window: Option<SessionBlock>,
+/// Tokens used in each of the last minutes, oldest first
+pub tokens_per_minute: Vec<u64>,
+/// Usage lines were found but none could be parsed
+#[serde(default)]
+pub usage_unparseable: bool,
+}
+
+/// A request line sent to the daemon
+///
+/// Every request carries the client's `Config::usage_key`; a daemon started
+/// with other settings refuses it, and the client scans for itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Request {
+/// The active window, answered from memory
+Window { config_key: String },
+/// Every billing window overlapping `[since, until)`
+History { config_key: String, since: DateTime<Utc>, until: DateTime<Utc> },
+/// The active window now and after every change, until the client hangs up
+Subscribe { config_key: String },
+}
+
+/// A response line sent by the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum Response {
+Window(WindowUpdate),
+History { windows: Vec<HistoricalWindow> },
+Error { message: String },
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use super::{Request, Response, WindowUpdate};
+use crate::config::Config;
+use crate::session_history::SessionHistory;
+use crate::state::create_private_dir;
+use crate::watcher::ChangeSet;
+
+/// How long a subscriber may stall an update before it is dropped
+const SUBSCRIBER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// State shared between the daemon loop and connection threads
+struct Shared {
+    config_key: String,
+    update: WindowUpdate,
+    subscribers: Vec<UnixStream>,
+}
+
+/// The listening end of the daemon socket
+///
+/// Connections are served on their own threads from the latest
+/// `WindowUpdate`; the daemon loop only has to `publish` new ones. History
+/// is answered from sessions kept in memory, which the daemon loop keeps
+/// current with `apply`. The socket file is removed when the server is
+/// dropped.
+pub struct Server {
+    shared: Arc<Mutex<Shared>>,
+    /// Locked apart from `shared`, so a first scan doesn't hold up window requests
+    history: Arc<Mutex<SessionHistory>>,
+    socket_path: PathBuf,
+}
+
+impl Server {
+    /// Listen on `socket_path`, replacing a socket left behind by a daemon that died
+    pub fn bind(socket_path: &Path, config: &Config, update: WindowUpdate) -> Result<Self> {
+        if let Some(dir) = socket_path.parent() {
+            create_private_dir(dir)?;
+        }
+        if UnixStream::connect(socket_path).is_ok() {
+            bail!("A daemon is already listening on {}", socket_path.display());
+        }
+        let history = Arc::new(Mutex::new(SessionHistory::new(config)?));
+        let _ = fs::remove_file(socket_path);
+        let listener = UnixListener::bind(socket_path)
+            .with_context(|| format!("Failed to listen on {}", socket_path.display()))?;
+
+        let shared = Arc::new(Mutex::new(Shared {
+            config_key: config.usage_key(),
+            update,
+            subscribers: Vec::new(),
+        }));
+        let accepting = (Arc::clone(&shared), Arc::clone(&history));
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (shared, history) = (Arc::clone(&accepting.0), Arc::clone(&accepting.1));
+                std::thread::spawn(move || {
+                    let _ = serve(stream, &shared, &history);
+                });
+            }
+        });
+
+        Ok(Self {
+            shared,
+            history,
+            socket_path: socket_path.to_path_buf(),
+        })
+    }
+
+    /// Make `update` the current window and send it to every subscriber
+    pub fn publish(&self, update: WindowUpdate) {
+        let mut shared = self.shared.lock().unwrap();
+        let line = response_line(&Response::Window(update.clone()));
+        shared.subscribers.retain_mut(|stream| stream.write_all(line.as_bytes()).is_ok());
+        shared.update = update;
+    }
+
+    /// Re-read the changed session files that history is answered from
+    pub fn apply(&self, changes: &ChangeSet) -> Result<()> {
+        self.history.lock().unwrap().apply(changes)
+    }
+
+    /// Read history from disk again on the next request, for when file events aren't available
+    pub fn rescan_history(&self) {
+        self.history.lock().unwrap().clear();
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket_path);
+        // Let subscribers know right away rather than when the process exits
+        for stream in self.shared.lock().unwrap().subscribers.drain(..) {
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+    }
+}
+
+/// Answer requests on one connection until it closes or subscribes
+fn serve(stream: UnixStream, shared: &Mutex<Shared>, history: &Mutex<SessionHistory>) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                writer.write_all(error_line(format!("Invalid request: {}", e)).as_bytes())?;
+                continue;
+            }
+        };
+        let config_key = match &request {
+            Request::Window { config_key }
+            | Request::History { config_key, .. }
+            | Request::Subscribe { config_key } => config_key,
+        };
+        if *config_key != shared.lock().unwrap().config_key {
+            writer.write_all(error_line("The daemon is running with different settings").as_bytes())?;
+            continue;
+        }
+
+        match request {
+            Request::Window { .. } => {
+                let update = shared.lock().unwrap().update.clone();
+                writer.write_all(response_line(&Response::Window(update)).as_bytes())?;
+            }
+            Request::History { since, until, .. } => {
+                let line = match history.lock().unwrap().windows(since, until) {
+                    Ok(windows) => response_line(&Response::History { windows }),
+                    Err(e) => error_line(format!("{:#}", e)),
+                };
+                writer.write_all(line.as_bytes())?;
+            }
+            Request::Subscribe { .. } => {
+                // Register under the lock so no update is missed in between
+                let mut shared = shared.lock().unwrap();
+                writer.write_all(response_line(&Response::Window(shared.update.clone())).as_bytes())?;
+                writer.set_write_timeout(Some(SUBSCRIBER_TIMEOUT))?;
+                shared.subscribers.push(writer);
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn response_line(response: &Response) -> String {
+    let mut line = serde_json::to_string(response).expect("responses serialize");
+    line.push('\n');
+    line
+}
+
+fn error_line(message: impl Into<String>) -> String {
+    response_line(&Response::Error { message: message.into() })
+}
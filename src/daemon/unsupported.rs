@@ -0,0 +1,65 @@
+use std::path::Path;
+use std::sync::mpsc::Sender;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+
+use super::WindowUpdate;
+use crate::config::Config;
+use crate::events::WatchEvent;
+use crate::history::HistoricalWindow;
+use crate::watcher::ChangeSet;
+
+pub enum Server {}
+
+impl Server {
+    pub fn bind(_socket_path: &Path, _config: &Config, _update: WindowUpdate) -> Result<Self> {
+        bail!("The daemon needs Unix domain sockets, which this platform doesn't have")
+    }
+
+    pub fn publish(&self, _update: WindowUpdate) {
+        match *self {}
+    }
+
+    pub fn apply(&self, _changes: &ChangeSet) -> Result<()> {
+        match *self {}
+    }
+
+    pub fn rescan_history(&self) {
+        match *self {}
+    }
+}
+
+pub enum Client {}
+
+impl Client {
+    pub fn connect(_config: &Config) -> Option<Self> {
+        None
+    }
+
+    pub fn connect_to(_socket_path: &Path, _config: &Config) -> Option<Self> {
+        None
+    }
+
+    pub fn window(&mut self) -> Result<WindowUpdate> {
+        match *self {}
+    }
+
+    pub fn history(&mut self, _since: DateTime<Utc>, _until: DateTime<Utc>) -> Result<Vec<HistoricalWindow>> {
+        match *self {}
+    }
+
+    pub fn subscribe(self) -> Result<(WindowUpdate, Subscription)> {
+        match self {}
+    }
+}
+
+pub enum Subscription {}
+
+pub fn forward_updates(
+    subscription: Subscription,
+    _events: Sender<WatchEvent>,
+    _on_update: impl Fn(WindowUpdate) + Send + 'static,
+) {
+    match subscription {}
+}
@@ -0,0 +1,183 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::coordinator::{dedup_entries, SUMMARY_SCHEMA_VERSION};
+use crate::parser::FileDiagnostics;
+use crate::scanner::{read_session_file_with_diagnostics, SessionScanner};
+use crate::types::EntryWithProject;
+
+/// A scanned projects directory
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectsDir {
+    pub path: PathBuf,
+    pub exists: bool,
+}
+
+/// A session file that couldn't be opened at all
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreadableFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Line counts summed over every scanned file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiagnosticsTotals {
+    pub files: usize,
+    pub lines: usize,
+    pub entries: usize,
+    pub no_usage: usize,
+    pub malformed_json: usize,
+    pub schema_mismatches: usize,
+    pub io_errors: usize,
+    pub unreadable_files: usize,
+    /// Entries that also appear in another file and are only counted once
+    pub duplicates_dropped: usize,
+}
+
+/// Everything clauditor found while reading session files, emitted by `doctor`
+#[derive(Debug, Serialize)]
+pub struct DiagnosticsReport {
+    pub schema_version: u32,
+    pub generated_at: DateTime<Utc>,
+    pub hours_back: i64,
+    pub projects_dirs: Vec<ProjectsDir>,
+    pub totals: DiagnosticsTotals,
+    /// Per-file details, only for files with problems
+    pub files: Vec<FileDiagnostics>,
+    pub unreadable_files: Vec<UnreadableFile>,
+}
+
+/// Printed by normal runs when `usage_unparseable` holds
+pub const UNPARSEABLE_WARNING: &str =
+    "No usage entries could be parsed; Claude Code's log format may have changed. Run `clauditor doctor` for details.";
+
+/// True if usage lines exist but none of them could be parsed
+///
+/// This is what a Claude Code format change looks like.
+pub fn usage_unparseable(entries: usize, schema_mismatches: usize) -> bool {
+    entries == 0 && schema_mismatches > 0
+}
+
+impl DiagnosticsReport {
+    /// True if usage lines exist but none of them could be parsed
+    pub fn all_usage_unparseable(&self) -> bool {
+        usage_unparseable(self.totals.entries, self.totals.schema_mismatches)
+    }
+
+    /// True if any line or file was lost to a problem
+    pub fn has_problems(&self) -> bool {
+        !self.files.is_empty() || !self.unreadable_files.is_empty()
+    }
+}
+
+/// Parse every session file the scanner can see and report what went wrong
+pub fn run_diagnostics(config: &Config) -> Result<DiagnosticsReport> {
+    let scanner = SessionScanner::from_config(config);
+    let projects_dirs = scanner.projects_dirs()
+        .into_iter()
+        .map(|(path, exists)| ProjectsDir { path, exists })
+        .collect();
+
+    let mut totals = DiagnosticsTotals::default();
+    let mut files = Vec::new();
+    let mut unreadable_files = Vec::new();
+    let mut entries = Vec::new();
+
+    for path in scanner.find_session_files()? {
+        totals.files += 1;
+        let (session, diagnostics) = match read_session_file_with_diagnostics(&path) {
+            Ok(result) => result,
+            Err(e) => {
+                unreadable_files.push(UnreadableFile { path, error: format!("{:#}", e) });
+                continue;
+            }
+        };
+
+        totals.lines += diagnostics.lines;
+        totals.entries += diagnostics.entries;
+        totals.no_usage += diagnostics.no_usage;
+        totals.malformed_json += diagnostics.malformed_json.count;
+        totals.schema_mismatches += diagnostics.schema_mismatches.count;
+        totals.io_errors += diagnostics.io_errors.count;
+        if diagnostics.has_problems() {
+            files.push(diagnostics);
+        }
+
+        entries.extend(session.entries.into_iter().map(|entry| EntryWithProject {
+            entry,
+            project: session.project.clone(),
+            session_id: session.session_id.clone(),
+        }));
+    }
+
+    totals.unreadable_files = unreadable_files.len();
+    totals.duplicates_dropped = entries.len() - dedup_entries(entries).len();
+
+    Ok(DiagnosticsReport {
+        schema_version: SUMMARY_SCHEMA_VERSION,
+        generated_at: Utc::now(),
+        hours_back: config.hours_back,
+        projects_dirs,
+        totals,
+        files,
+        unreadable_files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_run_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-home-me-proj");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::copy("test_data/sample.jsonl", project_dir.join("sample.jsonl")).unwrap();
+        fs::copy("test_data/sample.jsonl", project_dir.join("copy.jsonl")).unwrap();
+
+        let config = Config {
+            claude_paths: vec![dir.path().to_path_buf(), dir.path().join("missing")],
+            state_dir: Some(dir.path().join("state")),
+            ..Config::default()
+        };
+        let report = run_diagnostics(&config).unwrap();
+
+        assert_eq!(report.totals.files, 2);
+        assert_eq!(report.totals.entries, 8);
+        assert_eq!(report.totals.malformed_json, 2);
+        assert_eq!(report.totals.duplicates_dropped, 4);
+        assert_eq!(report.files.len(), 2);
+        assert!(report.projects_dirs[0].exists);
+        assert!(!report.projects_dirs[1].exists);
+        assert!(!report.all_usage_unparseable());
+    }
+
+    #[test]
+    fn test_format_change_is_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-home-me-proj");
+        fs::create_dir_all(&project_dir).unwrap();
+        // Usage data whose shape no longer matches the schema
+        fs::write(
+            project_dir.join("s.jsonl"),
+            r#"{"timestamp":"2025-01-12T14:00:00Z","message":{"usage":{"input_tokens":"lots"}}}"#,
+        ).unwrap();
+
+        let config = Config {
+            claude_paths: vec![dir.path().to_path_buf()],
+            state_dir: Some(dir.path().join("state")),
+            ..Config::default()
+        };
+        let report = run_diagnostics(&config).unwrap();
+
+        assert!(report.all_usage_unparseable());
+        assert_eq!(report.files[0].schema_mismatches.examples[0].line, 1);
+    }
+}
@@ -1,5 +1,10 @@
 use chrono::{DateTime, Duration, Local, Utc};
-use crate::types::SessionBlock;
+use crate::config::{BurnRateThresholds, Config, TimeRemainingThresholds};
+use crate::diagnostics::DiagnosticsReport;
+use crate::filter::FilterConfig;
+use crate::history::HistoricalWindow;
+use crate::plan::{LimitStatus, Plan};
+use crate::types::{ProjectUsage, SessionBlock, SessionUsage};
 use std::path::{Path, PathBuf};
 use std::env;
 
@@ -14,6 +19,64 @@ pub mod colors {
     pub const RESET: &str = "\x1B[0m";
 }
 
+/// Settings that change what the window display shows
+#[derive(Debug, Clone, Default)]
+pub struct DisplayOptions {
+    /// Plan to track usage against, if any
+    pub plan: Option<Plan>,
+    /// When the burn rate changes color
+    pub burn_rate: BurnRateThresholds,
+    /// When the time remaining changes color
+    pub time_remaining: TimeRemainingThresholds,
+    /// Project filter in effect, shown under the header
+    pub filter: FilterConfig,
+    /// List each project's sessions under it
+    pub show_sessions: bool,
+}
+
+impl DisplayOptions {
+    /// Display settings taken from the resolved config
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            plan: config.plan,
+            burn_rate: config.burn_rate,
+            time_remaining: config.time_remaining,
+            filter: config.filter.clone(),
+            show_sessions: false,
+        }
+    }
+}
+
+/// One-line description of an active project filter, or `None` if there isn't one
+fn describe_filter(filter: &FilterConfig) -> Option<String> {
+    let mut parts = Vec::new();
+    if !filter.include.is_empty() {
+        parts.push(format!("projects {}", filter.include.join(", ")));
+    }
+    if !filter.exclude.is_empty() {
+        parts.push(format!("excluding {}", filter.exclude.join(", ")));
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    if filter.account_window {
+        parts.push("account-wide window".to_string());
+    }
+    Some(format!("Filtered: {}", parts.join("; ")))
+}
+
+/// Print the section header, separator and any active filter
+fn print_header(title: &str, options: &DisplayOptions) {
+    println!("{}", colorize(title, colors::CYAN));
+    let terminal_width = get_terminal_width() as usize;
+    // Cap at 80 chars to avoid overly long lines
+    println!("{}", colorize(&"─".repeat(terminal_width.min(80)), colors::DIM));
+    if let Some(description) = describe_filter(&options.filter) {
+        println!("{}", colorize(&description, colors::DIM));
+    }
+    println!();
+}
+
 /// Check if the terminal supports colors.
 fn colors_enabled() -> bool {
     if env::var("NO_COLOR").is_ok() {
@@ -75,6 +138,7 @@ pub fn get_terminal_width() -> u16 {
 /// 
 /// # Examples
 /// ```
+/// # use clauditor::display::clean_project_paths;
 /// let paths = vec![
 ///     "/Users/phaedrus/Development/foo".to_string(),
 ///     "/Users/phaedrus/Development/bar".to_string(),
@@ -220,52 +284,68 @@ fn find_common_prefix(paths: &[PathBuf]) -> Option<PathBuf> {
     }
 }
 
+/// Show a full path with the home directory abbreviated to `~`
+fn tilde_path(path: &Path) -> String {
+    let home = env::var("HOME").ok().map(PathBuf::from);
+    match home.as_deref().and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) => Path::new("~").join(rest).display().to_string(),
+        None => path.display().to_string(),
+    }
+}
+
 /// Clean a single path
 #[allow(dead_code)]
 fn clean_single_path(path: &Path, home_dir: Option<&PathBuf>) -> String {
-    // Just return the last component (project name) for single paths
+    // The home directory itself is always shown as ~
+    if let Some(home) = home_dir {
+        if path == home {
+            return "~".to_string();
+        }
+    }
+    
+    // Otherwise just return the last component (project name) for single paths
     path.file_name()
         .and_then(|n| n.to_str())
         .map(|s| s.to_string())
-        .unwrap_or_else(|| {
-            // Only if there's no filename (e.g., path is "/"), check for home
-            if let Some(home) = home_dir {
-                if path == home {
-                    return "~".to_string();
-                }
-            }
-            path.display().to_string()
-        })
+        .unwrap_or_else(|| path.display().to_string())
 }
 
 
 
+/// Format a duration as "Xh Ym" or "Xm" for durations under an hour, without color
+pub fn format_duration_plain(duration: Duration) -> String {
+    let total_minutes = duration.num_minutes().max(0);
+    
+    let hours = total_minutes / 60;
+    let minutes = total_minutes % 60;
+    
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
 /// Format a duration as "Xh Ym" or "Xm" for durations under an hour with color coding
+#[allow(dead_code)]
 pub fn format_duration(duration: Duration) -> String {
+    format_duration_with(duration, &TimeRemainingThresholds::default())
+}
+
+/// Format a duration with color coding at the given thresholds
+pub fn format_duration_with(duration: Duration, thresholds: &TimeRemainingThresholds) -> String {
     let total_minutes = duration.num_minutes();
     
     if total_minutes <= 0 {
         return "0m".to_string();
     }
     
-    let hours = total_minutes / 60;
-    let minutes = total_minutes % 60;
-    
-    let time_str = if hours > 0 {
-        format!("{}h {}m", hours, minutes)
-    } else {
-        format!("{}m", minutes)
-    };
+    let time_str = format_duration_plain(duration);
     
     // Apply color coding based on time remaining
-    if total_minutes <= 30 {
-        colorize(&time_str, colors::RED)
-    } else if total_minutes <= 60 {
-        colorize(&time_str, colors::YELLOW)
-    } else if total_minutes > 120 {
-        colorize(&time_str, colors::GREEN)
-    } else {
-        time_str
+    match time_remaining_tone(total_minutes, thresholds) {
+        Some(tone) => colorize(&time_str, tone.ansi()),
+        None => time_str,
     }
 }
 
@@ -273,33 +353,125 @@ pub fn format_duration(duration: Duration) -> String {
 pub fn format_number(num: u64) -> String {
     let num_str = num.to_string();
     let mut result = String::new();
-    let mut count = 0;
     
-    for ch in num_str.chars().rev() {
+    for (count, ch) in num_str.chars().rev().enumerate() {
         if count > 0 && count % 3 == 0 {
             result.push(',');
         }
         result.push(ch);
-        count += 1;
     }
     
     result.chars().rev().collect()
 }
 
+/// Format a number in at most four characters (e.g., 45300 -> "45k", 1234567 -> "1.2M")
+pub fn format_number_compact(num: u64) -> String {
+    if num < 1_000 {
+        return format_number(num);
+    }
+
+    let mut value = num as f64;
+    let mut suffix = "";
+    for next in ["k", "M", "B"] {
+        // Move up a unit before rounding would print "1000k"
+        if value < 999.5 {
+            break;
+        }
+        value /= 1_000.0;
+        suffix = next;
+    }
+
+    if value < 9.95 {
+        format!("{:.1}{}", value, suffix).replace(".0", "")
+    } else {
+        format!("{:.0}{}", value, suffix)
+    }
+}
+
+/// Format a duration without spaces (e.g., "2h14m"), for status lines
+pub fn format_duration_compact(duration: Duration) -> String {
+    format_duration_plain(duration).replace(' ', "")
+}
+
+/// Format a dollar amount with cents and comma separators (e.g., 1234.5 -> "$1,234.50")
+pub fn format_cost(cost: f64) -> String {
+    let cents = (cost.max(0.0) * 100.0).round() as u64;
+    format!("${}.{:02}", format_number(cents / 100), cents % 100)
+}
+
 /// Format burn rate with color coding based on value
+#[allow(dead_code)]
 pub fn format_burn_rate(burn_rate: f64) -> String {
+    format_burn_rate_with(burn_rate, &BurnRateThresholds::default())
+}
+
+/// Format burn rate with color coding at the given thresholds
+pub fn format_burn_rate_with(burn_rate: f64, thresholds: &BurnRateThresholds) -> String {
     let rate_str = format!("{} tokens/min", format_number(burn_rate as u64));
     
-    if burn_rate > 1_000_000.0 {
-        colorize(&rate_str, colors::RED)
-    } else if burn_rate > 500_000.0 {
-        colorize(&rate_str, colors::ORANGE)
-    } else if burn_rate > 100_000.0 {
-        colorize(&rate_str, colors::YELLOW)
-    } else if burn_rate < 50_000.0 {
-        colorize(&rate_str, colors::GREEN)
+    match burn_rate_tone(burn_rate, thresholds) {
+        Some(tone) => colorize(&rate_str, tone.ansi()),
+        None => rate_str,
+    }
+}
+
+/// Color a value is shown in, depending on how it compares to its thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Green,
+    Yellow,
+    Orange,
+    Red,
+}
+
+impl Tone {
+    /// ANSI escape code for the color
+    pub fn ansi(self) -> &'static str {
+        match self {
+            Tone::Green => colors::GREEN,
+            Tone::Yellow => colors::YELLOW,
+            Tone::Orange => colors::ORANGE,
+            Tone::Red => colors::RED,
+        }
+    }
+}
+
+/// Tone for a burn rate in tokens/min, or `None` for the uncolored middle band
+pub fn burn_rate_tone(burn_rate: f64, thresholds: &BurnRateThresholds) -> Option<Tone> {
+    if burn_rate > thresholds.red_above {
+        Some(Tone::Red)
+    } else if burn_rate > thresholds.orange_above {
+        Some(Tone::Orange)
+    } else if burn_rate > thresholds.yellow_above {
+        Some(Tone::Yellow)
+    } else if burn_rate < thresholds.green_below {
+        Some(Tone::Green)
     } else {
-        rate_str
+        None
+    }
+}
+
+/// Tone for the minutes left in a window, or `None` for the uncolored middle band
+pub fn time_remaining_tone(minutes: i64, thresholds: &TimeRemainingThresholds) -> Option<Tone> {
+    if minutes <= thresholds.red_within {
+        Some(Tone::Red)
+    } else if minutes <= thresholds.yellow_within {
+        Some(Tone::Yellow)
+    } else if minutes > thresholds.green_beyond {
+        Some(Tone::Green)
+    } else {
+        None
+    }
+}
+
+/// Tone for the share of a plan's limit used
+pub fn limit_tone(percent_used: f64) -> Option<Tone> {
+    if percent_used >= 90.0 {
+        Some(Tone::Red)
+    } else if percent_used >= 75.0 {
+        Some(Tone::Yellow)
+    } else {
+        None
     }
 }
 
@@ -309,12 +481,18 @@ pub fn format_time(timestamp: DateTime<Utc>) -> String {
     local_time.format("%-I:%M %p").to_string()
 }
 
+/// Format a timestamp as a short date in local timezone (e.g., "Mon Jan 13")
+pub fn format_date(timestamp: DateTime<Utc>) -> String {
+    let local_time: DateTime<Local> = timestamp.with_timezone(&Local);
+    local_time.format("%a %b %-d").to_string()
+}
+
 /// Extract a meaningful display name from a project path
 /// Handles cases like:
 /// - /Users/name/Development/project -> project
 /// - /Users/name/Development/adminifi-web/feature-a-120 -> adminifi-web/feature-a-120
 /// - Simple names -> unchanged
-fn extract_display_name(project_path: &str) -> String {
+pub(crate) fn extract_display_name(project_path: &str) -> String {
     // If it's not a path, return as-is
     if !project_path.contains('/') {
         return project_path.to_string();
@@ -353,10 +531,10 @@ fn extract_display_name(project_path: &str) -> String {
 }
 
 /// Display the billing window
-pub fn display_window(window: &SessionBlock, now: DateTime<Utc>) {
+pub fn display_window(window: &SessionBlock, options: &DisplayOptions, now: DateTime<Utc>) {
     let time_remaining = window.time_remaining(now);
     let time_remaining_str = if time_remaining > Duration::zero() {
-        format!("ends in {}", format_duration(time_remaining))
+        format!("ends in {}", format_duration_with(time_remaining, &options.time_remaining))
     } else {
         "ended".to_string()
     };
@@ -368,14 +546,23 @@ pub fn display_window(window: &SessionBlock, now: DateTime<Utc>) {
     
     println!("Total: {} tokens ({})",
         format_number(window.token_counts.total()),
-        format_burn_rate(window.burn_rate())
+        format_burn_rate_with(window.burn_rate(), &options.burn_rate)
     );
     
+    println!("Cost: {} ({}/hr)",
+        format_cost(window.cost_usd),
+        format_cost(window.cost_burn_rate())
+    );
+    
+    if let Some(plan) = options.plan {
+        display_limit_status(&LimitStatus::check(window, plan, now), window, now);
+    }
+    
     println!();
     
     // Display projects sorted by token count (highest first)
     let mut projects = window.projects.clone();
-    projects.sort_by(|a, b| b.token_counts.total().cmp(&a.token_counts.total()));
+    projects.sort_by_key(|p| std::cmp::Reverse(p.token_counts.total()));
     
     let terminal_width = get_terminal_width() as usize;
     let total_tokens = window.token_counts.total();
@@ -384,12 +571,7 @@ pub fn display_window(window: &SessionBlock, now: DateTime<Utc>) {
     let project_data: Vec<_> = projects.iter().map(|p| {
         let name = extract_display_name(&p.name);
         let tokens = p.token_counts.total();
-        let percentage = if total_tokens > 0 {
-            (tokens as f64 / total_tokens as f64 * 100.0) as u32
-        } else {
-            0
-        };
-        (name, tokens, percentage)
+        (name, tokens, percent_of(tokens, total_tokens))
     }).collect();
 
     let max_token_len = project_data.iter()
@@ -399,7 +581,7 @@ pub fn display_window(window: &SessionBlock, now: DateTime<Utc>) {
     let percent_col_width = 4; // "100%"
 
     // Second pass: Display the formatted data
-    for (name, tokens, percentage) in &project_data {
+    for (project, (name, tokens, percentage)) in projects.iter().zip(&project_data) {
         let token_display = format!("{} tokens", format_number(*tokens));
         let percentage_display = format!("{}%", percentage);
 
@@ -412,29 +594,306 @@ pub fn display_window(window: &SessionBlock, now: DateTime<Utc>) {
             width_t = max_token_len
         );
 
-        // Calculate how much space the project name can take
-        let max_name_width = terminal_width.saturating_sub(stats_part.len() + 1); // +1 for padding
+        print_aligned_row(name, &stats_part, terminal_width);
+        
+        if options.show_sessions {
+            display_sessions(project, total_tokens, max_token_len, terminal_width);
+        }
+    }
+    
+    println!();
+    
+    display_models(window, terminal_width);
+}
+
+/// Display a project's sessions under it, with the same stats columns as the project rows
+fn display_sessions(project: &ProjectUsage, total_tokens: u64, token_width: usize, terminal_width: usize) {
+    for session in &project.sessions {
+        let tokens = session.token_counts.total();
+        let name = format!(
+            "  {:<8}  {}-{}  {} entries  {}",
+            short_session_id(&session.session_id),
+            format_time(session.first_activity),
+            format_time(session.last_activity),
+            format_number(session.entry_count as u64),
+            format_model_mix(session)
+        );
+        let stats_part = format!(
+            "{:>4}  {:>width_t$}",
+            format!("{}%", percent_of(tokens, total_tokens)),
+            format!("{} tokens", format_number(tokens)),
+            width_t = token_width
+        );
+        print_aligned_row(&name, &stats_part, terminal_width);
+    }
+}
 
-        let truncated_name = if name.len() > max_name_width {
-            format!("{}...", &name[..max_name_width.saturating_sub(3)])
+/// The first 8 characters of a session id, enough to tell conversations apart
+pub(crate) fn short_session_id(session_id: &str) -> String {
+    session_id.chars().take(8).collect()
+}
+
+/// A session's models with their share of its tokens (e.g., "opus-4 90% sonnet-4 10%"),
+/// or just the model name if it used one
+pub(crate) fn format_model_mix(session: &SessionUsage) -> String {
+    if let [model] = session.models.as_slice() {
+        return format_model_name(&model.model);
+    }
+    let total = session.token_counts.total();
+    session.models.iter()
+        .map(|m| format!("{} {}%", format_model_name(&m.model), percent_of(m.token_counts.total(), total)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Display plan usage, with a prominent warning if the limit will be hit before the window resets
+fn display_limit_status(status: &LimitStatus, window: &SessionBlock, now: DateTime<Utc>) {
+    let percent = format!("{:.0}%", status.percent_used);
+    let percent = match limit_tone(status.percent_used) {
+        Some(tone) => colorize(&percent, tone.ansi()),
+        None => percent,
+    };
+    
+    println!("Limit: {} of {} tokens ({} plan, {} remaining)",
+        percent,
+        format_number(status.token_limit),
+        status.plan,
+        format_number(status.tokens_remaining)
+    );
+    
+    if status.is_exhausted() {
+        println!("{}", colorize("⚠ Plan limit reached for this window", colors::RED));
+    } else if let Some(exhaustion) = status.projected_exhaustion.filter(|_| status.exhausts_before_reset) {
+        println!("{}", colorize(
+            &format!("⚠ Projected to hit the plan limit at {} (in {}, {} before the window resets)",
+                format_time(exhaustion),
+                format_duration_plain(exhaustion - now),
+                format_duration_plain(window.end_time - exhaustion)
+            ),
+            colors::RED
+        ));
+    }
+}
+
+/// Display the per-model breakdown, sorted by token count (highest first)
+fn display_models(window: &SessionBlock, terminal_width: usize) {
+    if window.models.is_empty() {
+        return;
+    }
+    
+    let mut models = window.models.clone();
+    models.sort_by_key(|m| std::cmp::Reverse(m.token_counts.total()));
+    
+    let total_tokens = window.token_counts.total();
+    
+    let model_data: Vec<_> = models.iter().map(|m| {
+        let tokens = m.token_counts.total();
+        (
+            format_model_name(&m.model),
+            format!("{}%", percent_of(tokens, total_tokens)),
+            format!("{} tokens", format_number(tokens)),
+            format!("{} entries", format_number(m.entry_count as u64)),
+        )
+    }).collect();
+    
+    let max_token_len = model_data.iter().map(|(_, _, t, _)| t.len()).max().unwrap_or(0);
+    let max_entry_len = model_data.iter().map(|(_, _, _, e)| e.len()).max().unwrap_or(0);
+    
+    println!("{}", colorize("Models", colors::DIM));
+    
+    for (name, percentage, tokens, entries) in &model_data {
+        let stats_part = format!(
+            "{:>4}  {:>width_t$}  {:>width_e$}",
+            percentage,
+            tokens,
+            entries,
+            width_t = max_token_len,
+            width_e = max_entry_len
+        );
+        
+        print_aligned_row(name, &stats_part, terminal_width);
+    }
+    
+    println!();
+}
+
+/// Shorten a model id for display (e.g., "claude-opus-4-20250514" -> "opus-4")
+pub fn format_model_name(model: &str) -> String {
+    let name = model.strip_prefix("claude-").unwrap_or(model);
+    
+    // Drop a trailing -YYYYMMDD release date
+    match name.rsplit_once('-') {
+        Some((base, date)) if date.len() == 8 && date.chars().all(|c| c.is_ascii_digit()) => {
+            base.to_string()
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// Integer percentage of `part` in `total`, or 0 when total is empty
+pub(crate) fn percent_of(part: u64, total: u64) -> u32 {
+    if total > 0 {
+        (part as f64 / total as f64 * 100.0) as u32
+    } else {
+        0
+    }
+}
+
+/// Print a name left-aligned and a stats block right-aligned to the terminal width,
+/// truncating the name with "..." if both don't fit
+fn print_aligned_row(name: &str, stats_part: &str, terminal_width: usize) {
+    // Calculate how much space the name can take
+    let max_name_width = terminal_width.saturating_sub(stats_part.len() + 1); // +1 for padding
+
+    let truncated_name = if name.len() > max_name_width {
+        format!("{}...", &name[..max_name_width.saturating_sub(3)])
+    } else {
+        name.to_string()
+    };
+    
+    let padding_len = terminal_width
+        .saturating_sub(truncated_name.len())
+        .saturating_sub(stats_part.len());
+
+    let padding = " ".repeat(padding_len);
+
+    println!("{}{}{}", truncated_name, padding, stats_part);
+}
+
+/// Display past billing windows, oldest first, followed by a grand total
+pub fn display_history(history: &[HistoricalWindow], options: &DisplayOptions) {
+    print_header("Billing window history", options);
+    
+    if history.is_empty() {
+        println!("No billing windows in this period");
+        return;
+    }
+    
+    for entry in history {
+        let window = &entry.window;
+        let status = if window.is_active {
+            format!(" {}", colorize("(active)", colors::GREEN))
         } else {
-            name.clone()
+            String::new()
         };
         
-        let padding_len = terminal_width
-            .saturating_sub(truncated_name.len())
-            .saturating_sub(stats_part.len());
-
-        let padding = " ".repeat(padding_len);
+        println!("{}  {} - {}{}",
+            format_date(window.start_time),
+            format_time(window.start_time),
+            format_time(window.end_time),
+            status
+        );
+        
+        println!("  {} tokens  {}  {} used  peak {}",
+            format_number(window.token_counts.total()),
+            format_cost(window.cost_usd),
+            format_duration_plain(entry.duration_used()),
+            format_burn_rate_with(entry.peak_burn_rate, &options.burn_rate)
+        );
+        
+        let mut projects = window.projects.clone();
+        projects.sort_by_key(|p| std::cmp::Reverse(p.token_counts.total()));
+        let top_projects: Vec<String> = projects.iter()
+            .take(3)
+            .map(|p| format!("{} {}%",
+                extract_display_name(&p.name),
+                percent_of(p.token_counts.total(), window.token_counts.total())
+            ))
+            .collect();
+        
+        if !top_projects.is_empty() {
+            println!("  {}", colorize(&format!("Top: {}", top_projects.join(", ")), colors::DIM));
+        }
+        println!();
+    }
+    
+    let total_tokens: u64 = history.iter().map(|h| h.window.token_counts.total()).sum();
+    let total_cost: f64 = history.iter().map(|h| h.window.cost_usd).sum();
+    println!("{} {}, {} tokens, {}",
+        history.len(),
+        if history.len() == 1 { "window" } else { "windows" },
+        format_number(total_tokens),
+        format_cost(total_cost)
+    );
+}
 
-        println!("{}{}{}", truncated_name, padding, stats_part);
+/// Display what was found while parsing session files
+pub fn display_diagnostics(report: &DiagnosticsReport) {
+    print_header("Session file diagnostics", &DisplayOptions::default());
+    
+    println!("Scanning files modified in the last {} hours:", report.hours_back);
+    for dir in &report.projects_dirs {
+        let status = if dir.exists {
+            colorize("found", colors::GREEN)
+        } else {
+            colorize("missing", colors::DIM)
+        };
+        println!("  {} ({})", tilde_path(&dir.path), status);
+    }
+    println!();
+    
+    let totals = &report.totals;
+    let problem = |count: usize| {
+        let text = format_number(count as u64);
+        if count > 0 { colorize(&text, colors::RED) } else { text }
+    };
+    println!("Files:             {}", format_number(totals.files as u64));
+    println!("Lines:             {}", format_number(totals.lines as u64));
+    println!("Usage entries:     {}", format_number(totals.entries as u64));
+    println!("Without usage:     {} {}", format_number(totals.no_usage as u64),
+        colorize("(user messages, summaries)", colors::DIM));
+    println!("Duplicates:        {} {}", format_number(totals.duplicates_dropped as u64),
+        colorize("(counted once)", colors::DIM));
+    println!("Malformed JSON:    {}", problem(totals.malformed_json));
+    println!("Schema mismatches: {}", problem(totals.schema_mismatches));
+    println!("I/O errors:        {}", problem(totals.io_errors));
+    println!("Unreadable files:  {}", problem(totals.unreadable_files));
+    
+    for file in &report.unreadable_files {
+        println!();
+        println!("{}", tilde_path(&file.path));
+        println!("  {}", colorize(&file.error, colors::RED));
+    }
+    
+    for file in &report.files {
+        println!();
+        println!("{}", tilde_path(&file.path));
+        for (kind, issues) in [
+            ("malformed JSON", &file.malformed_json),
+            ("schema mismatch", &file.schema_mismatches),
+            ("I/O error", &file.io_errors),
+        ] {
+            for issue in &issues.examples {
+                println!("  line {}: {}: {}", issue.line, kind, issue.message);
+            }
+            let hidden = issues.count - issues.examples.len();
+            if hidden > 0 {
+                println!("  {}", colorize(&format!("... and {} more {} lines", hidden, kind), colors::DIM));
+            }
+        }
     }
     
     println!();
+    if report.all_usage_unparseable() {
+        println!("{}", colorize(
+            "⚠ No usage entries could be parsed. Claude Code's log format may have changed.",
+            colors::RED
+        ));
+    } else if report.has_problems() {
+        println!("{}", colorize("⚠ Some lines could not be read; usage totals may be low.", colors::YELLOW));
+    } else {
+        println!("{}", colorize("No problems found", colors::GREEN));
+    }
 }
 
 /// Display the active billing window
+#[allow(dead_code)]
 pub fn display_active_window(window: Option<&SessionBlock>) {
+    display_active_window_with_options(window, &DisplayOptions::default());
+}
+
+/// Display the active billing window using the given display settings
+pub fn display_active_window_with_options(window: Option<&SessionBlock>, options: &DisplayOptions) {
     let now = Utc::now();
     
     match window {
@@ -442,16 +901,9 @@ pub fn display_active_window(window: Option<&SessionBlock>) {
             println!("No active billing window");
         }
         Some(w) => {
-            // Display header with color
-            println!("{}", colorize("Active billing window", colors::CYAN));
-            
-            // Display separator line
-            let terminal_width = get_terminal_width() as usize;
-            let separator = "─".repeat(terminal_width.min(80)); // Cap at 80 chars to avoid overly long lines
-            println!("{}", colorize(&separator, colors::DIM));
-            println!();
+            print_header("Active billing window", options);
             
-            display_window(w, now);
+            display_window(w, options, now);
         }
     }
 }
@@ -499,11 +951,63 @@ mod tests {
         assert_eq!(format_number(12345), "12,345");
         assert_eq!(format_number(1234567), "1,234,567");
     }
+
+    #[test]
+    fn test_format_number_compact() {
+        assert_eq!(format_number_compact(999), "999");
+        assert_eq!(format_number_compact(1_000), "1k");
+        assert_eq!(format_number_compact(4_540), "4.5k");
+        assert_eq!(format_number_compact(45_300), "45k");
+        assert_eq!(format_number_compact(999_600), "1M");
+        assert_eq!(format_number_compact(1_234_567), "1.2M");
+        assert_eq!(format_number_compact(12_345_678_901), "12B");
+    }
+    
+    #[test]
+    fn test_format_model_name() {
+        assert_eq!(format_model_name("claude-opus-4-20250514"), "opus-4");
+        assert_eq!(format_model_name("claude-3-5-haiku-20241022"), "3-5-haiku");
+        assert_eq!(format_model_name("claude-opus-4-1"), "opus-4-1");
+        assert_eq!(format_model_name("<synthetic>"), "<synthetic>");
+    }
+    
+    #[test]
+    fn test_describe_filter() {
+        assert_eq!(describe_filter(&FilterConfig::default()), None);
+        
+        let filter = FilterConfig {
+            include: vec!["*acme*".to_string()],
+            exclude: vec!["*/scratch".to_string()],
+            account_window: true,
+        };
+        assert_eq!(
+            describe_filter(&filter).unwrap(),
+            "Filtered: projects *acme*; excluding */scratch; account-wide window"
+        );
+    }
+    
+    #[test]
+    fn test_format_duration_plain() {
+        assert_eq!(format_duration_plain(Duration::minutes(-5)), "0m");
+        assert_eq!(format_duration_plain(Duration::minutes(30)), "30m");
+        assert_eq!(format_duration_plain(Duration::minutes(192)), "3h 12m");
+        assert_eq!(format_duration_compact(Duration::minutes(134)), "2h14m");
+    }
+    
+    #[test]
+    fn test_format_cost() {
+        assert_eq!(format_cost(0.0), "$0.00");
+        assert_eq!(format_cost(0.005), "$0.01");
+        assert_eq!(format_cost(3.4), "$3.40");
+        assert_eq!(format_cost(1234.567), "$1,234.57");
+    }
     
     #[test]
     fn test_format_burn_rate() {
+        // Low rate (green)
+        assert_eq!(format_burn_rate(100.0), colorize("100 tokens/min", colors::GREEN));
+        
         // Normal rate (no color)
-        assert_eq!(format_burn_rate(100.0), "100 tokens/min");
         assert_eq!(format_burn_rate(50000.0), "50,000 tokens/min");
         assert_eq!(format_burn_rate(100_000.0), "100,000 tokens/min");
         
@@ -517,6 +1021,20 @@ mod tests {
         assert_eq!(format_burn_rate(1_000_001.0), colorize("1,000,001 tokens/min", colors::RED));
     }
     
+    #[test]
+    fn test_format_burn_rate_with_custom_thresholds() {
+        let thresholds = BurnRateThresholds {
+            green_below: 10.0,
+            yellow_above: 20.0,
+            orange_above: 30.0,
+            red_above: 40.0,
+        };
+        
+        assert_eq!(format_burn_rate_with(5.0, &thresholds), colorize("5 tokens/min", colors::GREEN));
+        assert_eq!(format_burn_rate_with(15.0, &thresholds), "15 tokens/min");
+        assert_eq!(format_burn_rate_with(100.0, &thresholds), colorize("100 tokens/min", colors::RED));
+    }
+    
     #[test]
     fn test_format_time() {
         let time = DateTime::parse_from_rfc3339("2024-01-15T14:00:00Z")
@@ -646,7 +1164,6 @@ mod tests {
     #[test]
     fn test_display_window_with_number() {
         use crate::types::{SessionBlock, ProjectUsage};
-        use std::io::{self, Write};
         
         // Create a test window
         let now = Utc::now();
@@ -660,9 +1177,11 @@ mod tests {
                     input_tokens: 1000,
                     output_tokens: 500,
                     cache_creation_tokens: 0,
-                    cache_read_input_tokens: 0,
+                    cache_read_tokens: 0,
                 },
+                cost_usd: 0.0,
                 entry_count: 10,
+                sessions: Vec::new(),
             }],
             token_counts: TokenCounts {
                 input_tokens: 1000,
@@ -670,14 +1189,17 @@ mod tests {
                 cache_creation_tokens: 0,
                 cache_read_tokens: 0,
             },
+            cost_usd: 0.0,
+            models: vec![],
             is_active: true,
         };
         
         // Test window display
         println!("=== Window display ===");
-        display_window(&window, now);
-        
         // Visual verification - test passes if it compiles and runs
-        assert!(true);
+        display_window(&window, &DisplayOptions::default(), now);
+        
+        let options = DisplayOptions { plan: Some(Plan::Pro), ..DisplayOptions::default() };
+        display_window(&window, &options, now);
     }
 }
\ No newline at end of file
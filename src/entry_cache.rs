@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::parser::{parse_file_from_position_with_diagnostics, ParseErrors};
+use crate::position_tracker::{fnv1a, FilePosition};
+use crate::scanner::{extract_project_name, extract_session_id};
+use crate::state::{legacy_file, read_state, write_state, StateLock};
+use crate::types::{SessionFile, UsageEntry};
+
+/// Directory of per-file records in the state directory
+const ENTRIES_DIR: &str = "entries";
+
+/// Position cache written to the shared temp directory by older versions
+const LEGACY_POSITIONS_FILE: &str = "clauditor_positions.json";
+
+/// Entries already parsed from one session file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFile {
+    /// The session file, since record names are only a hash of it
+    path: String,
+    /// How far the file has been read, and what it looked like at the time
+    position: FilePosition,
+    /// Lines up to that offset, so later issues are numbered from the start of the file
+    #[serde(default)]
+    lines: usize,
+    entries: Vec<UsageEntry>,
+    /// Lines up to that offset that couldn't be parsed
+    #[serde(default)]
+    errors: ParseErrors,
+}
+
+/// Parsed entries for every session file, persisted between runs
+///
+/// Extends the position tracker idea: besides the offset, each file's entries
+/// up to that offset are kept, so a run only parses bytes appended since the
+/// last one. A file that was truncated or replaced is parsed again from the
+/// start.
+///
+/// Each session file gets its own record in the cache directory, read when the
+/// file is first loaded and written only when it changed. Records are replaced
+/// atomically, so concurrent runs need no lock.
+#[derive(Debug)]
+pub struct EntryCache {
+    files: HashMap<String, CachedFile>,
+    cache_dir: Option<PathBuf>,
+    /// Files whose record changed since it was last written
+    dirty: HashSet<String>,
+}
+
+impl EntryCache {
+    /// A cache kept in `cache_dir`, one record per session file
+    pub fn open(cache_dir: PathBuf) -> Self {
+        Self {
+            files: HashMap::new(),
+            cache_dir: Some(cache_dir),
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// A cache that is never read from or written to disk
+    pub fn in_memory() -> Self {
+        Self {
+            files: HashMap::new(),
+            cache_dir: None,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// A cache in the configured state directory, or in memory without one
+    pub fn from_config(config: &Config) -> Self {
+        match config.state_file(ENTRIES_DIR) {
+            Some(cache_dir) => Self::open(cache_dir),
+            None => Self::in_memory(),
+        }
+    }
+
+    /// Move the position cache older versions kept in the shared temp directory here
+    ///
+    /// Each offset becomes a record without a fingerprint, so the file is still
+    /// parsed in full once: the entries before the offset weren't kept. Files
+    /// that already have a record are left alone. The old file is removed
+    /// afterwards, which stops it exposing project paths to other users.
+    pub fn migrate_legacy_positions(config: &Config) -> Result<()> {
+        match legacy_file(LEGACY_POSITIONS_FILE) {
+            Some(legacy) => Self::from_config(config).migrate_positions(&legacy),
+            None => Ok(()),
+        }
+    }
+
+    fn migrate_positions(&self, legacy: &Path) -> Result<()> {
+        let Some(cache_dir) = &self.cache_dir else {
+            return remove_if_present(legacy);
+        };
+        let _lock = StateLock::acquire(&cache_dir.join(LEGACY_POSITIONS_FILE))?;
+
+        // Another instance may have migrated it while this one waited
+        let contents = match fs::read(legacy) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        // A cache that can't be parsed has nothing worth keeping
+        let positions: HashMap<String, u64> = serde_json::from_slice(&contents).unwrap_or_default();
+        for (path, offset) in positions {
+            if self.read_record(&path).is_some() {
+                continue;
+            }
+            let record = CachedFile {
+                path: path.clone(),
+                position: FilePosition { offset, fingerprint: None },
+                lines: 0,
+                entries: Vec::new(),
+                errors: ParseErrors::default(),
+            };
+            write_state(&record_path(cache_dir, &path), &record)?;
+        }
+        remove_if_present(legacy)
+    }
+
+    /// Read a session file, parsing only what was appended since it was cached
+    pub fn read_session_file(&mut self, path: &Path) -> Result<SessionFile> {
+        let key = path.to_string_lossy().to_string();
+        let cached = self.files.remove(&key).or_else(|| self.read_record(&key));
+
+        let (mut entries, mut errors, start, start_line) = match cached {
+            Some(cached) => match cached.position.resume_offset(path) {
+                0 => (Vec::new(), ParseErrors::default(), 0, 0),
+                offset => (cached.entries, cached.errors, offset, cached.lines),
+            },
+            None => (Vec::new(), ParseErrors::default(), 0, 0),
+        };
+
+        let (new_entries, offset, diagnostics) =
+            parse_file_from_position_with_diagnostics(path, start, start_line)?;
+        if start == 0 || offset != start {
+            self.dirty.insert(key.clone());
+        }
+        entries.extend(new_entries);
+        errors.add(&ParseErrors::from(&diagnostics));
+
+        let session = SessionFile {
+            path: key.clone(),
+            project: extract_project_name(path),
+            session_id: extract_session_id(path),
+            last_read_position: offset,
+            entries: entries.clone(),
+        };
+        self.files.insert(key.clone(), CachedFile {
+            path: key,
+            position: FilePosition::capture(path, offset),
+            lines: diagnostics.lines,
+            entries,
+            errors,
+        });
+
+        Ok(session)
+    }
+
+    /// Lines of a cached file that couldn't be parsed, as of its last read
+    pub fn parse_errors(&self, path: &Path) -> ParseErrors {
+        self.files.get(path.to_string_lossy().as_ref())
+            .map(|cached| cached.errors)
+            .unwrap_or_default()
+    }
+
+    /// Drop every file but `files`, e.g. ones removed or now older than the scan window
+    pub fn prune(&mut self, files: &[PathBuf]) {
+        let keep: HashSet<String> = files.iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        self.files.retain(|path, _| keep.contains(path));
+        self.dirty.retain(|path| keep.contains(path));
+
+        let Some(cache_dir) = &self.cache_dir else {
+            return;
+        };
+        let Ok(records) = fs::read_dir(cache_dir) else {
+            return;
+        };
+        let keep_records: HashSet<PathBuf> = keep.iter()
+            .map(|path| record_path(cache_dir, path))
+            .collect();
+        for record in records.flatten() {
+            let path = record.path();
+            if path.extension().is_some_and(|ext| ext == "json") && !keep_records.contains(&path) {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+
+    /// Write the records of files that changed since they were last written
+    ///
+    /// A record that can't be written stays dirty, so the next save retries it.
+    pub fn save(&mut self) -> Result<()> {
+        let Some(cache_dir) = &self.cache_dir else {
+            return Ok(());
+        };
+
+        let mut result = Ok(());
+        self.dirty.retain(|path| {
+            let Some(cached) = self.files.get(path) else {
+                return false;
+            };
+            match write_state(&record_path(cache_dir, path), cached) {
+                Ok(()) => false,
+                Err(e) => {
+                    result = Err(e);
+                    true
+                }
+            }
+        });
+        result
+    }
+
+    /// The stored record for a session file, if there is a usable one
+    fn read_record(&self, path: &str) -> Option<CachedFile> {
+        let cache_dir = self.cache_dir.as_ref()?;
+        read_state::<CachedFile>(&record_path(cache_dir, path))
+            .ok()
+            .flatten()
+            .filter(|cached| cached.path == path)
+    }
+}
+
+fn remove_if_present(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Where the record for a session file is kept
+fn record_path(cache_dir: &Path, path: &str) -> PathBuf {
+    cache_dir.join(format!("{:016x}.json", fnv1a(path.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests;
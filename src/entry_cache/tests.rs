@@ -0,0 +1,177 @@
+use super::*;
+use std::io::Write;
+use tempfile::TempDir;
+use crate::test_support::usage_line;
+
+fn ids(session: &SessionFile) -> Vec<&str> {
+    session.entries.iter().map(|e| e.message.id.as_str()).collect()
+}
+
+#[test]
+fn test_only_new_bytes_are_parsed() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("session.jsonl");
+    let with_cwd = usage_line("msg_1").replacen('{', r#"{"cwd":"/home/me","#, 1);
+    fs::write(&path, format!("{}\n", with_cwd)).unwrap();
+
+    let cache_dir = dir.path().join("entries");
+    let mut cache = EntryCache::open(cache_dir.clone());
+    assert_eq!(ids(&cache.read_session_file(&path).unwrap()), ["msg_1"]);
+    cache.save().unwrap();
+
+    let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    writeln!(file, "{}", usage_line("msg_2")).unwrap();
+
+    // A fresh run picks up the cached entry and parses only the new line
+    let mut cache = EntryCache::open(cache_dir);
+    let cached = cache.read_record(&path.to_string_lossy()).unwrap();
+    assert_eq!(cached.entries.len(), 1);
+
+    let session = cache.read_session_file(&path).unwrap();
+    assert_eq!(ids(&session), ["msg_1", "msg_2"]);
+    assert_eq!(session.last_read_position, fs::metadata(&path).unwrap().len());
+    // Fields clauditor doesn't model survive the round trip
+    assert_eq!(session.entries[0].unknown.entry["cwd"], "/home/me");
+}
+
+#[test]
+fn test_unchanged_files_are_not_rewritten() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("session.jsonl");
+    fs::write(&path, format!("{}\n", usage_line("msg_1"))).unwrap();
+
+    let cache_dir = dir.path().join("entries");
+    let mut cache = EntryCache::open(cache_dir.clone());
+    cache.read_session_file(&path).unwrap();
+    cache.save().unwrap();
+    let record = record_path(&cache_dir, &path.to_string_lossy());
+    assert!(record.exists());
+
+    fs::remove_file(&record).unwrap();
+    cache.read_session_file(&path).unwrap();
+    cache.save().unwrap();
+    assert!(!record.exists());
+}
+
+#[test]
+fn test_replaced_file_is_parsed_again() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("session.jsonl");
+    fs::write(&path, format!("{}\n", usage_line("msg_1"))).unwrap();
+
+    let mut cache = EntryCache::in_memory();
+    cache.read_session_file(&path).unwrap();
+
+    fs::write(&path, format!("{}\n{}\n", usage_line("msg_9"), usage_line("msg_2"))).unwrap();
+    assert_eq!(ids(&cache.read_session_file(&path).unwrap()), ["msg_9", "msg_2"]);
+}
+
+#[test]
+fn test_parse_errors_accumulate_until_rewrite() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("session.jsonl");
+    fs::write(&path, format!("{{oops\n{}\n", usage_line("msg_1"))).unwrap();
+
+    let mut cache = EntryCache::in_memory();
+    cache.read_session_file(&path).unwrap();
+    let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    writeln!(file, "{{oops again").unwrap();
+    cache.read_session_file(&path).unwrap();
+    assert_eq!(cache.parse_errors(&path).malformed_json, 2);
+
+    fs::write(&path, format!("{}\n", usage_line("msg_2"))).unwrap();
+    cache.read_session_file(&path).unwrap();
+    assert_eq!(cache.parse_errors(&path), ParseErrors::default());
+}
+
+#[test]
+fn test_unreadable_record_is_replaced() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("session.jsonl");
+    fs::write(&path, format!("{}\n", usage_line("msg_1"))).unwrap();
+
+    let cache_dir = dir.path().join("entries");
+    let record = record_path(&cache_dir, &path.to_string_lossy());
+    fs::create_dir_all(&cache_dir).unwrap();
+    fs::write(&record, "{not json").unwrap();
+
+    let mut cache = EntryCache::open(cache_dir.clone());
+    assert_eq!(ids(&cache.read_session_file(&path).unwrap()), ["msg_1"]);
+    cache.save().unwrap();
+    assert!(EntryCache::open(cache_dir).read_record(&path.to_string_lossy()).is_some());
+}
+
+#[test]
+fn test_prune_drops_files_outside_the_scan() {
+    let dir = TempDir::new().unwrap();
+    let kept = dir.path().join("kept.jsonl");
+    let old = dir.path().join("old.jsonl");
+    fs::write(&kept, format!("{}\n", usage_line("msg_1"))).unwrap();
+    fs::write(&old, format!("{}\n", usage_line("msg_2"))).unwrap();
+
+    let cache_dir = dir.path().join("entries");
+    let mut cache = EntryCache::open(cache_dir.clone());
+    cache.read_session_file(&kept).unwrap();
+    cache.read_session_file(&old).unwrap();
+    cache.save().unwrap();
+
+    cache.prune(std::slice::from_ref(&kept));
+    assert_eq!(cache.files.len(), 1);
+    assert!(record_path(&cache_dir, &kept.to_string_lossy()).exists());
+    assert!(!record_path(&cache_dir, &old.to_string_lossy()).exists());
+}
+
+#[test]
+fn test_failed_save_is_retried() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("session.jsonl");
+    fs::write(&path, format!("{}\n", usage_line("msg_1"))).unwrap();
+
+    // A file where the cache directory should be
+    let cache_dir = dir.path().join("entries");
+    fs::write(&cache_dir, "").unwrap();
+    let mut cache = EntryCache::open(cache_dir.clone());
+    cache.read_session_file(&path).unwrap();
+    assert!(cache.save().is_err());
+
+    fs::remove_file(&cache_dir).unwrap();
+    cache.save().unwrap();
+    assert!(record_path(&cache_dir, &path.to_string_lossy()).exists());
+}
+
+#[test]
+fn test_legacy_positions_are_migrated_once() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("session.jsonl");
+    let cached = dir.path().join("cached.jsonl");
+    fs::write(&path, format!("{}\n", usage_line("msg_1"))).unwrap();
+    fs::write(&cached, format!("{}\n", usage_line("msg_2"))).unwrap();
+
+    let cache_dir = dir.path().join("entries");
+    let mut cache = EntryCache::open(cache_dir.clone());
+    cache.read_session_file(&cached).unwrap();
+    cache.save().unwrap();
+
+    let legacy = dir.path().join(LEGACY_POSITIONS_FILE);
+    let positions = HashMap::from([
+        (path.to_string_lossy().to_string(), 10u64),
+        (cached.to_string_lossy().to_string(), 3u64),
+    ]);
+    fs::write(&legacy, serde_json::to_string(&positions).unwrap()).unwrap();
+
+    let cache = EntryCache::open(cache_dir.clone());
+    cache.migrate_positions(&legacy).unwrap();
+    assert!(!legacy.exists());
+
+    // The offset carries over, but without a fingerprint the file is read in full
+    let migrated = cache.read_record(&path.to_string_lossy()).unwrap();
+    assert_eq!(migrated.position, FilePosition { offset: 10, fingerprint: None });
+    let existing = cache.read_record(&cached.to_string_lossy()).unwrap();
+    assert_eq!(existing.entries.len(), 1);
+
+    let mut cache = EntryCache::open(cache_dir);
+    assert_eq!(ids(&cache.read_session_file(&path).unwrap()), ["msg_1"]);
+
+    // Nothing left to migrate
+    cache.migrate_positions(&legacy).unwrap();
+}
@@ -0,0 +1,242 @@
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+
+use ratatui::crossterm::event::KeyEvent;
+
+use crate::watcher::{ChangeSet, FileEvent};
+
+/// How long to wait after a file change for more changes before reloading
+///
+/// Claude Code writes several lines per response, so this coalesces a burst
+/// into one reload while keeping updates well within 200 ms.
+pub const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Something the watch loop reacts to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// A session file was created, written, removed or renamed
+    FileChanged(FileEvent),
+    /// The terminal was resized
+    Resize,
+    /// A key was pressed in the dashboard
+    Key(KeyEvent),
+    /// The daemon pushed a new window
+    DaemonUpdate,
+    /// The daemon this watch was subscribed to stopped
+    DaemonGone,
+    /// Ctrl+C or SIGTERM
+    Shutdown,
+}
+
+/// What the watch loop should do next
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Re-read these session files and redraw
+    Reload(ChangeSet),
+    /// Redraw the current window, e.g. to update the countdown
+    Redraw,
+    /// Let the dashboard handle a key press
+    Key(KeyEvent),
+    /// Stop relying on the daemon and watch session files directly
+    DaemonGone,
+    Shutdown,
+}
+
+/// Turns watch events and the passage of time into reloads and redraws
+///
+/// File changes are debounced, and a periodic tick redraws the countdown even
+/// when nothing is written. Between deadlines the loop blocks on the event
+/// channel, so an idle watch uses no CPU.
+#[derive(Debug)]
+pub struct Scheduler {
+    debounce: Duration,
+    tick: Duration,
+    next_tick: Instant,
+    reload_at: Option<Instant>,
+    /// Changes seen since the last reload
+    pending: ChangeSet,
+}
+
+impl Scheduler {
+    /// Create a scheduler whose first tick is at `first_tick`
+    pub fn new(debounce: Duration, tick: Duration, first_tick: Instant) -> Self {
+        Self {
+            debounce,
+            tick,
+            next_tick: first_tick,
+            reload_at: None,
+            pending: ChangeSet::default(),
+        }
+    }
+
+    /// Handle an event, returning an action if it should happen right away
+    pub fn on_event(&mut self, event: WatchEvent, now: Instant) -> Option<Action> {
+        match event {
+            WatchEvent::FileChanged(file_event) => {
+                // The first change of a burst starts the debounce window
+                self.pending.add(file_event);
+                if !self.pending.is_empty() {
+                    self.reload_at.get_or_insert(now + self.debounce);
+                }
+                None
+            }
+            WatchEvent::Resize | WatchEvent::DaemonUpdate => Some(Action::Redraw),
+            WatchEvent::DaemonGone => Some(Action::DaemonGone),
+            WatchEvent::Key(key) => Some(Action::Key(key)),
+            WatchEvent::Shutdown => Some(Action::Shutdown),
+        }
+    }
+
+    /// Time until the next deadline
+    pub fn timeout(&self, now: Instant) -> Duration {
+        let deadline = match self.reload_at {
+            Some(reload_at) => reload_at.min(self.next_tick),
+            None => self.next_tick,
+        };
+        deadline.saturating_duration_since(now)
+    }
+
+    /// The action whose deadline has passed, if any
+    ///
+    /// A reload also redraws, so it resets the tick.
+    pub fn due(&mut self, now: Instant) -> Option<Action> {
+        if self.reload_at.is_some_and(|reload_at| reload_at <= now) {
+            self.reload_at = None;
+            self.next_tick = now + self.tick;
+            return Some(Action::Reload(std::mem::take(&mut self.pending)));
+        }
+        if self.next_tick <= now {
+            self.next_tick = now + self.tick;
+            return Some(Action::Redraw);
+        }
+        None
+    }
+
+    /// Block until the next action
+    pub fn next_action(&mut self, events: &Receiver<WatchEvent>) -> Action {
+        loop {
+            let now = Instant::now();
+            if let Some(action) = self.due(now) {
+                return action;
+            }
+
+            match events.recv_timeout(self.timeout(now)) {
+                Ok(event) => {
+                    if let Some(action) = self.on_event(event, Instant::now()) {
+                        return action;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Action::Shutdown,
+            }
+        }
+    }
+}
+
+/// Forward Ctrl+C, SIGTERM and terminal resizes to the watch loop
+pub fn forward_signals(events: Sender<WatchEvent>) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use signal_hook::consts::{SIGTERM, SIGWINCH};
+        use signal_hook::iterator::Signals;
+
+        let mut signals = Signals::new([SIGTERM, SIGWINCH])
+            .context("Error setting signal handlers")?;
+        let events = events.clone();
+        std::thread::spawn(move || {
+            for signal in signals.forever() {
+                let event = if signal == SIGWINCH { WatchEvent::Resize } else { WatchEvent::Shutdown };
+                if events.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    ctrlc::set_handler(move || {
+        let _ = events.send(WatchEvent::Shutdown);
+    }).context("Error setting Ctrl-C handler")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    const TICK: Duration = Duration::from_secs(60);
+
+    fn modified(path: &str) -> WatchEvent {
+        WatchEvent::FileChanged(FileEvent::Modified(path.into()))
+    }
+
+    #[test]
+    fn test_file_changes_are_debounced() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(DEBOUNCE, TICK, start + TICK);
+
+        assert_eq!(scheduler.on_event(modified("/p/a.jsonl"), start), None);
+        assert_eq!(scheduler.timeout(start), DEBOUNCE);
+
+        // More changes in the window don't push the reload back
+        let later = start + Duration::from_millis(60);
+        scheduler.on_event(modified("/p/a.jsonl"), later);
+        scheduler.on_event(modified("/p/b.jsonl"), later);
+        assert_eq!(scheduler.due(later), None);
+
+        let mut expected = ChangeSet::default();
+        expected.add(FileEvent::Modified("/p/a.jsonl".into()));
+        expected.add(FileEvent::Modified("/p/b.jsonl".into()));
+        assert_eq!(scheduler.due(start + DEBOUNCE), Some(Action::Reload(expected)));
+        assert_eq!(scheduler.due(start + DEBOUNCE), None);
+    }
+
+    #[test]
+    fn test_tick_redraws_when_idle() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(DEBOUNCE, TICK, start + Duration::from_secs(5));
+
+        assert_eq!(scheduler.timeout(start), Duration::from_secs(5));
+        assert_eq!(scheduler.due(start + Duration::from_secs(5)), Some(Action::Redraw));
+        assert_eq!(scheduler.timeout(start + Duration::from_secs(5)), TICK);
+    }
+
+    #[test]
+    fn test_non_session_files_are_ignored() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(DEBOUNCE, TICK, start + TICK);
+
+        scheduler.on_event(modified("/p/notes.txt"), start);
+        assert_eq!(scheduler.timeout(start), TICK);
+    }
+
+    #[test]
+    fn test_resize_and_shutdown_are_immediate() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(DEBOUNCE, TICK, start + TICK);
+
+        assert_eq!(scheduler.on_event(WatchEvent::Resize, start), Some(Action::Redraw));
+        assert_eq!(scheduler.on_event(WatchEvent::Shutdown, start), Some(Action::Shutdown));
+    }
+
+    #[test]
+    fn test_next_action_blocks_on_channel() {
+        let (tx, rx) = channel();
+        let mut scheduler = Scheduler::new(DEBOUNCE, TICK, Instant::now() + TICK);
+
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            tx.send(modified("/p/a.jsonl")).unwrap();
+            tx.send(modified("/p/a.jsonl")).unwrap();
+            tx
+        });
+
+        let start = Instant::now();
+        assert!(matches!(scheduler.next_action(&rx), Action::Reload(_)));
+        assert!(start.elapsed() < Duration::from_millis(200), "took {:?}", start.elapsed());
+
+        // All senders gone
+        drop(writer.join().unwrap());
+        assert_eq!(scheduler.next_action(&rx), Action::Shutdown);
+    }
+}
@@ -0,0 +1,276 @@
+use std::io::Write;
+
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::coordinator::{dedup_entries, tag_with_projects};
+use crate::filter::ProjectFilter;
+use crate::pricing::entry_cost;
+use crate::scanner::SessionScanner;
+use crate::types::{EntryWithProject, UnknownFields};
+
+/// File format for exported entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// CSV column names, in `ExportRecord` field order (`extra` is NDJSON only)
+const CSV_HEADER: &str = "timestamp,project,session_id,model,input_tokens,output_tokens,\
+cache_creation_tokens,cache_read_tokens,cost_usd,message_id,request_id";
+
+/// One exported usage entry
+#[derive(Debug, Serialize)]
+pub struct ExportRecord<'a> {
+    pub timestamp: DateTime<Utc>,
+    pub project: &'a str,
+    pub session_id: &'a str,
+    pub model: &'a str,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cost_usd: f64,
+    pub message_id: &'a str,
+    pub request_id: &'a str,
+    /// Fields of the JSONL line clauditor doesn't model
+    #[serde(skip_serializing_if = "UnknownFields::is_empty")]
+    pub extra: &'a UnknownFields,
+}
+
+impl<'a> ExportRecord<'a> {
+    /// Build a record for an entry, or `None` if it has no usage data
+    pub fn new(tagged: &'a EntryWithProject) -> Option<Self> {
+        let entry = &tagged.entry;
+        let usage = entry.message.usage.as_ref()?;
+        Some(Self {
+            timestamp: entry.timestamp,
+            project: &tagged.project,
+            session_id: &tagged.session_id,
+            model: &entry.message.model,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_tokens: usage.cache_creation_input_tokens,
+            cache_read_tokens: usage.cache_read_input_tokens,
+            cost_usd: entry_cost(entry),
+            message_id: &entry.message.id,
+            request_id: &entry.request_id,
+            extra: &entry.unknown,
+        })
+    }
+
+    fn csv_row(&self) -> String {
+        [
+            self.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+            csv_field(self.project),
+            csv_field(self.session_id),
+            csv_field(self.model),
+            self.input_tokens.to_string(),
+            self.output_tokens.to_string(),
+            self.cache_creation_tokens.to_string(),
+            self.cache_read_tokens.to_string(),
+            format!("{:.6}", self.cost_usd),
+            csv_field(self.message_id),
+            csv_field(self.request_id),
+        ]
+        .join(",")
+    }
+}
+
+/// Writes records to an output as they are produced
+pub struct ExportWriter<W: Write> {
+    out: W,
+    format: ExportFormat,
+    rows: usize,
+    duplicates: usize,
+}
+
+impl<W: Write> ExportWriter<W> {
+    /// Create a writer, emitting the CSV header if needed
+    pub fn new(mut out: W, format: ExportFormat) -> Result<Self> {
+        if format == ExportFormat::Csv {
+            writeln!(out, "{}", CSV_HEADER)?;
+        }
+        Ok(Self { out, format, rows: 0, duplicates: 0 })
+    }
+
+    pub fn write(&mut self, record: &ExportRecord) -> Result<()> {
+        match self.format {
+            ExportFormat::Csv => writeln!(self.out, "{}", record.csv_row())?,
+            ExportFormat::Ndjson => writeln!(self.out, "{}", serde_json::to_string(record)?)?,
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Flush the output and return the number of records written and duplicates skipped
+    pub fn finish(mut self) -> Result<(usize, usize)> {
+        self.out.flush()?;
+        Ok((self.rows, self.duplicates))
+    }
+}
+
+/// Export every entry with a timestamp in `[since, until)` from matching projects
+///
+/// Rows are chronological within each session. Entries copied into several
+/// files by resumed sessions are written once: the whole scan is deduplicated
+/// before filtering, so a copy is credited to the same session as in the
+/// window and history views.
+pub fn export_entries<W: Write>(
+    config: &Config,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    writer: &mut ExportWriter<W>,
+) -> Result<()> {
+    let hours_back = (Utc::now() - since).num_hours() + 1;
+    let mut scanner = SessionScanner::from_config(config).with_hours_back(hours_back);
+    let filter = ProjectFilter::from_config(&config.filter)?;
+    let exported = |e: &EntryWithProject| {
+        e.entry.timestamp >= since && e.entry.timestamp < until && filter.matches(&e.project)
+    };
+
+    let entries = tag_with_projects(scanner.load_sessions()?);
+    let matching = entries.iter().filter(|e| exported(e)).count();
+    let entries = dedup_entries(entries);
+
+    let mut kept = 0;
+    for entry in entries.iter().filter(|e| exported(e)) {
+        kept += 1;
+        if let Some(record) = ExportRecord::new(entry) {
+            writer.write(&record)?;
+        }
+    }
+    writer.duplicates += matching - kept;
+
+    Ok(())
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::usage_line;
+    use crate::types::{Message, TokenUsage, UsageEntry};
+
+    fn entry() -> EntryWithProject {
+        EntryWithProject {
+            project: "/home/me/acme, inc".to_string(),
+            session_id: "s1".to_string(),
+            entry: UsageEntry {
+                timestamp: "2025-01-13T14:05:00Z".parse().unwrap(),
+                message: Message {
+                    id: "msg_1".to_string(),
+                    msg_type: "message".to_string(),
+                    role: "assistant".to_string(),
+                    model: "claude-sonnet-4-20250514".to_string(),
+                    usage: Some(TokenUsage {
+                        input_tokens: 1000,
+                        output_tokens: 500,
+                        cache_creation_input_tokens: 0,
+                        cache_read_input_tokens: 2000,
+                    }),
+                },
+                cost_usd: None,
+                request_id: "req_1".to_string(),
+                version: "1.0.51".to_string(),
+                unknown: UnknownFields {
+                    usage: serde_json::json!({"service_tier": "standard"}).as_object().unwrap().clone(),
+                    ..UnknownFields::default()
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let entry = entry();
+        let record = ExportRecord::new(&entry).unwrap();
+
+        let mut writer = ExportWriter::new(Vec::new(), ExportFormat::Csv).unwrap();
+        writer.write(&record).unwrap();
+        assert_eq!(writer.rows, 1);
+        let output = String::from_utf8(writer.out).unwrap();
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "2025-01-13T14:05:00.000Z,\"/home/me/acme, inc\",s1,claude-sonnet-4-20250514,\
+1000,500,0,2000,0.011100,msg_1,req_1"
+        );
+    }
+
+    #[test]
+    fn test_write_ndjson() {
+        let entry = entry();
+        let record = ExportRecord::new(&entry).unwrap();
+
+        let mut writer = ExportWriter::new(Vec::new(), ExportFormat::Ndjson).unwrap();
+        writer.write(&record).unwrap();
+        let output = String::from_utf8(writer.out).unwrap();
+
+        let value: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(value["project"], "/home/me/acme, inc");
+        assert_eq!(value["session_id"], "s1");
+        assert_eq!(value["cache_read_tokens"], 2000);
+        assert!((value["cost_usd"].as_f64().unwrap() - 0.0111).abs() < 1e-9);
+        assert_eq!(value["extra"]["usage"]["service_tier"], "standard");
+        assert!(value["extra"].get("entry").is_none());
+    }
+
+    #[test]
+    fn test_copies_are_credited_like_the_window() {
+        let dir = tempfile::tempdir().unwrap();
+        for (project, session) in [("-home-me-a", "s1"), ("-home-me-b", "s2")] {
+            let project_dir = dir.path().join("projects").join(project);
+            std::fs::create_dir_all(&project_dir).unwrap();
+            let lines = format!("{}\n{}\n", usage_line("msg_copied"), usage_line(session));
+            std::fs::write(project_dir.join(format!("{}.jsonl", session)), lines).unwrap();
+        }
+        let config = Config {
+            claude_paths: vec![dir.path().to_path_buf()],
+            state_dir: None,
+            filter: crate::filter::FilterConfig {
+                include: vec!["/home/me/a".to_string()],
+                ..Default::default()
+            },
+            ..Config::default()
+        };
+
+        let mut writer = ExportWriter::new(Vec::new(), ExportFormat::Ndjson).unwrap();
+        let since = "2025-01-01T00:00:00Z".parse().unwrap();
+        export_entries(&config, since, Utc::now(), &mut writer).unwrap();
+        let output = String::from_utf8(writer.out).unwrap();
+        let exported: Vec<String> = output.lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["message_id"].to_string())
+            .collect();
+
+        // The copy only counts toward project a if the full scan kept a's copy
+        let expected: Vec<String> = crate::coordinator::load_entries(&config).unwrap().into_iter()
+            .filter(|e| e.project == "/home/me/a")
+            .map(|e| serde_json::Value::from(e.entry.message.id).to_string())
+            .collect();
+        assert_eq!(exported, expected);
+        assert!(exported.contains(&"\"s1\"".to_string()));
+    }
+}
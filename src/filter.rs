@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+
+use crate::types::EntryWithProject;
+
+/// Project filter settings from the config file or `--project`/`--exclude`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterConfig {
+    /// Only count projects whose decoded path matches one of these globs
+    pub include: Vec<String>,
+    /// Skip projects whose decoded path matches one of these globs
+    pub exclude: Vec<String>,
+    /// Take window boundaries from all projects, but only show the filtered ones
+    pub account_window: bool,
+}
+
+/// Compiled include/exclude globs matched against decoded project paths
+///
+/// `*` also matches `/`, so `*client-a*` matches a project anywhere on disk.
+#[derive(Debug, Clone)]
+pub struct ProjectFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl ProjectFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: build_glob_set(include)?,
+            exclude: build_glob_set(exclude)?,
+        })
+    }
+
+    pub fn from_config(config: &FilterConfig) -> Result<Self> {
+        Self::new(&config.include, &config.exclude)
+    }
+
+    /// True if no patterns are set, so every project matches
+    pub fn is_empty(&self) -> bool {
+        self.include.is_none() && self.exclude.is_none()
+    }
+
+    /// Whether a project should be counted
+    pub fn matches(&self, project: &str) -> bool {
+        let included = self.include.as_ref().is_none_or(|set| set.is_match(project));
+        let excluded = self.exclude.as_ref().is_some_and(|set| set.is_match(project));
+        included && !excluded
+    }
+
+    /// Keep only entries from matching projects
+    pub fn apply(&self, entries: Vec<EntryWithProject>) -> Vec<EntryWithProject> {
+        if self.is_empty() {
+            return entries;
+        }
+        entries.into_iter().filter(|e| self.matches(&e.project)).collect()
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .with_context(|| format!("Invalid project pattern '{}'", pattern))?;
+        builder.add(glob);
+    }
+    Ok(Some(builder.build()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let filter = ProjectFilter::new(&[], &[]).unwrap();
+        assert!(filter.is_empty());
+        assert!(filter.matches("/home/me/anything"));
+    }
+
+    #[test]
+    fn test_include_and_exclude() {
+        let filter = ProjectFilter::new(
+            &patterns(&["/home/me/clients/**", "*acme*"]),
+            &patterns(&["*/scratch"]),
+        ).unwrap();
+
+        assert!(filter.matches("/home/me/clients/globex/api"));
+        assert!(filter.matches("/srv/acme-web"));
+        assert!(!filter.matches("/home/me/clients/scratch"));
+        assert!(!filter.matches("/home/me/personal/blog"));
+    }
+
+    #[test]
+    fn test_exclude_only() {
+        let filter = ProjectFilter::new(&[], &patterns(&["*dotfiles*"])).unwrap();
+        assert!(filter.matches("/home/me/work/api"));
+        assert!(!filter.matches("/home/me/dotfiles"));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let err = ProjectFilter::new(&patterns(&["[unclosed"]), &[]).unwrap_err();
+        assert!(err.to_string().contains("[unclosed"));
+    }
+}
@@ -0,0 +1,205 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::coordinator::SUMMARY_SCHEMA_VERSION;
+use crate::types::{is_block_active, EntryWithProject, SessionBlock};
+use crate::window::{create_window_with_projects, split_into_windows};
+
+/// Width of the buckets used to find a window's peak burn rate
+const PEAK_INTERVAL_MINUTES: i64 = 15;
+
+/// A past (or current) billing window with statistics only derivable from its entries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoricalWindow {
+    #[serde(flatten)]
+    pub window: SessionBlock,
+    /// Highest burn rate over any 15-minute interval, in tokens per minute
+    pub peak_burn_rate: f64,
+}
+
+impl HistoricalWindow {
+    /// Time between the window start and its last activity
+    pub fn duration_used(&self) -> Duration {
+        self.window.last_activity - self.window.start_time
+    }
+}
+
+/// Machine-readable history document, emitted by `history --format json`
+#[derive(Debug, Serialize)]
+pub struct HistoryReport<'a> {
+    pub schema_version: u32,
+    pub generated_at: DateTime<Utc>,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub windows: &'a [HistoricalWindow],
+}
+
+impl<'a> HistoryReport<'a> {
+    pub fn new(windows: &'a [HistoricalWindow], since: DateTime<Utc>, until: DateTime<Utc>) -> Self {
+        Self {
+            schema_version: SUMMARY_SCHEMA_VERSION,
+            generated_at: Utc::now(),
+            since,
+            until,
+            windows,
+        }
+    }
+}
+
+/// Rebuild every billing window that overlaps `[since, until)`, oldest first
+///
+/// Window boundaries depend on earlier activity, so `entries` should reach back
+/// at least one window duration before `since`. Boundaries come from all
+/// entries; only those `keep` accepts are counted, and windows left with no
+/// usage are dropped.
+pub fn build_history<F>(
+    entries: Vec<EntryWithProject>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    now: DateTime<Utc>,
+    session_duration: Duration,
+    keep: F,
+) -> Vec<HistoricalWindow>
+where
+    F: Fn(&EntryWithProject) -> bool,
+{
+    split_into_windows(entries, session_duration)
+        .into_iter()
+        .filter_map(|(start, mut window_entries)| {
+            window_entries.retain(|e| keep(e));
+            let mut window = create_window_with_projects(start, &window_entries, session_duration)?;
+            if window.end_time <= since || window.start_time >= until {
+                return None;
+            }
+            window.is_active = is_block_active(&window, now);
+
+            Some(HistoricalWindow {
+                peak_burn_rate: peak_burn_rate(window.start_time, &window_entries),
+                window,
+            })
+        })
+        .collect()
+}
+
+/// Highest tokens per minute in any fixed 15-minute bucket from the window start
+fn peak_burn_rate(start_time: DateTime<Utc>, entries: &[EntryWithProject]) -> f64 {
+    let interval = Duration::minutes(PEAK_INTERVAL_MINUTES);
+    let mut buckets: Vec<u64> = Vec::new();
+
+    for entry_with_project in entries {
+        if let Some(usage) = &entry_with_project.entry.message.usage {
+            let offset = entry_with_project.entry.timestamp - start_time;
+            let index = (offset.num_seconds() / interval.num_seconds()).max(0) as usize;
+            if buckets.len() <= index {
+                buckets.resize(index + 1, 0);
+            }
+            buckets[index] += usage.input_tokens
+                + usage.output_tokens
+                + usage.cache_creation_input_tokens
+                + usage.cache_read_input_tokens;
+        }
+    }
+
+    buckets.into_iter().max().unwrap_or(0) as f64 / PEAK_INTERVAL_MINUTES as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Message, TokenUsage, UsageEntry};
+
+    fn entry(timestamp: &str, project: &str, tokens: u64) -> EntryWithProject {
+        EntryWithProject {
+            entry: UsageEntry {
+                timestamp: timestamp.parse().unwrap(),
+                message: Message {
+                    id: format!("msg_{}", timestamp),
+                    msg_type: "message".to_string(),
+                    role: "assistant".to_string(),
+                    model: "claude-sonnet-4-20250514".to_string(),
+                    usage: Some(TokenUsage {
+                        input_tokens: tokens,
+                        output_tokens: 0,
+                        cache_creation_input_tokens: 0,
+                        cache_read_input_tokens: 0,
+                    }),
+                },
+                cost_usd: None,
+                request_id: format!("req_{}", timestamp),
+                version: "1.0.51".to_string(),
+                unknown: Default::default(),
+            },
+            project: project.to_string(),
+            session_id: "session".to_string(),
+        }
+    }
+
+    fn time(timestamp: &str) -> DateTime<Utc> {
+        timestamp.parse().unwrap()
+    }
+
+    #[test]
+    fn test_build_history_returns_every_window() {
+        let entries = vec![
+            entry("2025-01-13T09:10:00Z", "a", 100),
+            entry("2025-01-13T10:00:00Z", "b", 300),
+            entry("2025-01-13T17:00:00Z", "a", 50),
+            entry("2025-01-14T01:00:00Z", "b", 25),
+        ];
+
+        let history = build_history(
+            entries,
+            time("2025-01-13T00:00:00Z"),
+            time("2025-01-15T00:00:00Z"),
+            time("2025-01-14T02:00:00Z"),
+            Duration::hours(5),
+            |_| true,
+        );
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].window.start_time, time("2025-01-13T09:00:00Z"));
+        assert_eq!(history[0].window.token_counts.total(), 400);
+        assert_eq!(history[0].window.projects.len(), 2);
+        assert_eq!(history[0].duration_used(), Duration::minutes(60));
+        assert!(!history[0].window.is_active);
+
+        assert_eq!(history[1].window.start_time, time("2025-01-13T17:00:00Z"));
+        assert_eq!(history[2].window.start_time, time("2025-01-14T01:00:00Z"));
+        assert!(history[2].window.is_active);
+    }
+
+    #[test]
+    fn test_build_history_filters_by_range() {
+        let entries = vec![
+            entry("2025-01-13T09:10:00Z", "a", 100),
+            entry("2025-01-13T17:00:00Z", "a", 50),
+            entry("2025-01-14T01:00:00Z", "b", 25),
+        ];
+
+        // The 17:00-22:00 window overlaps the range start, the next one starts after it ends
+        let history = build_history(
+            entries,
+            time("2025-01-13T20:00:00Z"),
+            time("2025-01-14T00:00:00Z"),
+            time("2025-01-14T02:00:00Z"),
+            Duration::hours(5),
+            |_| true,
+        );
+
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].window.start_time, time("2025-01-13T17:00:00Z"));
+    }
+
+    #[test]
+    fn test_peak_burn_rate() {
+        let entries = vec![
+            entry("2025-01-13T09:01:00Z", "a", 1500),
+            entry("2025-01-13T09:05:00Z", "a", 1500),
+            // Next bucket
+            entry("2025-01-13T09:20:00Z", "a", 600),
+        ];
+
+        let rate = peak_burn_rate(time("2025-01-13T09:00:00Z"), &entries);
+        assert_eq!(rate, 200.0);
+    }
+}
@@ -6,6 +6,26 @@ pub mod coordinator;
 pub mod display;
 pub mod watcher;
 pub mod position_tracker;
+pub mod pricing;
+pub mod history;
+pub mod session_history;
+pub mod plan;
+pub mod config;
+pub mod filter;
+pub mod export;
+pub mod diagnostics;
+pub mod schema;
+pub mod entry_cache;
+pub mod state;
+pub mod events;
+pub mod usage_state;
+pub mod tui;
+pub mod statusline;
+pub mod daemon;
+pub mod metrics;
+pub mod alerts;
+#[cfg(test)]
+mod test_support;
 
 // Re-export commonly used types
 pub use types::{UsageEntry, SessionFile, SessionBlock};
\ No newline at end of file
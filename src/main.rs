@@ -6,13 +6,36 @@ mod coordinator;
 mod display;
 mod watcher;
 mod position_tracker;
+mod pricing;
+mod history;
+mod session_history;
+mod plan;
+mod config;
+mod filter;
+mod export;
+mod diagnostics;
+mod schema;
+mod entry_cache;
+mod state;
+mod events;
+mod usage_state;
+mod tui;
+mod statusline;
+mod daemon;
+mod metrics;
+mod alerts;
+#[cfg(test)]
+mod test_support;
 
-use anyhow::Result;
-use clap::Parser;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::thread;
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate, TimeZone, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Multi-session Claude Code usage tracker
 #[derive(Parser)]
@@ -20,26 +43,266 @@ use std::thread;
 #[command(version)]
 #[command(about = "Track active Claude Code billing windows across multiple sessions", long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    
     /// Watch for file changes and continuously update the display
     #[arg(short, long)]
     watch: bool,
+    
+    /// In watch mode, reprint plain text instead of opening the interactive dashboard
+    #[arg(long)]
+    plain: bool,
+    
+    /// List each project's sessions (conversations) with their tokens, time span and models
+    #[arg(long)]
+    sessions: bool,
+    
+    /// Plan to track the window's token limit against: pro, max5, max20, or a custom token limit
+    #[arg(short, long)]
+    plan: Option<plan::Plan>,
+    
+    /// Config file to read [default: ~/.config/clauditor/config.toml]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    
+    /// Claude data directory to scan (repeatable; replaces claude_paths and extra_claude_paths)
+    #[arg(long = "claude-path", global = true)]
+    claude_paths: Vec<PathBuf>,
+    
+    /// Only scan session files modified within this many hours
+    #[arg(long, global = true)]
+    hours_back: Option<i64>,
+    
+    /// Length of a billing window in hours
+    #[arg(long, global = true)]
+    session_hours: Option<i64>,
+    
+    /// Only count projects whose path matches this glob (repeatable)
+    #[arg(long = "project", global = true, value_name = "GLOB")]
+    projects: Vec<String>,
+    
+    /// Skip projects whose path matches this glob (repeatable)
+    #[arg(long = "exclude", global = true, value_name = "GLOB")]
+    exclude: Vec<String>,
+    
+    /// Keep the account-wide window boundaries when filtering projects
+    #[arg(long, global = true)]
+    account_window: bool,
+    
+    /// Report problems reading session files instead of showing usage (same as `doctor`)
+    #[arg(long)]
+    diagnostics: bool,
+    
+    /// Output format (json emits one NDJSON object per refresh in watch mode)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List past billing windows with totals, peak burn rate and top projects
+    History {
+        /// First day to include, in local time (YYYY-MM-DD) [default: 6 days ago]
+        #[arg(long)]
+        since: Option<NaiveDate>,
+        
+        /// Last day to include, in local time (YYYY-MM-DD) [default: today]
+        #[arg(long)]
+        until: Option<NaiveDate>,
+        
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Write every usage entry to CSV or NDJSON
+    Export {
+        /// First day to include, in local time (YYYY-MM-DD) [default: 6 days ago]
+        #[arg(long)]
+        since: Option<NaiveDate>,
+        
+        /// Last day to include, in local time (YYYY-MM-DD) [default: today]
+        #[arg(long)]
+        until: Option<NaiveDate>,
+        
+        /// File to write to [default: stdout]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        
+        /// File format
+        #[arg(short, long, value_enum, default_value_t = ExportFileFormat::Csv)]
+        format: ExportFileFormat,
+    },
+    /// Check session files for lines that couldn't be parsed
+    Doctor {
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Print a one-line summary for shell prompts, tmux and Claude Code's status line
+    Statusline {
+        /// Template to fill in, e.g. "{remaining} {tokens}" [default: from the config file]
+        #[arg(long)]
+        template: Option<String>,
+        
+        /// Recompute the cached summary without printing anything
+        #[arg(long, hide = true)]
+        refresh: bool,
+    },
+    /// Keep sessions in memory and answer other clauditor runs over a local socket
+    Daemon,
+    /// Expose the active window as Prometheus metrics over HTTP
+    Serve {
+        /// Address to serve /metrics on, e.g. 127.0.0.1:9464
+        #[arg(long, value_name = "ADDR")]
+        metrics: SocketAddr,
+    },
+}
+
+/// How the billing window is rendered
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable colored text
+    Text,
+    /// Versioned JSON document
+    Json,
+}
+
+/// File format written by `export`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportFileFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON object per line
+    #[value(alias = "json")]
+    Ndjson,
+}
+
+impl From<ExportFileFormat> for export::ExportFormat {
+    fn from(format: ExportFileFormat) -> Self {
+        match format {
+            ExportFileFormat::Csv => Self::Csv,
+            ExportFileFormat::Ndjson => Self::Ndjson,
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let config = resolve_config(&cli)?;
+    let mut options = display::DisplayOptions::from_config(&config);
+    options.show_sessions = cli.sessions;
 
+    match cli.command {
+        Some(Command::History { since, until, format }) => {
+            return run_history_mode(since, until, format, &config, &options);
+        }
+        Some(Command::Export { since, until, ref output, format }) => {
+            return run_export_mode(since, until, output.as_deref(), format.into(), &config);
+        }
+        Some(Command::Doctor { format }) => return run_doctor_mode(format, &config),
+        Some(Command::Statusline { ref template, refresh }) => {
+            return run_statusline_mode(template.as_deref(), refresh, &config);
+        }
+        Some(Command::Daemon) => return run_daemon_mode(&config),
+        Some(Command::Serve { metrics }) => return run_serve_mode(metrics, &config),
+        None if cli.diagnostics => return run_doctor_mode(cli.format, &config),
+        None => {}
+    }
+    
     if cli.watch {
-        run_watch_mode()
+        run_watch_mode(cli.format, cli.plain, &config, &options)
     } else {
-        run_one_shot_mode()
+        run_one_shot_mode(cli.format, &config, &options)
+    }
+}
+
+/// Load the config file and apply command-line overrides on top
+fn resolve_config(cli: &Cli) -> Result<config::Config> {
+    let mut config = config::Config::load(cli.config.as_deref())?;
+    
+    if !cli.claude_paths.is_empty() {
+        config.claude_paths = cli.claude_paths.clone();
+        config.extra_claude_paths.clear();
+    }
+    if let Some(hours_back) = cli.hours_back {
+        config.hours_back = hours_back;
+    }
+    if let Some(session_hours) = cli.session_hours {
+        config.session_duration_hours = session_hours;
+    }
+    if cli.plan.is_some() {
+        config.plan = cli.plan;
+    }
+    if !cli.projects.is_empty() {
+        config.filter.include = cli.projects.clone();
+    }
+    if !cli.exclude.is_empty() {
+        config.filter.exclude = cli.exclude.clone();
+    }
+    if cli.account_window {
+        config.filter.account_window = true;
+    }
+    
+    config.validate()?;
+    Ok(config)
+}
+
+/// Render the active window in the requested format
+fn render(window: Option<&types::SessionBlock>, format: OutputFormat, options: &display::DisplayOptions) -> Result<()> {
+    match format {
+        OutputFormat::Text => display::display_active_window_with_options(window, options),
+        OutputFormat::Json => {
+            let summary = coordinator::ActiveWindowSummary::from_window(window, Utc::now())
+                .with_plan(window, options.plan);
+            println!("{}", summary.to_json_line()?);
+        }
+    }
+    Ok(())
+}
+
+/// Ask a running daemon, or `None` if there isn't one for these settings
+fn query_daemon<T>(config: &config::Config, query: impl FnOnce(&mut daemon::Client) -> Result<T>) -> Option<T> {
+    let mut client = daemon::Client::connect(config)?;
+    query(&mut client).ok()
+}
+
+/// Print a warning that session files couldn't be parsed, pointing to `doctor`
+fn warn_unparseable() {
+    eprintln!("Warning: {}", diagnostics::UNPARSEABLE_WARNING);
+}
+
+/// Warns once each time session files become unparseable, for output that isn't redrawn
+#[derive(Default)]
+struct UnparseableWarning {
+    shown: bool,
+}
+
+impl UnparseableWarning {
+    fn update(&mut self, unparseable: bool) {
+        if unparseable && !self.shown {
+            warn_unparseable();
+        }
+        self.shown = unparseable;
     }
 }
 
 /// Run once, print the current billing window, and exit.
-fn run_one_shot_mode() -> Result<()> {
-    match coordinator::get_active_billing_window() {
-        Ok(window) => {
-            display::display_active_window(window.as_ref());
+fn run_one_shot_mode(format: OutputFormat, config: &config::Config, options: &display::DisplayOptions) -> Result<()> {
+    let update = match query_daemon(config, |daemon| daemon.window()) {
+        Some(update) => Ok(update),
+        None => coordinator::LiveSessions::load(config).map(|mut live| daemon::WindowUpdate {
+            window: live.active_window(),
+            tokens_per_minute: Vec::new(),
+            usage_unparseable: live.usage_unparseable(),
+        }),
+    };
+    match update {
+        Ok(update) => {
+            render(update.window.as_ref(), format, options)?;
+            if update.usage_unparseable {
+                warn_unparseable();
+            }
         }
         Err(e) => {
             eprintln!("Error loading sessions: {}", e);
@@ -48,100 +311,456 @@ fn run_one_shot_mode() -> Result<()> {
     Ok(())
 }
 
+/// Print every billing window between two local dates (inclusive) and exit.
+fn run_history_mode(
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    format: OutputFormat,
+    config: &config::Config,
+    options: &display::DisplayOptions,
+) -> Result<()> {
+    let (since, until) = resolve_date_range(since, until)?;
+    let history = match query_daemon(config, |daemon| daemon.history(since, until)) {
+        Some(history) => history,
+        None => coordinator::load_history(config, since, until)?,
+    };
+    
+    match format {
+        OutputFormat::Text => display::display_history(&history, options),
+        OutputFormat::Json => {
+            let report = history::HistoryReport::new(&history, since, until);
+            println!("{}", serde_json::to_string(&report)?);
+        }
+    }
+    Ok(())
+}
+
+/// Write usage entries between two local dates (inclusive) and exit.
+fn run_export_mode(
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    output: Option<&Path>,
+    format: export::ExportFormat,
+    config: &config::Config,
+) -> Result<()> {
+    let (since, until) = resolve_date_range(since, until)?;
+    
+    let out: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?),
+        None => Box::new(io::stdout().lock()),
+    };
+    
+    let mut writer = export::ExportWriter::new(BufWriter::new(out), format)?;
+    export::export_entries(config, since, until, &mut writer)?;
+    let (rows, duplicates) = writer.finish()?;
+    
+    if let Some(path) = output {
+        eprintln!("Exported {} entries to {}", rows, path.display());
+    }
+    if duplicates > 0 {
+        eprintln!("Skipped {} duplicate entries from resumed sessions", duplicates);
+    }
+    Ok(())
+}
+
+/// Report what went wrong reading session files and exit.
+fn run_doctor_mode(format: OutputFormat, config: &config::Config) -> Result<()> {
+    let report = diagnostics::run_diagnostics(config)?;
+    
+    match format {
+        OutputFormat::Text => display::display_diagnostics(&report),
+        OutputFormat::Json => println!("{}", serde_json::to_string(&report)?),
+    }
+    Ok(())
+}
+
+/// Print the cached status line and exit, refreshing the cache if it's stale.
+/// 
+/// A running daemon is asked first. Otherwise a stale summary is still
+/// printed, and a detached copy of this command recomputes it so the prompt
+/// never waits on session files. Only the first run, with nothing cached yet,
+/// computes the window itself.
+fn run_statusline_mode(template: Option<&str>, refresh: bool, config: &config::Config) -> Result<()> {
+    if let Some(template) = template {
+        statusline::check_template(template)?;
+    }
+    let cache_file = statusline::cache_path(config)
+        .context("Cannot locate the state directory (HOME is not set)")?;
+    
+    if refresh {
+        statusline::refresh(&cache_file, config)?;
+        return Ok(());
+    }
+    
+    let project = statusline::read_stdin_payload()
+        .and_then(|payload| statusline::project_from_payload(&payload));
+    let now = Utc::now();
+    let snapshot = match query_daemon(config, |daemon| daemon.window()) {
+        Some(update) => statusline::StatusSnapshot::from_window(update.window.as_ref(), config, now),
+        None => match statusline::read_cached(&cache_file, config) {
+            Some(snapshot) => {
+                if snapshot.is_stale(config, now) {
+                    spawn_statusline_refresh();
+                }
+                snapshot
+            }
+            None => statusline::refresh(&cache_file, config)?,
+        },
+    };
+    
+    let template = template.unwrap_or(&config.statusline.template);
+    println!("{}", snapshot.render(template, &config.statusline.idle_template, project.as_deref(), now));
+    Ok(())
+}
+
+/// Rerun this command with `--refresh` in the background, detached from our output
+fn spawn_statusline_refresh() {
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+    let _ = std::process::Command::new(exe)
+        .args(std::env::args_os().skip(1))
+        .arg("--refresh")
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+}
+
+/// How often watch mode redraws when nothing changes
+const WATCH_TICK: Duration = Duration::from_secs(60);
+
+/// Time until the next wall-clock minute, when the displayed countdown changes
+fn until_next_minute() -> Duration {
+    let into_minute = Utc::now().timestamp_millis().rem_euclid(60_000) as u64;
+    Duration::from_millis(60_000 - into_minute)
+}
+
+/// Clear the screen and render the window as of now
+fn redraw(window: Option<&mut types::SessionBlock>, format: OutputFormat, options: &display::DisplayOptions) -> Result<()> {
+    // Clear screen (JSON output is appended as NDJSON instead)
+    if format == OutputFormat::Text {
+        print!("\x1B[2J\x1B[1;1H");
+    }
+    
+    let window = window.and_then(|window| {
+        window.is_active = types::is_block_active(window, Utc::now());
+        window.is_active.then_some(&*window)
+    });
+    render(window, format, options)?;
+    io::stdout().flush()?;
+    Ok(())
+}
+
+/// Turn optional `--since`/`--until` dates into a half-open UTC range
+/// 
+/// Defaults to the last 7 days including today.
+fn resolve_date_range(since: Option<NaiveDate>, until: Option<NaiveDate>) -> Result<(chrono::DateTime<Utc>, chrono::DateTime<Utc>)> {
+    let today = Local::now().date_naive();
+    let until = until.unwrap_or(today);
+    let since = since.unwrap_or(today - chrono::Duration::days(6));
+    
+    if since > until {
+        anyhow::bail!("--since ({}) must not be after --until ({})", since, until);
+    }
+    
+    Ok((local_midnight(since), local_midnight(until + chrono::Duration::days(1))))
+}
+
+/// Start of a local calendar day, in UTC
+fn local_midnight(date: NaiveDate) -> chrono::DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    Local.from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        // Midnight skipped by a DST change: treat the naive time as UTC
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+/// Where watch mode and the daemon get the active window from
+enum WatchSource {
+    /// Session files read by this process and re-read as they change
+    Direct {
+        live: Box<coordinator::LiveSessions>,
+        /// `None` if file events aren't available, so every tick rescans
+        watcher: Option<watcher::SessionWatcher>,
+    },
+    /// Windows pushed by a running daemon
+    Daemon(Arc<Mutex<daemon::WindowUpdate>>),
+}
+
+impl WatchSource {
+    /// Follow a running daemon, if there is one for these settings
+    fn subscribe(config: &config::Config, events: &mpsc::Sender<events::WatchEvent>) -> Option<Self> {
+        let (update, subscription) = daemon::Client::connect(config)?.subscribe().ok()?;
+        let latest = Arc::new(Mutex::new(update));
+        let slot = Arc::clone(&latest);
+        daemon::forward_updates(subscription, events.clone(), move |update| {
+            *slot.lock().unwrap() = update;
+        });
+        Some(Self::Daemon(latest))
+    }
+    
+    /// Scan session files and watch them for changes
+    /// 
+    /// Also returns why file events aren't available, if they aren't.
+    fn direct(config: &config::Config, events: &mpsc::Sender<events::WatchEvent>) -> Result<(Self, Option<anyhow::Error>)> {
+        // Sessions stay in memory, so only changed files are read on each reload
+        let live = Box::new(coordinator::LiveSessions::load(config)?);
+        let file_tx = events.clone();
+        let file_watcher = watcher::SessionWatcher::with_handler(config.claude_roots(), move |file_event| {
+            let _ = file_tx.send(events::WatchEvent::FileChanged(file_event));
+        });
+        let (watcher, watch_error) = match file_watcher {
+            Ok(w) => (Some(w), None),
+            Err(e) => (None, Some(e.context("Could not set up file watching, will rely on periodic refresh only"))),
+        };
+        Ok((Self::Direct { live, watcher }, watch_error))
+    }
+    
+    /// The active window, with tokens per minute for the dashboard's sparkline
+    fn current(&mut self) -> daemon::WindowUpdate {
+        match self {
+            // Cheap even on ticks: the window is kept up to date as entries
+            // arrive, and rolls over once its end has passed
+            Self::Direct { live, .. } => daemon::WindowUpdate {
+                window: live.active_window(),
+                tokens_per_minute: live.tokens_per_minute(tui::SPARKLINE_MINUTES),
+                usage_unparseable: live.usage_unparseable(),
+            },
+            Self::Daemon(latest) => latest.lock().unwrap().clone(),
+        }
+    }
+    
+    /// Lines of the loaded session files that couldn't be parsed
+    fn parse_errors(&self) -> parser::ParseErrors {
+        match self {
+            Self::Direct { live, .. } => live.parse_errors(),
+            Self::Daemon(_) => parser::ParseErrors::default(),
+        }
+    }
+    
+    /// Re-read session files that changed
+    fn reload(&mut self, changes: &watcher::ChangeSet) -> Result<()> {
+        match self {
+            Self::Direct { live, .. } => live.apply(changes).context("Error loading changed sessions"),
+            Self::Daemon(_) => Ok(()),
+        }
+    }
+    
+    /// Catch up on a tick: without file events, rescan every session
+    fn tick(&mut self) -> Result<()> {
+        match self {
+            Self::Direct { live, watcher: None } => live.rescan().context("Error loading sessions"),
+            _ => Ok(()),
+        }
+    }
+}
+
 /// Run in a continuous loop, watching for file changes.
-fn run_watch_mode() -> Result<()> {
-    // Create persistent scanner with position tracking
-    let mut scanner = scanner::SessionScanner::new();
-    let mut current_window: Option<types::SessionBlock> = None;
-    
-    // Set up file watcher
-    let file_watcher = match watcher::SessionWatcher::with_default_paths() {
-        Ok(w) => Some(w),
-        Err(e) => {
-            eprintln!("Warning: Could not set up file watching: {}", e);
-            eprintln!("Will rely on periodic refresh only");
-            None
+/// 
+/// Blocks on one channel fed by the file watcher (or a running daemon),
+/// Ctrl+C and terminal resizes. Changes are debounced per file and only those
+/// files are re-read; a tick on each minute boundary keeps the countdown
+/// current. Text output to a terminal opens the dashboard unless `plain` is set.
+fn run_watch_mode(format: OutputFormat, plain: bool, config: &config::Config, options: &display::DisplayOptions) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    
+    // A running daemon already watches the sessions; otherwise read them here
+    let mut source = match WatchSource::subscribe(config, &tx) {
+        Some(source) => source,
+        None => {
+            let (source, watch_error) = WatchSource::direct(config, &tx)?;
+            if let Some(e) = watch_error {
+                eprintln!("Warning: {:#}", e);
+            }
+            source
         }
     };
     
-    // Set up Ctrl+C handler
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-    
-    ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-    }).expect("Error setting Ctrl-C handler");
-    
-    // Track if we need immediate refresh
-    let mut needs_refresh = true;
-    let mut needs_full_reload = true;
-    
-    // Main loop
-    while running.load(Ordering::SeqCst) {
-        // Check for file events
-        if let Some(ref watcher) = file_watcher {
-            let events = watcher.poll_events();
-            if !events.is_empty() {
-                // Files changed, use incremental loading
-                needs_refresh = true;
-                
-                // Load data incrementally (or full reload if active window detected)
-                match coordinator::load_and_group_sessions_incremental(&mut scanner) {
-                    Ok(new_window_opt) => {
-                        // The coordinator now returns complete window data when active
-                        // No need for complex merging - just replace
-                        current_window = new_window_opt;
-                    }
-                    Err(e) => {
-                        eprintln!("Error loading incremental sessions: {}", e);
-                    }
+    let mut dashboard = if format == OutputFormat::Text && !plain && io::stdout().is_terminal() {
+        tui::forward_input(tx.clone());
+        Some(tui::Dashboard::open(options)?)
+    } else {
+        None
+    };
+    events::forward_signals(tx.clone())?;
+    
+    let now = Instant::now();
+    let mut scheduler = events::Scheduler::new(events::DEBOUNCE, WATCH_TICK, now + until_next_minute());
+    let mut alerts = alerts::AlertEngine::new(config, alerts::state_path(config));
+    // Errors are shown in the dashboard's footer, which stderr would scribble over
+    let mut status: Option<String> = None;
+    let mut unparseable = UnparseableWarning::default();
+    
+    loop {
+        let mut current = source.current();
+        let warning = current.usage_unparseable.then_some(diagnostics::UNPARSEABLE_WARNING);
+        alerts.evaluate(current.window.as_ref(), Utc::now());
+        for failure in alerts.take_failures() {
+            if dashboard.is_some() {
+                status = Some(failure);
+            } else {
+                eprintln!("{}", failure);
+            }
+        }
+        match dashboard.as_mut() {
+            Some(dashboard) => dashboard.draw(&tui::Snapshot {
+                window: current.window.as_ref(),
+                tokens_per_minute: &current.tokens_per_minute,
+                options,
+                now: Utc::now(),
+                status: warning.or(status.as_deref()),
+            })?,
+            None => {
+                redraw(current.window.as_mut(), format, options)?;
+                // Text output clears the screen on every redraw, NDJSON doesn't
+                if format == OutputFormat::Text && warning.is_some() {
+                    warn_unparseable();
+                } else {
+                    unparseable.update(warning.is_some());
                 }
             }
         }
         
-        if needs_refresh || needs_full_reload {
-            // Clear screen
-            print!("\x1B[2J\x1B[1;1H");
-            
-            if needs_full_reload {
-                // Full reload on first run or periodic refresh
-                match coordinator::get_active_billing_window() {
-                    Ok(window) => {
-                        current_window = window;
-                        display::display_active_window(current_window.as_ref());
-                    }
-                    Err(e) => {
-                        eprintln!("Error loading sessions: {}", e);
-                    }
+        let result = match scheduler.next_action(&rx) {
+            events::Action::Reload(changes) => source.reload(&changes),
+            events::Action::Redraw => source.tick(),
+            events::Action::Key(key) => {
+                let outcome = dashboard.as_mut().map(|dashboard| dashboard.handle_key(key));
+                match outcome {
+                    Some(tui::KeyOutcome::Quit) => break,
+                    Some(tui::KeyOutcome::Redraw) => Ok(()),
+                    Some(tui::KeyOutcome::Ignored) | None => continue,
+                }
+            }
+            // Carry on without it
+            events::Action::DaemonGone => match WatchSource::direct(config, &tx) {
+                Ok((direct, watch_error)) => {
+                    source = direct;
+                    watch_error.map_or(Ok(()), Err)
                 }
-                needs_full_reload = false;
+                Err(e) => Err(e.context("The daemon stopped and sessions couldn't be loaded")),
+            },
+            events::Action::Shutdown => break,
+        };
+        
+        if let Err(e) = result {
+            if dashboard.is_some() {
+                status = Some(format!("{:#}", e));
             } else {
-                // Just display current window if active
-                if let Some(ref mut window) = current_window {
-                    window.is_active = types::is_block_active(window, chrono::Utc::now());
+                eprintln!("{:#}", e);
+            }
+        }
+    }
+    
+    // Leave the alternate screen before printing anything
+    let had_dashboard = dashboard.take().is_some();
+    if format == OutputFormat::Text && !had_dashboard {
+        println!("\nShutting down...");
+    }
+    Ok(())
+}
+
+/// Keep the active window in memory and serve it over a local socket until stopped.
+/// 
+/// Other clauditor runs with the same settings ask the daemon instead of
+/// scanning session files, and watch modes subscribe to its updates.
+fn run_daemon_mode(config: &config::Config) -> Result<()> {
+    let socket_path = daemon::socket_path(config)
+        .context("Cannot locate the state directory (HOME is not set)")?;
+    let (tx, rx) = mpsc::channel();
+    let (mut source, watch_error) = WatchSource::direct(config, &tx)?;
+    let watching = watch_error.is_none();
+    if let Some(e) = watch_error {
+        eprintln!("Warning: {:#}", e);
+    }
+    
+    let mut alerts = alerts::AlertEngine::new(config, alerts::state_path(config));
+    let current = source.current();
+    report_alerts(&mut alerts, current.window.as_ref());
+    let mut unparseable = UnparseableWarning::default();
+    unparseable.update(current.usage_unparseable);
+    let server = daemon::Server::bind(&socket_path, config, current)?;
+    events::forward_signals(tx)?;
+    eprintln!("Listening on {}", socket_path.display());
+    
+    let mut scheduler = events::Scheduler::new(events::DEBOUNCE, WATCH_TICK, Instant::now() + until_next_minute());
+    loop {
+        let result = match scheduler.next_action(&rx) {
+            events::Action::Reload(changes) => {
+                // History is kept current from the same changes as the window
+                if let Err(e) = server.apply(&changes) {
+                    eprintln!("Error loading changed sessions for history: {:#}", e);
                 }
-                display::display_active_window(current_window.as_ref().filter(|w| w.is_active));
+                source.reload(&changes)
             }
-            
-            needs_refresh = false;
+            events::Action::Redraw => {
+                if !watching {
+                    server.rescan_history();
+                }
+                source.tick()
+            }
+            events::Action::Key(_) | events::Action::DaemonGone => continue,
+            events::Action::Shutdown => break,
+        };
+        if let Err(e) = result {
+            eprintln!("{:#}", e);
         }
-        
-        // Sleep briefly to avoid busy waiting
-        thread::sleep(Duration::from_millis(100));
-        
-        // Force full reload every 5 seconds
-        static LAST_REFRESH: AtomicU64 = AtomicU64::new(0);
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let last = LAST_REFRESH.load(Ordering::Relaxed);
-        if now - last >= 5 {
-            needs_full_reload = true;
-            LAST_REFRESH.store(now, Ordering::Relaxed);
+        let current = source.current();
+        report_alerts(&mut alerts, current.window.as_ref());
+        unparseable.update(current.usage_unparseable);
+        server.publish(current);
+    }
+    
+    eprintln!("Shutting down...");
+    Ok(())
+}
+
+/// Check alert rules against the window, printing actions that failed
+fn report_alerts(alerts: &mut alerts::AlertEngine, window: Option<&types::SessionBlock>) {
+    alerts.evaluate(window, Utc::now());
+    for failure in alerts.take_failures() {
+        eprintln!("{}", failure);
+    }
+}
+
+/// Serve the active window as Prometheus metrics, reloading like watch mode
+fn run_serve_mode(addr: SocketAddr, config: &config::Config) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let (mut source, watch_error) = WatchSource::direct(config, &tx)?;
+    if let Some(e) = watch_error {
+        eprintln!("Warning: {:#}", e);
+    }
+    
+    let server = metrics::MetricsServer::bind(addr)?;
+    let mut alerts = alerts::AlertEngine::new(config, alerts::state_path(config));
+    let current = source.current();
+    report_alerts(&mut alerts, current.window.as_ref());
+    let mut unparseable = UnparseableWarning::default();
+    unparseable.update(current.usage_unparseable);
+    server.update(current.window, source.parse_errors());
+    events::forward_signals(tx)?;
+    eprintln!("Serving metrics on http://{}/metrics", server.local_addr());
+    
+    let mut scheduler = events::Scheduler::new(events::DEBOUNCE, WATCH_TICK, Instant::now() + until_next_minute());
+    loop {
+        let result = match scheduler.next_action(&rx) {
+            events::Action::Reload(changes) => source.reload(&changes),
+            events::Action::Redraw => source.tick(),
+            events::Action::Key(_) | events::Action::DaemonGone => continue,
+            events::Action::Shutdown => break,
+        };
+        if let Err(e) = result {
+            eprintln!("{:#}", e);
         }
+        let current = source.current();
+        report_alerts(&mut alerts, current.window.as_ref());
+        unparseable.update(current.usage_unparseable);
+        server.update(current.window, source.parse_errors());
     }
     
-    println!("\nShutting down...");
+    eprintln!("Shutting down...");
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,286 @@
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::parser::ParseErrors;
+use crate::types::{SessionBlock, TokenCounts};
+
+/// Content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Token types, as used for the `type` label
+fn token_types(counts: &TokenCounts) -> [(&'static str, u64); 4] {
+    [
+        ("input", counts.input_tokens),
+        ("output", counts.output_tokens),
+        ("cache_creation", counts.cache_creation_tokens),
+        ("cache_read", counts.cache_read_tokens),
+    ]
+}
+
+/// Escape a label value: backslash, double quote and newline
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Writes metric families in the text exposition format
+struct Exposition {
+    out: String,
+}
+
+impl Exposition {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels.iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.out, " {}", value);
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, "gauge", help);
+        self.sample(name, &[], value);
+    }
+}
+
+/// Render the active window and parse error counts as Prometheus metrics
+///
+/// Without an active window the window totals are zero and there are no
+/// per-project or per-model samples.
+pub fn render(window: Option<&SessionBlock>, parse_errors: &ParseErrors, now: DateTime<Utc>) -> String {
+    let mut exposition = Exposition { out: String::new() };
+    let out = &mut exposition;
+
+    out.gauge("clauditor_window_active", "Whether a billing window is active (1) or not (0)",
+        if window.is_some() { 1.0 } else { 0.0 });
+
+    if let Some(window) = window {
+        out.gauge("clauditor_window_start_timestamp_seconds", "Start of the active window, in seconds since the epoch",
+            window.start_time.timestamp() as f64);
+        out.gauge("clauditor_window_end_timestamp_seconds", "End of the active window, in seconds since the epoch",
+            window.end_time.timestamp() as f64);
+    }
+    let remaining = window.map_or(0, |w| (w.end_time - now).num_seconds().max(0));
+    out.gauge("clauditor_window_remaining_seconds", "Seconds until the active window ends", remaining as f64);
+
+    let totals = window.map(|w| w.token_counts.clone()).unwrap_or_default();
+    out.family("clauditor_window_tokens", "gauge", "Tokens used in the active window");
+    for (kind, count) in token_types(&totals) {
+        out.sample("clauditor_window_tokens", &[("type", kind)], count as f64);
+    }
+
+    out.family("clauditor_project_tokens", "gauge", "Tokens used in the active window per project");
+    for project in window.iter().flat_map(|w| &w.projects) {
+        for (kind, count) in token_types(&project.token_counts) {
+            out.sample("clauditor_project_tokens", &[("project", &project.name), ("type", kind)], count as f64);
+        }
+    }
+
+    out.family("clauditor_model_tokens", "gauge", "Tokens used in the active window per model");
+    for model in window.iter().flat_map(|w| &w.models) {
+        for (kind, count) in token_types(&model.token_counts) {
+            out.sample("clauditor_model_tokens", &[("model", &model.model), ("type", kind)], count as f64);
+        }
+    }
+
+    out.gauge("clauditor_window_cost_usd", "Estimated cost of the active window in US dollars",
+        window.map_or(0.0, |w| w.cost_usd));
+
+    out.family("clauditor_project_cost_usd", "gauge", "Estimated cost of the active window per project in US dollars");
+    for project in window.iter().flat_map(|w| &w.projects) {
+        out.sample("clauditor_project_cost_usd", &[("project", &project.name)], project.cost_usd);
+    }
+
+    out.family("clauditor_model_cost_usd", "gauge", "Estimated cost of the active window per model in US dollars");
+    for model in window.iter().flat_map(|w| &w.models) {
+        out.sample("clauditor_model_cost_usd", &[("model", &model.model)], model.cost_usd);
+    }
+
+    out.gauge("clauditor_burn_rate_tokens_per_minute", "Average tokens per minute since the window started",
+        window.map_or(0.0, |w| w.burn_rate()));
+    out.gauge("clauditor_cost_burn_rate_usd_per_hour", "Average cost per hour since the window started, in US dollars",
+        window.map_or(0.0, |w| w.cost_burn_rate()));
+
+    // A gauge, since the count drops when a file is rewritten or leaves the scan
+    out.family("clauditor_parse_errors", "gauge", "Session file lines in the scan that could not be read as usage entries");
+    for (kind, count) in [
+        ("malformed_json", parse_errors.malformed_json),
+        ("schema_mismatch", parse_errors.schema_mismatches),
+        ("io_error", parse_errors.io_errors),
+    ] {
+        out.sample("clauditor_parse_errors", &[("kind", kind)], count as f64);
+    }
+
+    exposition.out
+}
+
+/// What the endpoint reports at the next scrape
+#[derive(Debug, Default)]
+struct Snapshot {
+    window: Option<SessionBlock>,
+    parse_errors: ParseErrors,
+}
+
+/// Serves `GET /metrics` over HTTP from a background thread
+///
+/// The window is rendered at scrape time, so the remaining time is current
+/// even between updates.
+pub struct MetricsServer {
+    server: Arc<Server>,
+    snapshot: Arc<Mutex<Snapshot>>,
+    local_addr: SocketAddr,
+}
+
+impl MetricsServer {
+    /// Listen on `addr` and start answering scrapes
+    pub fn bind(addr: SocketAddr) -> Result<Self> {
+        let server = Server::http(addr)
+            .map_err(|e| anyhow!("Failed to listen on {}: {}", addr, e))?;
+        let local_addr = server.server_addr().to_ip().unwrap_or(addr);
+        let server = Arc::new(server);
+        let snapshot = Arc::new(Mutex::new(Snapshot::default()));
+
+        let requests = Arc::clone(&server);
+        let shared = Arc::clone(&snapshot);
+        std::thread::spawn(move || {
+            for request in requests.incoming_requests() {
+                let response = if request.url() != "/metrics" && !request.url().starts_with("/metrics?") {
+                    Response::from_string("Not found\n").with_status_code(404)
+                } else if *request.method() != Method::Get {
+                    Response::from_string("Method not allowed\n").with_status_code(405)
+                } else {
+                    let body = {
+                        let snapshot = shared.lock().unwrap();
+                        render(snapshot.window.as_ref(), &snapshot.parse_errors, Utc::now())
+                    };
+                    let content_type = Header::from_bytes("Content-Type", CONTENT_TYPE)
+                        .expect("static header is valid");
+                    Response::from_string(body).with_header(content_type)
+                };
+                let _ = request.respond(response);
+            }
+        });
+
+        Ok(Self { server, snapshot, local_addr })
+    }
+
+    /// Address the endpoint is listening on, with the actual port if 0 was requested
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Report this window and these error counts from now on
+    pub fn update(&self, window: Option<SessionBlock>, parse_errors: ParseErrors) {
+        *self.snapshot.lock().unwrap() = Snapshot { window, parse_errors };
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use chrono::{Duration, TimeZone};
+    use crate::types::{ModelUsage, ProjectUsage};
+
+    fn window() -> SessionBlock {
+        let start = Utc.with_ymd_and_hms(2025, 1, 12, 14, 0, 0).unwrap();
+        let counts = TokenCounts {
+            input_tokens: 100,
+            output_tokens: 50,
+            cache_creation_tokens: 10,
+            cache_read_tokens: 1000,
+        };
+        SessionBlock {
+            start_time: start,
+            end_time: start + Duration::hours(5),
+            last_activity: start + Duration::minutes(30),
+            projects: vec![ProjectUsage {
+                name: "my \"app\"".to_string(),
+                token_counts: counts.clone(),
+                cost_usd: 1.5,
+                entry_count: 2,
+                sessions: Vec::new(),
+            }],
+            models: vec![ModelUsage {
+                model: "claude-opus-4-20250514".to_string(),
+                token_counts: counts.clone(),
+                cost_usd: 1.5,
+                entry_count: 2,
+            }],
+            token_counts: counts,
+            cost_usd: 1.5,
+            is_active: true,
+        }
+    }
+
+    fn get(addr: SocketAddr, method: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", method, path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_render_active_window() {
+        let window = window();
+        let errors = ParseErrors { malformed_json: 3, ..ParseErrors::default() };
+        let text = render(Some(&window), &errors, window.start_time + Duration::hours(1));
+
+        assert!(text.contains("# TYPE clauditor_window_tokens gauge\n"));
+        assert!(text.contains("clauditor_window_active 1\n"));
+        assert!(text.contains("clauditor_window_start_timestamp_seconds 1736690400\n"));
+        assert!(text.contains("clauditor_window_remaining_seconds 14400\n"));
+        assert!(text.contains("clauditor_window_tokens{type=\"cache_read\"} 1000\n"));
+        assert!(text.contains("clauditor_project_tokens{project=\"my \\\"app\\\"\",type=\"input\"} 100\n"));
+        assert!(text.contains("clauditor_model_cost_usd{model=\"claude-opus-4-20250514\"} 1.5\n"));
+        assert!(text.contains("clauditor_burn_rate_tokens_per_minute 38.666666666666664\n"));
+        assert!(text.contains("# TYPE clauditor_parse_errors gauge\n"));
+        assert!(text.contains("clauditor_parse_errors{kind=\"malformed_json\"} 3\n"));
+    }
+
+    #[test]
+    fn test_render_without_window() {
+        let text = render(None, &ParseErrors::default(), Utc::now());
+        assert!(text.contains("clauditor_window_active 0\n"));
+        assert!(text.contains("clauditor_window_tokens{type=\"input\"} 0\n"));
+        assert!(!text.contains("clauditor_window_start_timestamp_seconds"));
+        assert!(!text.contains("clauditor_project_tokens{"));
+    }
+
+    #[test]
+    fn test_http_endpoint() {
+        let server = MetricsServer::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = server.local_addr();
+
+        let response = get(addr, "GET", "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.contains(CONTENT_TYPE));
+        assert!(response.contains("clauditor_window_active 0\n"));
+
+        server.update(Some(window()), ParseErrors::default());
+        assert!(get(addr, "GET", "/metrics").contains("clauditor_window_active 1\n"));
+
+        assert!(get(addr, "GET", "/").starts_with("HTTP/1.1 404"));
+        assert!(get(addr, "POST", "/metrics").starts_with("HTTP/1.1 405"));
+    }
+}
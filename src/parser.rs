@@ -1,150 +1,303 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 
+use crate::schema::parse_entry;
 use crate::types::UsageEntry;
 
+/// Most example issues kept per category in a file's diagnostics
+const MAX_ISSUE_EXAMPLES: usize = 5;
+
+/// What a single JSONL line turned out to contain
+#[derive(Debug)]
+pub enum LineOutcome {
+    /// A usage entry
+    Entry(Box<UsageEntry>),
+    /// Whitespace only
+    Blank,
+    /// Valid JSON with no `message.usage`, e.g. a user message or summary
+    NoUsage,
+    /// Not valid JSON
+    Malformed(String),
+    /// Has `message.usage` but doesn't match the entry schema
+    SchemaMismatch(String),
+}
+
+/// Classify a single JSONL line
+pub fn parse_line_detailed(line: &str) -> LineOutcome {
+    if line.trim().is_empty() {
+        return LineOutcome::Blank;
+    }
+    
+    let value: serde_json::Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => return LineOutcome::Malformed(e.to_string()),
+    };
+    
+    if value.pointer("/message/usage").is_none_or(|usage| usage.is_null()) {
+        return LineOutcome::NoUsage;
+    }
+    
+    match parse_entry(value) {
+        Ok(entry) => LineOutcome::Entry(Box::new(entry)),
+        Err(e) => LineOutcome::SchemaMismatch(e.to_string()),
+    }
+}
+
 /// Parse a single JSONL line into a UsageEntry
+#[allow(dead_code)]
 pub fn parse_line(line: &str) -> Option<UsageEntry> {
-    // Skip empty lines
-    if line.trim().is_empty() {
-        return None;
+    match parse_line_detailed(line) {
+        LineOutcome::Entry(entry) => Some(*entry),
+        _ => None,
+    }
+}
+
+/// A problem on a specific line of a session file
+#[derive(Debug, Clone, Serialize)]
+pub struct LineIssue {
+    /// 1-based line number, or 0 if the file couldn't be opened at all
+    pub line: usize,
+    pub message: String,
+}
+
+/// Count and example lines for one kind of problem
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IssueCount {
+    pub count: usize,
+    /// The first few occurrences
+    pub examples: Vec<LineIssue>,
+}
+
+impl IssueCount {
+    fn record(&mut self, line: usize, message: String) {
+        self.count += 1;
+        if self.examples.len() < MAX_ISSUE_EXAMPLES {
+            self.examples.push(LineIssue { line, message });
+        }
+    }
+}
+
+/// What parsing a session file found, line by line
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FileDiagnostics {
+    pub path: PathBuf,
+    pub lines: usize,
+    pub entries: usize,
+    /// Lines without usage data; normal for user messages and summaries
+    pub no_usage: usize,
+    pub malformed_json: IssueCount,
+    pub schema_mismatches: IssueCount,
+    pub io_errors: IssueCount,
+}
+
+impl FileDiagnostics {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            ..Self::default()
+        }
+    }
+    
+    /// True if any line was lost to a problem rather than skipped by design
+    pub fn has_problems(&self) -> bool {
+        self.malformed_json.count + self.schema_mismatches.count + self.io_errors.count > 0
     }
     
-    // Try to parse the JSON
-    match serde_json::from_str::<UsageEntry>(line) {
-        Ok(entry) => {
-            // Only return entries that have usage data
-            if entry.message.usage.is_some() {
-                Some(entry)
-            } else {
-                None
+    /// Record a line's outcome, returning the entry if there is one
+    fn record(&mut self, line: usize, outcome: LineOutcome) -> Option<UsageEntry> {
+        match outcome {
+            LineOutcome::Entry(entry) => {
+                self.entries += 1;
+                return Some(*entry);
             }
+            LineOutcome::Blank => {}
+            LineOutcome::NoUsage => self.no_usage += 1,
+            LineOutcome::Malformed(message) => self.malformed_json.record(line, message),
+            LineOutcome::SchemaMismatch(message) => self.schema_mismatches.record(line, message),
         }
-        Err(_) => {
-            // Silently skip malformed lines
-            None
+        None
+    }
+}
+
+/// Lines of session files that should have been read but couldn't be
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseErrors {
+    pub malformed_json: usize,
+    pub schema_mismatches: usize,
+    pub io_errors: usize,
+}
+
+impl ParseErrors {
+    pub fn add(&mut self, other: &ParseErrors) {
+        self.malformed_json += other.malformed_json;
+        self.schema_mismatches += other.schema_mismatches;
+        self.io_errors += other.io_errors;
+    }
+}
+
+impl From<&FileDiagnostics> for ParseErrors {
+    fn from(diagnostics: &FileDiagnostics) -> Self {
+        Self {
+            malformed_json: diagnostics.malformed_json.count,
+            schema_mismatches: diagnostics.schema_mismatches.count,
+            io_errors: diagnostics.io_errors.count,
         }
     }
 }
 
 /// Parse a JSONL file and return all valid usage entries
+#[allow(dead_code)]
 pub fn parse_file(path: &Path) -> Result<Vec<UsageEntry>> {
+    parse_file_with_diagnostics(path).map(|(entries, _)| entries)
+}
+
+/// Parse a JSONL file, recording every line that didn't yield an entry
+pub fn parse_file_with_diagnostics(path: &Path) -> Result<(Vec<UsageEntry>, FileDiagnostics)> {
     let file = File::open(path)
         .with_context(|| format!("Failed to open file: {}", path.display()))?;
     
     let reader = BufReader::new(file);
     let mut entries = Vec::new();
+    let mut diagnostics = FileDiagnostics::new(path);
     
     for (line_num, line) in reader.lines().enumerate() {
+        diagnostics.lines += 1;
         match line {
             Ok(line_content) => {
-                if let Some(entry) = parse_line(&line_content) {
+                if let Some(entry) = diagnostics.record(line_num + 1, parse_line_detailed(&line_content)) {
                     entries.push(entry);
                 }
-                // Silently skip lines without usage data or malformed lines
-            }
-            Err(e) => {
-                // Log error but continue processing
-                eprintln!("Error reading line {} in {}: {}", 
-                    line_num + 1, path.display(), e);
             }
+            Err(e) => diagnostics.io_errors.record(line_num + 1, e.to_string()),
         }
     }
     
-    Ok(entries)
+    Ok((entries, diagnostics))
 }
 
-/// Parse a JSONL file starting from a specific position
+/// Parse a JSONL file starting from a specific byte offset
+///
+/// Only complete, newline-terminated lines are consumed. The returned offset
+/// is just past the last newline read, so a line Claude Code is still writing
+/// is left for the next call instead of being dropped.
+#[allow(dead_code)]
 pub fn parse_file_from_position(path: &Path, start_position: u64) -> Result<(Vec<UsageEntry>, u64)> {
+    parse_file_from_position_with_diagnostics(path, start_position, 0)
+        .map(|(entries, offset, _)| (entries, offset))
+}
+
+/// Like `parse_file_from_position`, also recording every line that didn't yield an entry
+///
+/// `start_line` is the number of lines before `start_position`, so issues are
+/// numbered from the start of the file; the returned `lines` counts from there
+/// too, ready to be passed back as the next call's `start_line`.
+pub fn parse_file_from_position_with_diagnostics(
+    path: &Path,
+    start_position: u64,
+    start_line: usize,
+) -> Result<(Vec<UsageEntry>, u64, FileDiagnostics)> {
     let mut file = File::open(path)
         .with_context(|| format!("Failed to open file: {}", path.display()))?;
     
-    // Get current file size
-    let file_size = file.metadata()?.len();
-    
     // If start position is beyond file size, file was likely replaced
-    if start_position > file_size {
-        // Read entire file from beginning
-        return parse_file_with_position(path);
-    }
+    let file_size = file.metadata()?.len();
+    let (start_position, start_line) = if start_position > file_size { (0, 0) } else { (start_position, start_line) };
     
-    // Seek to the start position
     file.seek(SeekFrom::Start(start_position))?;
     
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
     let mut entries = Vec::new();
+    let mut diagnostics = FileDiagnostics::new(path);
+    diagnostics.lines = start_line;
     let mut current_position = start_position;
+    let mut record = Vec::new();
     
-    // Read lines from the current position
-    for line in reader.lines() {
-        match line {
-            Ok(line_content) => {
-                current_position += line_content.len() as u64 + 1; // +1 for newline
+    loop {
+        match read_record(&mut reader, &mut record) {
+            Ok(Some(consumed)) => {
+                current_position += consumed;
+                diagnostics.lines += 1;
+                let line_num = diagnostics.lines;
                 
-                if let Some(entry) = parse_line(&line_content) {
+                // Invalid UTF-8 can't be a usage entry, but the bytes are still consumed
+                let Ok(line) = std::str::from_utf8(&record) else {
+                    diagnostics.malformed_json.record(line_num, "invalid UTF-8".to_string());
+                    continue;
+                };
+                if let Some(entry) = diagnostics.record(line_num, parse_line_detailed(line)) {
                     entries.push(entry);
                 }
             }
+            Ok(None) => break,
             Err(e) => {
-                eprintln!("Error reading line in {}: {}", path.display(), e);
+                diagnostics.io_errors.record(diagnostics.lines + 1, e.to_string());
                 break;
             }
         }
     }
     
-    Ok((entries, current_position))
+    Ok((entries, current_position, diagnostics))
 }
 
-/// Parse entire file and return entries with final position
+/// Parse entire file and return entries with the offset of the last complete line
+#[allow(dead_code)]
 pub fn parse_file_with_position(path: &Path) -> Result<(Vec<UsageEntry>, u64)> {
-    let file = File::open(path)
-        .with_context(|| format!("Failed to open file: {}", path.display()))?;
-    
-    let file_size = file.metadata()?.len();
-    let reader = BufReader::new(file);
-    let mut entries = Vec::new();
-    
-    for line in reader.lines() {
-        match line {
-            Ok(line_content) => {
-                if let Some(entry) = parse_line(&line_content) {
-                    entries.push(entry);
-                }
-            }
-            Err(e) => {
-                eprintln!("Error reading line in {}: {}", path.display(), e);
-                break;
-            }
-        }
+    parse_file_from_position(path, 0)
+}
+
+/// Read the next newline-terminated record into `record`, without its line ending
+///
+/// Returns the number of bytes consumed including the `\n` or `\r\n`, or `None`
+/// at end of input or when only a partial line remains.
+fn read_record<R: BufRead>(reader: &mut R, record: &mut Vec<u8>) -> std::io::Result<Option<u64>> {
+    record.clear();
+    let consumed = reader.read_until(b'\n', record)?;
+    if record.last() != Some(&b'\n') {
+        return Ok(None);
     }
     
-    Ok((entries, file_size))
+    record.pop();
+    if record.last() == Some(&b'\r') {
+        record.pop();
+    }
+    Ok(Some(consumed as u64))
 }
 
-/// Parse multiple JSONL files and return all entries
+/// Parse multiple JSONL files and return all entries, with each file's diagnostics
+/// 
+/// A file that can't be opened is recorded as an I/O error on line 0 rather
+/// than stopping the other files.
 #[allow(dead_code)]
-pub fn parse_files(paths: &[&Path]) -> Result<Vec<UsageEntry>> {
+pub fn parse_files(paths: &[&Path]) -> (Vec<UsageEntry>, Vec<FileDiagnostics>) {
     let mut all_entries = Vec::new();
+    let mut all_diagnostics = Vec::new();
     
     for path in paths {
-        match parse_file(path) {
-            Ok(mut entries) => all_entries.append(&mut entries),
+        match parse_file_with_diagnostics(path) {
+            Ok((mut entries, diagnostics)) => {
+                all_entries.append(&mut entries);
+                all_diagnostics.push(diagnostics);
+            }
             Err(e) => {
-                // Log error but continue with other files
-                eprintln!("Error parsing file {}: {}", path.display(), e);
+                let mut diagnostics = FileDiagnostics::new(path);
+                diagnostics.io_errors.record(0, format!("{:#}", e));
+                all_diagnostics.push(diagnostics);
             }
         }
     }
     
-    Ok(all_entries)
+    (all_entries, all_diagnostics)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Write;
     use std::path::PathBuf;
+    use crate::test_support::usage_line;
     
     #[test]
     fn test_parse_valid_line() {
@@ -237,6 +390,157 @@ mod tests {
         assert_eq!(usage.cache_read_input_tokens, 0); // Default value
     }
     
+    #[test]
+    fn test_parse_line_detailed() {
+        assert!(matches!(parse_line_detailed("  "), LineOutcome::Blank));
+        assert!(matches!(parse_line_detailed("{oops"), LineOutcome::Malformed(_)));
+        assert!(matches!(
+            parse_line_detailed(r#"{"type": "user", "message": {"role": "user", "content": "hi"}}"#),
+            LineOutcome::NoUsage
+        ));
+        
+        // Usage present but the timestamp is missing
+        let outcome = parse_line_detailed(r#"{
+            "message": {
+                "id": "msg_1", "type": "message", "role": "assistant", "model": "claude-opus-4-20250514",
+                "usage": {"input_tokens": 1, "output_tokens": 2}
+            },
+            "requestId": "req_1",
+            "version": "1.0.51"
+        }"#);
+        match outcome {
+            LineOutcome::SchemaMismatch(message) => assert!(message.contains("timestamp"), "{}", message),
+            other => panic!("expected schema mismatch, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_parse_file_with_diagnostics() {
+        let (entries, diagnostics) = parse_file_with_diagnostics(Path::new("test_data/sample.jsonl"))
+            .expect("Should parse test file");
+        
+        assert_eq!(entries.len(), 4);
+        assert_eq!(diagnostics.lines, 7);
+        assert_eq!(diagnostics.entries, 4);
+        assert_eq!(diagnostics.no_usage, 1);
+        assert_eq!(diagnostics.malformed_json.count, 1);
+        assert_eq!(diagnostics.malformed_json.examples[0].line, 4);
+        assert!(diagnostics.has_problems());
+    }
+    
+    #[test]
+    fn test_incremental_read_leaves_partial_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let first = format!("{}\n", usage_line("msg_1"));
+        let second = usage_line("msg_2");
+        let (head, tail) = second.split_at(40);
+        std::fs::write(&path, format!("{}{}", first, head)).unwrap();
+        
+        let (entries, position) = parse_file_from_position(&path, 0).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(position, first.len() as u64);
+        
+        // The writer finishes the line; the next read picks it up whole
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "{}", tail).unwrap();
+        
+        let (entries, position) = parse_file_from_position(&path, position).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].message.id, "msg_2");
+        assert_eq!(position, std::fs::metadata(&path).unwrap().len());
+    }
+    
+    #[test]
+    fn test_incremental_read_crlf() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        std::fs::write(&path, format!("{}\r\n{}\r\n", usage_line("msg_1"), usage_line("msg_2"))).unwrap();
+        
+        let (entries, position) = parse_file_from_position(&path, 0).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(position, std::fs::metadata(&path).unwrap().len());
+        
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{}\r\n", usage_line("msg_3")).unwrap();
+        
+        let (entries, _) = parse_file_from_position(&path, position).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].message.id, "msg_3");
+    }
+    
+    #[test]
+    fn test_incremental_read_skips_invalid_utf8() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let mut contents = b"\xff\xfe garbage\n".to_vec();
+        contents.extend(format!("{}\n", usage_line("msg_1")).bytes());
+        std::fs::write(&path, &contents).unwrap();
+        
+        let (entries, position) = parse_file_from_position(&path, 0).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(position, contents.len() as u64);
+    }
+
+    #[test]
+    fn test_incremental_read_counts_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let mut contents = b"\xff\xfe garbage\n{oops\n".to_vec();
+        contents.extend(br#"{"type":"user","message":{"role":"user"}}"#);
+        contents.extend(b"\n");
+        contents.extend(br#"{"timestamp":"2025-01-12T14:00:00Z","message":{"usage":{"input_tokens":"lots"}}}"#);
+        contents.extend(format!("\n{}\n", usage_line("msg_1")).bytes());
+        std::fs::write(&path, &contents).unwrap();
+
+        let (entries, _, diagnostics) = parse_file_from_position_with_diagnostics(&path, 0, 0).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            ParseErrors::from(&diagnostics),
+            ParseErrors { malformed_json: 2, schema_mismatches: 1, io_errors: 0 }
+        );
+        assert_eq!(diagnostics.malformed_json.examples[1].line, 2);
+        assert_eq!(diagnostics.schema_mismatches.examples[0].line, 4);
+    }
+    
+    #[test]
+    fn test_incremental_read_numbers_lines_from_the_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        std::fs::write(&path, format!("{}\n{}\n{{oops\n", usage_line("msg_1"), usage_line("msg_2"))).unwrap();
+        let first_line_end = usage_line("msg_1").len() as u64 + 1;
+        
+        let (entries, _, diagnostics) = parse_file_from_position_with_diagnostics(&path, first_line_end, 1).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(diagnostics.lines, 3);
+        assert_eq!(diagnostics.malformed_json.examples[0].line, 3);
+    }
+    
+    #[test]
+    fn test_parse_files_records_unreadable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        std::fs::write(&path, format!("{}\n", usage_line("msg_1"))).unwrap();
+        let missing = dir.path().join("missing.jsonl");
+        
+        let (entries, diagnostics) = parse_files(&[&path, &missing]);
+        assert_eq!(entries.len(), 1);
+        assert!(!diagnostics[0].has_problems());
+        assert_eq!(diagnostics[1].io_errors.count, 1);
+        assert_eq!(diagnostics[1].io_errors.examples[0].line, 0);
+    }
+    
+    #[test]
+    fn test_position_past_end_rereads_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        std::fs::write(&path, format!("{}\n", usage_line("msg_1"))).unwrap();
+        
+        let (entries, position) = parse_file_from_position(&path, 1_000_000).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(position, std::fs::metadata(&path).unwrap().len());
+    }
+    
     #[test]
     fn test_parse_file() {
         let test_file = PathBuf::from("test_data/sample.jsonl");
@@ -0,0 +1,209 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::types::{SessionBlock, TokenCounts};
+
+/// Subscription plan whose per-window token limit usage is tracked against
+///
+/// Anthropic doesn't publish exact limits; the presets are the commonly cited
+/// community estimates. Use a custom limit when you know better.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Plan {
+    Pro,
+    Max5,
+    Max20,
+    Custom(u64),
+}
+
+impl Plan {
+    /// Tokens allowed per 5-hour billing window
+    pub fn token_limit(&self) -> u64 {
+        match self {
+            Plan::Pro => 19_000,
+            Plan::Max5 => 88_000,
+            Plan::Max20 => 220_000,
+            Plan::Custom(limit) => *limit,
+        }
+    }
+
+    /// Short plan name for display (`custom` for custom limits)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Plan::Pro => "pro",
+            Plan::Max5 => "max5",
+            Plan::Max20 => "max20",
+            Plan::Custom(_) => "custom",
+        }
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Plan::Pro => write!(f, "pro"),
+            Plan::Max5 => write!(f, "max5"),
+            Plan::Max20 => write!(f, "max20"),
+            Plan::Custom(limit) => write!(f, "{}", limit),
+        }
+    }
+}
+
+impl FromStr for Plan {
+    type Err = String;
+
+    /// Parse `pro`, `max5`, `max20`, or a custom token limit such as `500000`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pro" => Ok(Plan::Pro),
+            "max5" => Ok(Plan::Max5),
+            "max20" => Ok(Plan::Max20),
+            other => match other.replace('_', "").parse::<u64>() {
+                Ok(0) => Err("custom token limit must be greater than zero".to_string()),
+                Ok(limit) => Ok(Plan::Custom(limit)),
+                Err(_) => Err(format!(
+                    "unknown plan '{}' (expected pro, max5, max20 or a token limit)",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// How a billing window stands against a plan's token limit
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitStatus {
+    pub plan: String,
+    pub token_limit: u64,
+    pub tokens_used: u64,
+    pub tokens_remaining: u64,
+    pub percent_used: f64,
+    /// When the limit will be hit at the current burn rate, if usage is ongoing
+    pub projected_exhaustion: Option<DateTime<Utc>>,
+    /// True if the projection lands before the window resets
+    pub exhausts_before_reset: bool,
+}
+
+impl LimitStatus {
+    /// Check a window against a plan
+    ///
+    /// Limits count input and output tokens only; cache tokens are excluded,
+    /// matching how the plan presets are estimated. The burn rate used for the
+    /// projection is measured the same way as `SessionBlock::burn_rate`, from
+    /// window start to last activity.
+    pub fn check(window: &SessionBlock, plan: Plan, now: DateTime<Utc>) -> Self {
+        let token_limit = plan.token_limit();
+        let tokens_used = limited_tokens(&window.token_counts);
+        let tokens_remaining = token_limit.saturating_sub(tokens_used);
+
+        let elapsed_minutes = (window.last_activity - window.start_time).num_seconds() as f64 / 60.0;
+        let rate = if elapsed_minutes > 0.0 {
+            tokens_used as f64 / elapsed_minutes
+        } else {
+            0.0
+        };
+
+        let projected_exhaustion = if tokens_remaining == 0 {
+            Some(now)
+        } else if rate > 0.0 {
+            let seconds = (tokens_remaining as f64 / rate * 60.0).ceil() as i64;
+            Some(now + Duration::seconds(seconds))
+        } else {
+            None
+        };
+
+        Self {
+            plan: plan.name().to_string(),
+            token_limit,
+            tokens_used,
+            tokens_remaining,
+            percent_used: tokens_used as f64 / token_limit as f64 * 100.0,
+            projected_exhaustion,
+            exhausts_before_reset: projected_exhaustion.is_some_and(|t| t < window.end_time),
+        }
+    }
+
+    /// True once usage has reached the limit
+    pub fn is_exhausted(&self) -> bool {
+        self.tokens_remaining == 0
+    }
+}
+
+/// Tokens that count toward a plan limit
+fn limited_tokens(counts: &TokenCounts) -> u64 {
+    counts.input_tokens + counts.output_tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(input_tokens: u64, cache_read_tokens: u64) -> SessionBlock {
+        SessionBlock {
+            start_time: "2025-01-13T14:00:00Z".parse().unwrap(),
+            end_time: "2025-01-13T19:00:00Z".parse().unwrap(),
+            last_activity: "2025-01-13T15:00:00Z".parse().unwrap(),
+            projects: vec![],
+            models: vec![],
+            token_counts: TokenCounts {
+                input_tokens,
+                output_tokens: 0,
+                cache_creation_tokens: 0,
+                cache_read_tokens,
+            },
+            cost_usd: 0.0,
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn test_parse_plan() {
+        assert_eq!("pro".parse::<Plan>(), Ok(Plan::Pro));
+        assert_eq!("Max5".parse::<Plan>(), Ok(Plan::Max5));
+        assert_eq!("max20".parse::<Plan>(), Ok(Plan::Max20));
+        assert_eq!("500000".parse::<Plan>(), Ok(Plan::Custom(500_000)));
+        assert_eq!("500_000".parse::<Plan>(), Ok(Plan::Custom(500_000)));
+        assert!("0".parse::<Plan>().is_err());
+        assert!("enterprise".parse::<Plan>().is_err());
+    }
+
+    #[test]
+    fn test_limit_status_projects_exhaustion() {
+        // 60,000 tokens in the first hour = 1,000 tokens/min; cache reads don't count
+        let window = window(60_000, 5_000_000);
+        let now: DateTime<Utc> = "2025-01-13T15:00:00Z".parse().unwrap();
+
+        let status = LimitStatus::check(&window, Plan::Max5, now);
+        assert_eq!(status.tokens_used, 60_000);
+        assert_eq!(status.tokens_remaining, 28_000);
+        assert!((status.percent_used - 68.18).abs() < 0.01);
+        assert_eq!(status.projected_exhaustion, Some(now + Duration::minutes(28)));
+        assert!(status.exhausts_before_reset);
+    }
+
+    #[test]
+    fn test_limit_status_within_limit() {
+        let window = window(60_000, 0);
+        let now: DateTime<Utc> = "2025-01-13T15:00:00Z".parse().unwrap();
+
+        // 440,000 tokens left at 1,000 tokens/min lasts past the 19:00 reset
+        let status = LimitStatus::check(&window, Plan::Custom(500_000), now);
+        assert_eq!(status.plan, "custom");
+        assert!(!status.exhausts_before_reset);
+        assert!(!status.is_exhausted());
+    }
+
+    #[test]
+    fn test_limit_status_exhausted() {
+        let window = window(25_000, 0);
+        let now: DateTime<Utc> = "2025-01-13T15:00:00Z".parse().unwrap();
+
+        let status = LimitStatus::check(&window, Plan::Pro, now);
+        assert_eq!(status.tokens_remaining, 0);
+        assert!(status.is_exhausted());
+        assert_eq!(status.projected_exhaustion, Some(now));
+        assert!(status.exhausts_before_reset);
+    }
+}
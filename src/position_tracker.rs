@@ -1,195 +1,199 @@
-use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::path::{Path, PathBuf};
-use anyhow::{Context, Result};
+use std::io::Read;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
 use serde::{Deserialize, Serialize};
 
-/// Tracks last read positions for JSONL files
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FilePositionTracker {
-    positions: HashMap<String, u64>,
-    cache_file: PathBuf,
+/// Bytes at the start of a file that are hashed to recognize it
+const HEAD_BYTES: u64 = 4096;
+
+/// A read offset and the identity of the file it belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilePosition {
+    pub offset: u64,
+    /// `None` if the file couldn't be inspected when the offset was stored
+    pub fingerprint: Option<FileFingerprint>,
 }
 
-impl FilePositionTracker {
-    /// Create a new position tracker with default cache location
-    pub fn new() -> Self {
-        let cache_dir = std::env::temp_dir();
-        let cache_file = cache_dir.join("clauditor_positions.json");
-        
-        let mut tracker = Self {
-            positions: HashMap::new(),
-            cache_file,
-        };
-        
-        // Load existing positions if available
-        let _ = tracker.load();
-        tracker
-    }
-    
-    /// Get the last read position for a file
-    pub fn get_position(&self, path: &Path) -> u64 {
-        let path_str = path.to_string_lossy().to_string();
-        self.positions.get(&path_str).copied().unwrap_or(0)
-    }
-    
-    /// Update the position for a file
-    pub fn set_position(&mut self, path: &Path, position: u64) {
-        let path_str = path.to_string_lossy().to_string();
-        self.positions.insert(path_str, position);
-    }
-    
-    /// Check if file has been truncated or replaced
-    #[allow(dead_code)]
-    pub fn validate_position(&self, path: &Path, current_size: u64) -> u64 {
-        let stored_position = self.get_position(path);
-        
-        // If stored position is beyond current file size, file was truncated/replaced
-        if stored_position > current_size {
-            0
-        } else {
-            stored_position
-        }
-    }
-    
-    /// Save positions to cache file
-    pub fn save(&self) -> Result<()> {
-        let file = File::create(&self.cache_file)
-            .context("Failed to create position cache file")?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, &self.positions)
-            .context("Failed to write position cache")?;
-        Ok(())
-    }
-    
-    /// Load positions from cache file
-    fn load(&mut self) -> Result<()> {
-        if !self.cache_file.exists() {
-            return Ok(());
+/// What identifies a file across polls, so a replaced file isn't resumed mid-way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    /// Device and inode, on platforms that have them
+    pub dev: Option<u64>,
+    pub inode: Option<u64>,
+    /// Modification time in nanoseconds since the epoch
+    pub mtime_ns: Option<u128>,
+    /// Number of leading bytes covered by `head_hash`
+    pub head_len: u64,
+    /// FNV-1a hash of the first `head_len` bytes
+    pub head_hash: u64,
+}
+
+impl FilePosition {
+    /// Record `offset` for `path`, fingerprinting the file as it is now
+    pub fn capture(path: &Path, offset: u64) -> Self {
+        Self {
+            offset,
+            fingerprint: FileFingerprint::read(path, offset).ok(),
         }
-        
-        let file = File::open(&self.cache_file)
-            .context("Failed to open position cache file")?;
-        let reader = BufReader::new(file);
-        self.positions = serde_json::from_reader(reader)
-            .context("Failed to read position cache")?;
-        Ok(())
     }
     
-    /// Clean up stale entries (files that no longer exist)
-    pub fn cleanup(&mut self) {
-        let paths_to_remove: Vec<String> = self.positions
-            .keys()
-            .filter(|path_str| !Path::new(path_str).exists())
-            .cloned()
-            .collect();
+    /// Offset to resume reading `path` from
+    ///
+    /// Returns 0 if the file was truncated, or replaced by a different file
+    /// (different inode, older mtime or different leading bytes), so it's
+    /// read again from the start.
+    pub fn resume_offset(&self, path: &Path) -> u64 {
+        let Some(fingerprint) = &self.fingerprint else {
+            return 0;
+        };
         
-        for path in paths_to_remove {
-            self.positions.remove(&path);
+        match (FileFingerprint::read(path, self.offset), std::fs::metadata(path)) {
+            (Ok(current), Ok(metadata))
+                if metadata.len() >= self.offset && fingerprint.matches(&current) =>
+            {
+                self.offset
+            }
+            _ => 0,
         }
     }
 }
 
-impl Drop for FilePositionTracker {
-    fn drop(&mut self) {
-        // Save positions when tracker is dropped
-        let _ = self.save();
+impl FileFingerprint {
+    /// Fingerprint `path`, hashing up to `HEAD_BYTES` but no further than `offset`
+    ///
+    /// Bytes past the read offset may still be being written, so they aren't hashed.
+    pub fn read(path: &Path, offset: u64) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let (dev, inode) = file_id(&metadata);
+        let mtime_ns = metadata.modified().ok()
+            .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+            .map(|since_epoch| since_epoch.as_nanos());
+
+        let mut head = Vec::new();
+        file.take(offset.min(HEAD_BYTES)).read_to_end(&mut head)?;
+
+        Ok(Self {
+            dev,
+            inode,
+            mtime_ns,
+            head_len: head.len() as u64,
+            head_hash: fnv1a(&head),
+        })
+    }
+
+    /// True if `current` looks like the same file, possibly appended to
+    ///
+    /// Appending only moves mtime forward, so an older mtime means the file
+    /// was replaced, e.g. by a copy or a restore from backup.
+    pub fn matches(&self, current: &FileFingerprint) -> bool {
+        self.dev == current.dev
+            && self.inode == current.inode
+            && self.head_len == current.head_len
+            && self.head_hash == current.head_hash
+            && match (self.mtime_ns, current.mtime_ns) {
+                (Some(stored), Some(now)) => now >= stored,
+                _ => true,
+            }
     }
 }
 
+/// Device and inode of a file
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> (Option<u64>, Option<u64>) {
+    use std::os::unix::fs::MetadataExt;
+    (Some(metadata.dev()), Some(metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> (Option<u64>, Option<u64>) {
+    (None, None)
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust releases
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
     use std::io::Write;
     use tempfile::TempDir;
     
     #[test]
-    fn test_position_tracking() {
+    fn test_resume_offset() {
         let temp_dir = TempDir::new().unwrap();
         let test_file = temp_dir.path().join("test.jsonl");
+        fs::write(&test_file, "first line\nsecond line\n").unwrap();
         
-        let mut tracker = FilePositionTracker::new();
+        let position = FilePosition::capture(&test_file, 11);
+        assert_eq!(position.resume_offset(&test_file), 11);
         
-        // Initially no position
-        assert_eq!(tracker.get_position(&test_file), 0);
+        // Appending keeps the position
+        let mut file = fs::OpenOptions::new().append(true).open(&test_file).unwrap();
+        writeln!(file, "third line").unwrap();
+        assert_eq!(position.resume_offset(&test_file), 11);
         
-        // Set position
-        tracker.set_position(&test_file, 1024);
-        assert_eq!(tracker.get_position(&test_file), 1024);
-        
-        // Update position
-        tracker.set_position(&test_file, 2048);
-        assert_eq!(tracker.get_position(&test_file), 2048);
+        // Truncation resets it
+        fs::write(&test_file, "first").unwrap();
+        assert_eq!(position.resume_offset(&test_file), 0);
     }
     
     #[test]
-    fn test_validate_position() {
+    fn test_missing_file_is_read_from_start() {
         let temp_dir = TempDir::new().unwrap();
         let test_file = temp_dir.path().join("test.jsonl");
         
-        let mut tracker = FilePositionTracker::new();
-        tracker.set_position(&test_file, 1000);
-        
-        // File size is larger than position - valid
-        assert_eq!(tracker.validate_position(&test_file, 2000), 1000);
-        
-        // File size equals position - valid
-        assert_eq!(tracker.validate_position(&test_file, 1000), 1000);
-        
-        // File size is smaller than position - file was truncated
-        assert_eq!(tracker.validate_position(&test_file, 500), 0);
+        // Captured before the file existed, so there's nothing to match
+        let position = FilePosition::capture(&test_file, 1024);
+        assert_eq!(position.fingerprint, None);
+        fs::write(&test_file, "x".repeat(2048)).unwrap();
+        assert_eq!(position.resume_offset(&test_file), 0);
     }
     
     #[test]
-    fn test_persistence() -> Result<()> {
-        let temp_dir = TempDir::new()?;
-        let cache_file = temp_dir.path().join("test_cache.json");
-        let test_path = PathBuf::from("/test/file.jsonl");
-        
-        // Create and save tracker
-        {
-            let mut tracker = FilePositionTracker {
-                positions: HashMap::new(),
-                cache_file: cache_file.clone(),
-            };
-            tracker.set_position(&test_path, 12345);
-            tracker.save()?;
-        }
+    fn test_rewritten_file_is_read_from_start() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.jsonl");
+        fs::write(&test_file, "session a line\n").unwrap();
         
-        // Load tracker
-        {
-            let mut tracker = FilePositionTracker {
-                positions: HashMap::new(),
-                cache_file,
-            };
-            tracker.load()?;
-            assert_eq!(tracker.get_position(&test_path), 12345);
-        }
+        let position = FilePosition::capture(&test_file, 15);
         
-        Ok(())
+        // Same inode, larger size, different content
+        fs::write(&test_file, "session b line\nanother line\n").unwrap();
+        assert_eq!(position.resume_offset(&test_file), 0);
     }
     
     #[test]
-    fn test_cleanup() {
+    fn test_replaced_file_is_read_from_start() {
         let temp_dir = TempDir::new().unwrap();
-        let existing_file = temp_dir.path().join("exists.jsonl");
-        let missing_file = PathBuf::from("/nonexistent/file.jsonl");
-        
-        // Create the existing file
-        File::create(&existing_file).unwrap();
+        let test_file = temp_dir.path().join("test.jsonl");
+        let replacement = temp_dir.path().join("replacement.jsonl");
+        fs::write(&test_file, "same line\n").unwrap();
         
-        let mut tracker = FilePositionTracker::new();
-        tracker.set_position(&existing_file, 100);
-        tracker.set_position(&missing_file, 200);
+        let position = FilePosition::capture(&test_file, 10);
         
-        assert_eq!(tracker.positions.len(), 2);
+        // Identical content, but a different file renamed over the original
+        fs::write(&replacement, "same line\nmore\n").unwrap();
+        fs::rename(&replacement, &test_file).unwrap();
+        assert_eq!(position.resume_offset(&test_file), 0);
+    }
+    
+    #[test]
+    fn test_older_mtime_is_read_from_start() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.jsonl");
+        fs::write(&test_file, "same line\n").unwrap();
         
-        tracker.cleanup();
+        let position = FilePosition::capture(&test_file, 10);
         
-        // Should only keep the existing file
-        assert_eq!(tracker.positions.len(), 1);
-        assert_eq!(tracker.get_position(&existing_file), 100);
-        assert_eq!(tracker.get_position(&missing_file), 0);
+        // Restored from a backup in place
+        filetime::set_file_mtime(&test_file, filetime::FileTime::from_unix_time(1_000_000, 0)).unwrap();
+        assert_eq!(position.resume_offset(&test_file), 0);
     }
-}
\ No newline at end of file
+}
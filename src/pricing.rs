@@ -0,0 +1,156 @@
+use crate::types::{TokenUsage, UsageEntry};
+
+/// Per-million-token rates for a Claude model, in USD
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_creation: f64,
+    pub cache_read: f64,
+}
+
+impl ModelPricing {
+    /// Dollar cost of a single usage record at these rates
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_creation_input_tokens as f64 * self.cache_creation
+            + usage.cache_read_input_tokens as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+const OPUS_4: ModelPricing = ModelPricing { input: 15.0, output: 75.0, cache_creation: 18.75, cache_read: 1.50 };
+const OPUS_4_5: ModelPricing = ModelPricing { input: 5.0, output: 25.0, cache_creation: 6.25, cache_read: 0.50 };
+const SONNET: ModelPricing = ModelPricing { input: 3.0, output: 15.0, cache_creation: 3.75, cache_read: 0.30 };
+const HAIKU_4_5: ModelPricing = ModelPricing { input: 1.0, output: 5.0, cache_creation: 1.25, cache_read: 0.10 };
+const HAIKU_3_5: ModelPricing = ModelPricing { input: 0.80, output: 4.0, cache_creation: 1.0, cache_read: 0.08 };
+const HAIKU_3: ModelPricing = ModelPricing { input: 0.25, output: 1.25, cache_creation: 0.30, cache_read: 0.03 };
+
+/// Model name fragments and their pricing, for families other than Opus 4
+///
+/// Checked in order, so more specific fragments must come before the
+/// families they belong to (e.g. `3-5-haiku` before `3-haiku`).
+const PRICING_TABLE: &[(&str, ModelPricing)] = &[
+    ("3-opus", OPUS_4),
+    ("sonnet-4", SONNET),
+    ("3-7-sonnet", SONNET),
+    ("3-5-sonnet", SONNET),
+    ("haiku-4-5", HAIKU_4_5),
+    ("3-5-haiku", HAIKU_3_5),
+    ("3-haiku", HAIKU_3),
+];
+
+/// Look up pricing for a model id such as `claude-opus-4-20250514`
+pub fn pricing_for_model(model: &str) -> Option<ModelPricing> {
+    opus_4_pricing(model).or_else(|| {
+        PRICING_TABLE
+            .iter()
+            .find(|(fragment, _)| model.contains(fragment))
+            .map(|(_, pricing)| *pricing)
+    })
+}
+
+/// Pricing for an Opus 4.x model, by minor version
+///
+/// Opus 4 and 4.1 kept the Claude 3 Opus rates. 4.5 cut them, and later
+/// 4.x releases are assumed to keep the cut rates rather than fall back to
+/// the old ones.
+fn opus_4_pricing(model: &str) -> Option<ModelPricing> {
+    let rest = &model[model.find("opus-4")? + "opus-4".len()..];
+    let minor = match rest.strip_prefix('-') {
+        // A minor version, unlike the release date, is at most two digits
+        Some(rest) => rest.split('-').next()
+            .filter(|part| part.len() <= 2)
+            .and_then(|part| part.parse::<u32>().ok())
+            .unwrap_or(0),
+        None if rest.is_empty() => 0,
+        // Some other model whose name merely contains `opus-4`, e.g. `opus-40`
+        None => return None,
+    };
+    Some(if minor >= 5 { OPUS_4_5 } else { OPUS_4 })
+}
+
+/// Dollar cost of an entry
+///
+/// Known models are priced from the table. For unknown models we fall back to
+/// the `costUSD` value Claude Code recorded, or zero if there is none.
+pub fn entry_cost(entry: &UsageEntry) -> f64 {
+    let usage = match &entry.message.usage {
+        Some(usage) => usage,
+        None => return 0.0,
+    };
+
+    match pricing_for_model(&entry.message.model) {
+        Some(pricing) => pricing.cost(usage),
+        None => entry.cost_usd.unwrap_or(0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Message;
+
+    fn entry(model: &str, usage: TokenUsage, cost_usd: Option<f64>) -> UsageEntry {
+        UsageEntry {
+            timestamp: "2025-01-12T14:00:00Z".parse().unwrap(),
+            message: Message {
+                id: "msg_1".to_string(),
+                msg_type: "message".to_string(),
+                role: "assistant".to_string(),
+                model: model.to_string(),
+                usage: Some(usage),
+            },
+            cost_usd,
+            request_id: "req_1".to_string(),
+            version: "1.0.51".to_string(),
+            unknown: Default::default(),
+        }
+    }
+
+    fn million_of_each() -> TokenUsage {
+        TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 1_000_000,
+            cache_creation_input_tokens: 1_000_000,
+            cache_read_input_tokens: 1_000_000,
+        }
+    }
+
+    #[test]
+    fn test_pricing_for_model() {
+        assert_eq!(pricing_for_model("claude-opus-4-20250514"), Some(OPUS_4));
+        assert_eq!(pricing_for_model("claude-opus-4-1-20250805"), Some(OPUS_4));
+        assert_eq!(pricing_for_model("claude-opus-4-5-20251101"), Some(OPUS_4_5));
+        assert_eq!(pricing_for_model("claude-opus-4-6"), Some(OPUS_4_5));
+        assert_eq!(pricing_for_model("claude-opus-4-6-20260205"), Some(OPUS_4_5));
+        assert_eq!(pricing_for_model("claude-3-opus-20240229"), Some(OPUS_4));
+        assert_eq!(pricing_for_model("claude-sonnet-4-20250514"), Some(SONNET));
+        assert_eq!(pricing_for_model("claude-3-5-haiku-20241022"), Some(HAIKU_3_5));
+        assert_eq!(pricing_for_model("<synthetic>"), None);
+    }
+
+    #[test]
+    fn test_entry_cost_uses_table() {
+        let opus = entry("claude-opus-4-20250514", million_of_each(), Some(99.0));
+        assert!((entry_cost(&opus) - (15.0 + 75.0 + 18.75 + 1.50)).abs() < 1e-9);
+
+        let sonnet = entry("claude-sonnet-4-20250514", TokenUsage {
+            input_tokens: 1000,
+            output_tokens: 500,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+        }, None);
+        assert!((entry_cost(&sonnet) - 0.0105).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_entry_cost_falls_back_to_cost_usd() {
+        let unknown = entry("some-future-model", million_of_each(), Some(0.25));
+        assert_eq!(entry_cost(&unknown), 0.25);
+
+        let unknown_without_cost = entry("some-future-model", million_of_each(), None);
+        assert_eq!(entry_cost(&unknown_without_cost), 0.0);
+    }
+}
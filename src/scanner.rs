@@ -1,39 +1,56 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use chrono::{DateTime, Duration, Utc};
 use anyhow::{Context, Result};
 
-use crate::parser::{parse_file, parse_file_from_position};
+use crate::config::Config;
+use crate::entry_cache::EntryCache;
+use crate::parser::{parse_file_with_diagnostics, FileDiagnostics, ParseErrors};
 use crate::types::{UsageEntry, SessionFile};
-use crate::position_tracker::FilePositionTracker;
 
 /// Scan for Claude Code session files
 pub struct SessionScanner {
     claude_paths: Vec<PathBuf>,
     hours_back: i64,
-    position_tracker: FilePositionTracker,
+    /// Parsed entries, kept between calls and, with a state directory, between runs
+    entry_cache: EntryCache,
+    /// Whether a full scan covers the configured window, so the cache can be pruned to it
+    prune_cache: bool,
+    /// Lines that couldn't be parsed in each file read since the last full scan, for files with any
+    parse_errors: BTreeMap<PathBuf, ParseErrors>,
+}
+
+impl Default for SessionScanner {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SessionScanner {
     /// Create a new scanner with default paths
     pub fn new() -> Self {
-        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
-        let claude_paths = vec![
-            PathBuf::from(&home).join(".claude"),
-            PathBuf::from(&home).join(".config/claude"),
-        ];
-        
+        Self::from_config(&Config::default())
+    }
+    
+    /// Create a scanner for the configured paths and lookback
+    pub fn from_config(config: &Config) -> Self {
         Self {
-            claude_paths,
-            hours_back: 10, // Default to 10 hours as per requirements
-            position_tracker: FilePositionTracker::new(),
+            claude_paths: config.claude_roots(),
+            hours_back: config.hours_back,
+            entry_cache: EntryCache::from_config(config),
+            prune_cache: true,
+            parse_errors: BTreeMap::new(),
         }
     }
     
     /// Set how many hours back to scan
-    #[allow(dead_code)]
+    /// 
+    /// Such a scan leaves the entry cache unpruned, since live views still need
+    /// the files it doesn't cover.
     pub fn with_hours_back(mut self, hours: i64) -> Self {
         self.hours_back = hours;
+        self.prune_cache = false;
         self
     }
     
@@ -58,98 +75,86 @@ impl SessionScanner {
         Ok(all_files)
     }
     
+    /// Projects directories that will be scanned, and whether each exists
+    pub fn projects_dirs(&self) -> Vec<(PathBuf, bool)> {
+        self.claude_paths.iter()
+            .map(|base| {
+                let projects_dir = base.join("projects");
+                let exists = projects_dir.is_dir();
+                (projects_dir, exists)
+            })
+            .collect()
+    }
+    
     /// Load all session data from found files
     pub fn load_sessions(&mut self) -> Result<Vec<SessionFile>> {
         let files = self.find_session_files()?;
-        let mut sessions = Vec::new();
         
-        // Clean up stale entries from position tracker
-        self.position_tracker.cleanup();
-        
-        for file_path in files {
-            // eprintln!("[DEBUG] load_sessions: Processing file: {}", file_path.display());
-            
-            // Extract project name from path
-            let project_name = extract_project_name(&file_path);
-            let session_id = extract_session_id(&file_path);
-            
-            // Parse the file
-            match parse_file(&file_path) {
-                Ok(entries) => {
-                    // eprintln!("[DEBUG] load_sessions: File {} has {} entries", file_path.display(), entries.len());
-                    
-                    if !entries.is_empty() {
-                        // Log first and last entry timestamps
-                        if let (Some(_first), Some(_last)) = (entries.first(), entries.last()) {
-                            // eprintln!("[DEBUG] load_sessions: File {} entries span {} to {}", 
-                            //     file_path.display(), first.timestamp, last.timestamp);
-                        }
-                        
-                        sessions.push(SessionFile {
-                            path: file_path.to_string_lossy().to_string(),
-                            project: project_name,
-                            session_id,
-                            last_read_position: 0, // Will be used for incremental reading
-                            entries,
-                        });
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Error parsing {}: {}", file_path.display(), e);
-                    // Continue with other files
-                }
-            }
+        // Forget files that were removed or fell out of the window
+        if self.prune_cache {
+            self.entry_cache.prune(&files);
         }
         
-        // eprintln!("[DEBUG] load_sessions: Loaded {} sessions total", sessions.len());
-        Ok(sessions)
+        self.parse_errors.clear();
+        self.load_session_files(&files)
     }
     
-    /// Load sessions incrementally, only reading new data
-    pub fn load_sessions_incremental(&mut self) -> Result<Vec<SessionFile>> {
-        let files = self.find_session_files()?;
+    /// Load specific session files, e.g. ones a file watcher reported as changed
+    /// 
+    /// Entries parsed by earlier runs or earlier calls come from the entry
+    /// cache, so only bytes appended since then are parsed. Files without any
+    /// usage entries are left out.
+    pub fn load_session_files(&mut self, files: &[PathBuf]) -> Result<Vec<SessionFile>> {
         let mut sessions = Vec::new();
-        
-        // Clean up stale entries from position tracker
-        self.position_tracker.cleanup();
+        let mut file_errors = Vec::new();
+        let cache = &mut self.entry_cache;
         
         for file_path in files {
-            // Extract project name from path
-            let project_name = extract_project_name(&file_path);
-            let session_id = extract_session_id(&file_path);
-            
-            // Get last read position
-            let last_position = self.position_tracker.get_position(&file_path);
-            
-            // Parse the file incrementally
-            match parse_file_from_position(&file_path, last_position) {
-                Ok((entries, new_position)) => {
-                    // Update position tracker
-                    self.position_tracker.set_position(&file_path, new_position);
-                    
-                    if !entries.is_empty() {
-                        sessions.push(SessionFile {
-                            path: file_path.to_string_lossy().to_string(),
-                            project: project_name,
-                            session_id,
-                            last_read_position: new_position,
-                            entries,
-                        });
+            match cache.read_session_file(file_path) {
+                Ok(session) => {
+                    file_errors.push((file_path, cache.parse_errors(file_path)));
+                    if !session.entries.is_empty() {
+                        sessions.push(session);
                     }
                 }
                 Err(e) => {
                     eprintln!("Error parsing {}: {}", file_path.display(), e);
+                    file_errors.push((file_path, ParseErrors { io_errors: 1, ..ParseErrors::default() }));
                     // Continue with other files
                 }
             }
         }
         
-        // Save position tracker state
-        let _ = self.position_tracker.save();
+        // A cache that can't be written only costs the next run some parsing
+        let _ = cache.save();
+        
+        for (file_path, errors) in file_errors {
+            if errors == ParseErrors::default() {
+                self.parse_errors.remove(file_path);
+            } else {
+                self.parse_errors.insert(file_path.clone(), errors);
+            }
+        }
         
         Ok(sessions)
     }
     
+    /// Lines that couldn't be parsed in the files loaded since the last full scan
+    /// 
+    /// A file that couldn't be opened counts as one I/O error.
+    pub fn parse_errors(&self) -> ParseErrors {
+        let mut total = ParseErrors::default();
+        for errors in self.parse_errors.values() {
+            total.add(errors);
+        }
+        total
+    }
+    
+    /// Stop counting parse errors for a file that was removed
+    pub fn forget_file(&mut self, path: &Path) {
+        self.parse_errors.remove(path);
+    }
+    
     /// Load all entries from all sessions (flattened)
     #[allow(dead_code)]
     pub fn load_all_entries(&mut self) -> Result<Vec<UsageEntry>> {
@@ -164,6 +169,19 @@ impl SessionScanner {
     }
 }
 
+/// Parse a whole session file, also reporting lines that yielded no entry
+pub fn read_session_file_with_diagnostics(path: &Path) -> Result<(SessionFile, FileDiagnostics)> {
+    let (entries, diagnostics) = parse_file_with_diagnostics(path)?;
+    let session = SessionFile {
+        path: path.to_string_lossy().to_string(),
+        project: extract_project_name(path),
+        session_id: extract_session_id(path),
+        last_read_position: 0, // Will be used for incremental reading
+        entries,
+    };
+    Ok((session, diagnostics))
+}
+
 /// Recursively find JSONL files modified after cutoff time
 fn find_jsonl_files(dir: &Path, cutoff_time: DateTime<Utc>) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
@@ -198,31 +216,43 @@ fn find_jsonl_files(dir: &Path, cutoff_time: DateTime<Utc>) -> Result<Vec<PathBu
 
 /// Extract project name from file path
 /// Path format: ~/.claude/projects/{project-name}/{session-uuid}.jsonl
-fn extract_project_name(path: &Path) -> String {
+pub(crate) fn extract_project_name(path: &Path) -> String {
     path.parent()
         .and_then(|p| p.file_name())
         .and_then(|s| s.to_str())
-        .map(|s| decode_project_name(s))
+        .map(decode_project_name)
         .unwrap_or_else(|| "unknown".to_string())
 }
 
 /// Extract session ID from file path
-fn extract_session_id(path: &Path) -> String {
+pub(crate) fn extract_session_id(path: &Path) -> String {
     path.file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("unknown")
         .to_string()
 }
 
+/// Project name for sessions started in `dir`
+///
+/// Claude Code names a project's directory after its working directory, with
+/// every character other than a letter or digit replaced by a hyphen.
+pub(crate) fn project_name_for_dir(dir: &Path) -> String {
+    let encoded: String = dir.to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    decode_project_name(&encoded)
+}
+
 /// Decode project name from file system encoding
 /// Example: -Users-phaedrus-Development-ccusage -> /Users/phaedrus/Development/ccusage
 /// Note: Double hyphens (--) in encoded names represent path separators between 
 /// components that themselves contain hyphens
 fn decode_project_name(encoded: &str) -> String {
-    if encoded.starts_with('-') {
+    if let Some(rest) = encoded.strip_prefix('-') {
         // Leading hyphen indicates absolute path
         // Simply replace all hyphens with slashes
-        format!("/{}", encoded[1..].replace('-', "/"))
+        format!("/{}", rest.replace('-', "/"))
     } else {
         encoded.replace('-', "/")
     }
@@ -234,6 +264,7 @@ mod tests {
     use std::fs::{self, File};
     use std::time::SystemTime;
     use tempfile::TempDir;
+    use crate::test_support::usage_line;
     
     #[test]
     fn test_decode_project_name() {
@@ -262,7 +293,16 @@ mod tests {
             "/Users/phaedrus/Development/ccusage"
         );
     }
-    
+
+    #[test]
+    fn test_project_name_for_dir_matches_session_directory() {
+        let path = PathBuf::from("/home/user/.claude/projects/-home-me-my-app-v1-2/session.jsonl");
+        assert_eq!(
+            project_name_for_dir(Path::new("/home/me/my_app/v1.2")),
+            extract_project_name(&path)
+        );
+    }
+
     #[test]
     fn test_find_jsonl_files() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -298,4 +338,38 @@ mod tests {
         
         Ok(())
     }
+    
+    #[test]
+    fn test_only_scans_of_the_configured_window_prune_the_cache() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path().join("projects").join("-home-me-proj");
+        fs::create_dir_all(&project_dir)?;
+        let recent_file = project_dir.join("recent.jsonl");
+        let old_file = project_dir.join("old.jsonl");
+        fs::write(&recent_file, format!("{}\n", usage_line("msg_1")))?;
+        fs::write(&old_file, format!("{}\n", usage_line("msg_2")))?;
+        let eleven_hours_ago = SystemTime::now() - std::time::Duration::from_secs(11 * 3600);
+        filetime::set_file_mtime(&old_file, filetime::FileTime::from_system_time(eleven_hours_ago))?;
+        
+        let config = Config {
+            claude_paths: vec![temp_dir.path().to_path_buf()],
+            hours_back: 10,
+            state_dir: Some(temp_dir.path().join("state")),
+            ..Config::default()
+        };
+        let records = || fs::read_dir(temp_dir.path().join("state/entries")).unwrap().count();
+        
+        // A longer scan, as history runs, caches both files
+        SessionScanner::from_config(&config).with_hours_back(24).load_sessions()?;
+        assert_eq!(records(), 2);
+        
+        // A shorter one leaves the old file's record alone
+        SessionScanner::from_config(&config).with_hours_back(1).load_sessions()?;
+        assert_eq!(records(), 2);
+        
+        SessionScanner::from_config(&config).load_sessions()?;
+        assert_eq!(records(), 1);
+        
+        Ok(())
+    }
 }
\ No newline at end of file
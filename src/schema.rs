@@ -0,0 +1,275 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+use crate::types::{Message, TokenUsage, UnknownFields, UsageEntry};
+
+/// Model name recorded for messages that don't name one
+pub const SYNTHETIC_MODEL: &str = "<synthetic>";
+
+#[derive(Deserialize)]
+struct RawEntry {
+    timestamp: DateTime<Utc>,
+    message: RawMessage,
+    #[serde(rename = "costUSD")]
+    cost_usd: Option<f64>,
+    #[serde(rename = "requestId")]
+    request_id: Option<String>,
+    version: Option<String>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct RawMessage {
+    id: Option<String>,
+    #[serde(rename = "type")]
+    msg_type: Option<String>,
+    role: Option<String>,
+    model: Option<String>,
+    usage: Option<RawUsage>,
+    /// The response text can be large and isn't needed, so it's not kept
+    #[serde(default, rename = "content", deserialize_with = "skip_value")]
+    _content: (),
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct RawUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_input_tokens: Option<u64>,
+    cache_read_input_tokens: Option<u64>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+/// Entry layouts of the Claude Code releases clauditor knows, picked by `version`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaVersion {
+    /// 0.2.x: no `requestId`, `costUSD` on every entry, cache counts optional
+    V0_2,
+    /// 1.0.0 to 1.0.39: `requestId`, message id, model and both cache counts always written
+    V1_0,
+    /// 1.0.40 and later 1.x: usage gains `service_tier`, `server_tool_use` and a
+    /// `cache_creation` breakdown, cache counts can be `null`, and synthetic
+    /// messages (API errors, interruptions) have no id, model or `requestId`
+    V1_0_40,
+    /// No `version`, or one clauditor doesn't know: accept anything a known release could write
+    Unknown,
+}
+
+impl SchemaVersion {
+    /// The layout for a `version` field such as `1.0.51`
+    pub fn detect(version: Option<&str>) -> Self {
+        let Some(version) = version else {
+            return Self::Unknown;
+        };
+        let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+        match (parts.next().flatten(), parts.next().flatten(), parts.next().flatten()) {
+            (Some(0), Some(2), _) => Self::V0_2,
+            (Some(1), Some(0), Some(patch)) if patch < 40 => Self::V1_0,
+            (Some(1), Some(_), _) => Self::V1_0_40,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Check that an entry has every field its release always writes
+    fn check(self, raw: &RawEntry) -> Result<()> {
+        let usage = raw.message.usage.as_ref();
+        let required: &[(&str, bool)] = match self {
+            Self::V0_2 => &[
+                ("costUSD", raw.cost_usd.is_some()),
+                ("message.id", raw.message.id.is_some()),
+                ("message.model", raw.message.model.is_some()),
+            ],
+            Self::V1_0 => &[
+                ("requestId", raw.request_id.is_some()),
+                ("message.id", raw.message.id.is_some()),
+                ("message.model", raw.message.model.is_some()),
+                ("cache_creation_input_tokens", usage.is_none_or(|u| u.cache_creation_input_tokens.is_some())),
+                ("cache_read_input_tokens", usage.is_none_or(|u| u.cache_read_input_tokens.is_some())),
+            ],
+            Self::V1_0_40 if !raw.is_synthetic() => &[
+                ("requestId", raw.request_id.is_some()),
+                ("message.id", raw.message.id.is_some()),
+                ("message.model", raw.message.model.is_some()),
+            ],
+            Self::V1_0_40 | Self::Unknown => &[],
+        };
+
+        if let Some((field, _)) = required.iter().find(|(_, present)| !present) {
+            bail!(
+                "missing `{}`, which Claude Code {} always writes",
+                field,
+                raw.version.as_deref().unwrap_or_default()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl RawEntry {
+    /// An API error or interruption rather than a model response
+    fn is_synthetic(&self) -> bool {
+        self.extra.get("isApiErrorMessage") == Some(&Value::Bool(true))
+            || self.message.model.as_deref() == Some(SYNTHETIC_MODEL)
+    }
+}
+
+/// Normalize a parsed JSONL line into a `UsageEntry`
+///
+/// The entry's `version` picks the layout it is checked against (see
+/// `SchemaVersion`), so a release that stops writing a field it always wrote
+/// shows up as schema mismatches in `clauditor doctor` rather than as quietly
+/// wrong numbers. Entries without a known version are accepted as long as they
+/// have a timestamp and input and output token counts.
+///
+/// Whatever the layout, missing optional fields are normalized to defaults.
+/// Fields clauditor doesn't model are kept in `UsageEntry::unknown`, which
+/// the entry cache stores and NDJSON exports carry as `extra`.
+pub fn parse_entry(value: Value) -> Result<UsageEntry> {
+    let raw: RawEntry = serde_json::from_value(value)?;
+    SchemaVersion::detect(raw.version.as_deref()).check(&raw)?;
+
+    let mut unknown = UnknownFields {
+        entry: raw.extra,
+        message: raw.message.extra,
+        usage: Map::new(),
+    };
+
+    let usage = raw.message.usage.map(|usage| {
+        unknown.usage = usage.extra;
+        TokenUsage {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_input_tokens: usage.cache_creation_input_tokens.unwrap_or(0),
+            cache_read_input_tokens: usage.cache_read_input_tokens.unwrap_or(0),
+        }
+    });
+
+    Ok(UsageEntry {
+        timestamp: raw.timestamp,
+        message: Message {
+            id: raw.message.id.unwrap_or_default(),
+            msg_type: raw.message.msg_type.unwrap_or_else(|| "message".to_string()),
+            role: raw.message.role.unwrap_or_else(|| "assistant".to_string()),
+            model: raw.message.model.unwrap_or_else(|| SYNTHETIC_MODEL.to_string()),
+            usage,
+        },
+        cost_usd: raw.cost_usd,
+        request_id: raw.request_id.unwrap_or_default(),
+        version: raw.version.unwrap_or_default(),
+        unknown,
+    })
+}
+
+fn skip_value<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<(), D::Error> {
+    IgnoredAny::deserialize(deserializer).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_missing_optional_fields_are_normalized() {
+        let entry = parse_entry(json!({
+            "timestamp": "2025-01-12T16:03:28.593Z",
+            "message": {"usage": {"input_tokens": 10, "output_tokens": 5}}
+        })).unwrap();
+
+        assert_eq!(entry.request_id, "");
+        assert_eq!(entry.version, "");
+        assert_eq!(entry.message.id, "");
+        assert_eq!(entry.message.model, SYNTHETIC_MODEL);
+        assert_eq!(entry.message.role, "assistant");
+        assert_eq!(entry.unknown, UnknownFields::default());
+    }
+
+    #[test]
+    fn test_unknown_fields_are_kept() {
+        let entry = parse_entry(json!({
+            "timestamp": "2025-01-12T16:03:28.593Z",
+            "sessionId": "abc",
+            "message": {
+                "id": "msg_1",
+                "model": "claude-sonnet-4-20250514",
+                "content": [{"type": "text", "text": "a long answer"}],
+                "stop_reason": "end_turn",
+                "usage": {
+                    "input_tokens": 10,
+                    "output_tokens": 5,
+                    "cache_read_input_tokens": null,
+                    "service_tier": "standard",
+                    "server_tool_use": {"web_search_requests": 2}
+                }
+            },
+            "requestId": "req_1"
+        })).unwrap();
+
+        assert_eq!(entry.unknown.entry["sessionId"], "abc");
+        assert_eq!(entry.unknown.message["stop_reason"], "end_turn");
+        assert!(!entry.unknown.message.contains_key("content"));
+        assert_eq!(entry.unknown.usage["service_tier"], "standard");
+        assert_eq!(entry.unknown.usage["server_tool_use"]["web_search_requests"], 2);
+        assert_eq!(entry.message.usage.unwrap().cache_read_input_tokens, 0);
+    }
+
+    #[test]
+    fn test_required_fields() {
+        let no_timestamp = json!({"message": {"usage": {"input_tokens": 1, "output_tokens": 1}}});
+        assert!(parse_entry(no_timestamp).unwrap_err().to_string().contains("timestamp"));
+
+        let no_output = json!({
+            "timestamp": "2025-01-12T16:03:28.593Z",
+            "message": {"usage": {"input_tokens": 1}}
+        });
+        assert!(parse_entry(no_output).unwrap_err().to_string().contains("output_tokens"));
+    }
+
+    #[test]
+    fn test_detect_version() {
+        assert_eq!(SchemaVersion::detect(Some("0.2.118")), SchemaVersion::V0_2);
+        assert_eq!(SchemaVersion::detect(Some("1.0.17")), SchemaVersion::V1_0);
+        assert_eq!(SchemaVersion::detect(Some("1.0.51")), SchemaVersion::V1_0_40);
+        assert_eq!(SchemaVersion::detect(Some("1.2.3")), SchemaVersion::V1_0_40);
+        assert_eq!(SchemaVersion::detect(Some("2.0.0")), SchemaVersion::Unknown);
+        assert_eq!(SchemaVersion::detect(Some("")), SchemaVersion::Unknown);
+        assert_eq!(SchemaVersion::detect(None), SchemaVersion::Unknown);
+    }
+
+    #[test]
+    fn test_each_version_requires_what_it_always_writes() {
+        let entry = |version: &str| json!({
+            "timestamp": "2025-01-12T16:03:28.593Z",
+            "version": version,
+            "message": {"id": "msg_1", "model": "claude-sonnet-4-20250514",
+                        "usage": {"input_tokens": 1, "output_tokens": 1}}
+        });
+
+        // 0.2.x priced every entry
+        let err = parse_entry(entry("0.2.118")).unwrap_err();
+        assert!(err.to_string().contains("`costUSD`"), "{}", err);
+
+        // 1.0.x before 1.0.40 always wrote the request id and cache counts
+        let err = parse_entry(entry("1.0.17")).unwrap_err();
+        assert!(err.to_string().contains("`requestId`, which Claude Code 1.0.17"), "{}", err);
+        let mut with_request = entry("1.0.17");
+        with_request["requestId"] = json!("req_1");
+        assert!(parse_entry(with_request).unwrap_err().to_string().contains("cache_creation_input_tokens"));
+
+        // Later releases only leave it off synthetic messages
+        assert!(parse_entry(entry("1.0.51")).is_err());
+        let mut synthetic = entry("1.0.51");
+        synthetic["isApiErrorMessage"] = json!(true);
+        assert!(parse_entry(synthetic).is_ok());
+
+        // An unknown release gets the benefit of the doubt
+        assert!(parse_entry(entry("2.0.0")).is_ok());
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::Config;
+use crate::coordinator::{dedup_entries, tag_with_projects};
+use crate::filter::ProjectFilter;
+use crate::history::{build_history, HistoricalWindow};
+use crate::scanner::SessionScanner;
+use crate::types::{EntryWithProject, SessionFile};
+use crate::watcher::ChangeSet;
+
+/// Session files read for history, kept in memory between queries
+///
+/// The first query scans back as far as its range needs; later ones are
+/// answered from memory unless they reach further back. `apply` keeps the
+/// sessions current from the same `ChangeSet`s as the live window.
+pub struct SessionHistory {
+    config: Config,
+    filter: ProjectFilter,
+    scanner: SessionScanner,
+    /// Files modified since then are in memory, or `None` before the first scan
+    scanned_since: Option<DateTime<Utc>>,
+    /// Keyed by path so entries come out in a stable order
+    sessions: BTreeMap<PathBuf, SessionFile>,
+    /// Entries of `sessions` with duplicates removed, rebuilt after they change
+    entries: Option<Vec<EntryWithProject>>,
+}
+
+impl SessionHistory {
+    /// Nothing is read until the first query
+    pub fn new(config: &Config) -> Result<Self> {
+        Ok(Self {
+            config: config.clone(),
+            filter: ProjectFilter::from_config(&config.filter)?,
+            scanner: SessionScanner::from_config(config),
+            scanned_since: None,
+            sessions: BTreeMap::new(),
+            entries: None,
+        })
+    }
+
+    /// Reconstruct every billing window that overlaps `[since, until)`
+    ///
+    /// Sessions are read back one extra window duration before `since` so the
+    /// first window's boundaries match what they were at the time.
+    pub fn windows(&mut self, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<HistoricalWindow>> {
+        let now = Utc::now();
+        let needed = since - self.config.session_duration();
+        if self.scanned_since.is_none_or(|scanned| scanned > needed) {
+            self.scan(now, needed)?;
+        }
+
+        let sessions = &self.sessions;
+        let mut entries = self.entries
+            .get_or_insert_with(|| dedup_entries(tag_with_projects(sessions.values().cloned())))
+            .clone();
+        if !self.config.filter.account_window {
+            entries = self.filter.apply(entries);
+        }
+
+        let filter = &self.filter;
+        Ok(build_history(entries, since, until, now, self.config.session_duration(), |e| {
+            filter.matches(&e.project)
+        }))
+    }
+
+    /// Re-read updated files and forget removed ones, once anything was scanned
+    pub fn apply(&mut self, changes: &ChangeSet) -> Result<()> {
+        if self.scanned_since.is_none() {
+            return Ok(());
+        }
+        for path in changes.removed() {
+            self.scanner.forget_file(path);
+            self.forget(path);
+        }
+
+        let updated: Vec<PathBuf> = changes.updated()
+            .filter(|path| path.is_file())
+            .map(Path::to_path_buf)
+            .collect();
+        let mut reloaded = HashSet::new();
+        for session in self.scanner.load_session_files(&updated)? {
+            let path = PathBuf::from(&session.path);
+            reloaded.insert(path.clone());
+            self.sessions.insert(path, session);
+            self.entries = None;
+        }
+
+        // Updated files that are gone or now empty
+        for path in changes.updated().filter(|path| !reloaded.contains(*path)) {
+            self.forget(path);
+        }
+        Ok(())
+    }
+
+    /// Drop everything read so far, so the next query scans again
+    ///
+    /// For when file events aren't available to keep the sessions current.
+    pub fn clear(&mut self) {
+        self.scanned_since = None;
+        self.sessions.clear();
+        self.entries = None;
+    }
+
+    /// Read every session file modified since `cutoff`
+    fn scan(&mut self, now: DateTime<Utc>, cutoff: DateTime<Utc>) -> Result<()> {
+        // Whole hours, rounded up so the scan covers the cutoff
+        let hours_back = (now - cutoff).num_hours() + 1;
+        self.scanner = SessionScanner::from_config(&self.config).with_hours_back(hours_back);
+        self.sessions = self.scanner.load_sessions()?
+            .into_iter()
+            .map(|session| (PathBuf::from(&session.path), session))
+            .collect();
+        self.scanned_since = Some(now - Duration::hours(hours_back));
+        self.entries = None;
+        Ok(())
+    }
+
+    fn forget(&mut self, path: &Path) {
+        if self.sessions.remove(path).is_some() {
+            self.entries = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::watcher::FileEvent;
+    use tempfile::TempDir;
+
+    fn usage_line(id: &str, timestamp: DateTime<Utc>) -> String {
+        crate::test_support::usage_line(id).replace("2025-01-12T16:03:28.593Z", &timestamp.to_rfc3339())
+    }
+
+    #[test]
+    fn test_queries_are_answered_from_memory() {
+        let dir = TempDir::new().unwrap();
+        let project_dir = dir.path().join("projects").join("-home-me-proj");
+        fs::create_dir_all(&project_dir).unwrap();
+        let path = project_dir.join("session.jsonl");
+        let now = Utc::now();
+        fs::write(&path, format!("{}\n", usage_line("msg_1", now - Duration::minutes(10)))).unwrap();
+
+        let config = Config {
+            claude_paths: vec![dir.path().to_path_buf()],
+            state_dir: None,
+            ..Config::default()
+        };
+        let mut history = SessionHistory::new(&config).unwrap();
+        let windows = history.windows(now - Duration::days(1), now + Duration::hours(1)).unwrap();
+        assert_eq!(windows.len(), 1);
+
+        // Until a change is reported, queries don't go back to disk
+        fs::write(&path, "").unwrap();
+        assert_eq!(history.windows(now - Duration::days(1), now + Duration::hours(1)).unwrap().len(), 1);
+
+        let mut changes = ChangeSet::default();
+        changes.add(FileEvent::Modified(path.clone()));
+        history.apply(&changes).unwrap();
+        assert!(history.windows(now - Duration::days(1), now + Duration::hours(1)).unwrap().is_empty());
+    }
+}
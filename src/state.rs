@@ -0,0 +1,248 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Version of the state file format; files written with another version are ignored
+pub const STATE_FORMAT_VERSION: u32 = 1;
+
+/// Distinguishes temporary files written concurrently by one process
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+    version: u32,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+struct VersionHeader {
+    version: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct VersionedOwned<T> {
+    data: T,
+}
+
+/// `$XDG_STATE_HOME/clauditor`, falling back to `~/.local/state/clauditor`
+pub fn default_state_dir() -> Option<PathBuf> {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+
+    Some(state_dir.join("clauditor"))
+}
+
+/// Read a state file written by `write_state`
+///
+/// Returns `None` if the file doesn't exist or was written in another format
+/// version, and an error if it can't be read or parsed.
+pub fn read_state<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+
+    let header: VersionHeader = serde_json::from_slice(&contents)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    if header.version != Some(STATE_FORMAT_VERSION) {
+        return Ok(None);
+    }
+
+    let state: VersionedOwned<T> = serde_json::from_slice(&contents)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(state.data))
+}
+
+/// Write a state file atomically, creating a private state directory if needed
+///
+/// The data goes to a temporary file that is renamed over `path`, so readers
+/// see either the old or the new contents, never a partial write.
+pub fn write_state<T: Serialize>(path: &Path, data: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        create_private_dir(dir)?;
+    }
+
+    let temp_file = path.with_extension(format!(
+        "{}.{}.tmp",
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = write_json(&temp_file, &Versioned { version: STATE_FORMAT_VERSION, data })
+        .and_then(|_| {
+            fs::rename(&temp_file, path)
+                .with_context(|| format!("Failed to replace {}", path.display()))
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_file);
+    }
+    result
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, value)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    Ok(())
+}
+
+/// Create a directory readable only by the current user
+pub(crate) fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+        .with_context(|| format!("Failed to create state directory: {}", dir.display()))
+}
+
+/// An exclusive lock on a state file, held until dropped
+///
+/// Lets concurrent clauditor instances read, merge and write a state file
+/// without losing each other's updates. The lock is taken on a `.lock` file
+/// next to the state file, since the state file itself is replaced on write:
+/// with `flock` on Unix and `LockFileEx` on Windows.
+pub struct StateLock {
+    _file: File,
+}
+
+impl StateLock {
+    /// Block until the lock for `path` is acquired
+    pub fn acquire(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            create_private_dir(dir)?;
+        }
+        let lock_path = path.with_extension("lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open lock file: {}", lock_path.display()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+                return Err(std::io::Error::last_os_error())
+                    .with_context(|| format!("Failed to lock {}", lock_path.display()));
+            }
+        }
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::io::AsRawHandle;
+            use windows_sys::Win32::Storage::FileSystem::{LockFileEx, LOCKFILE_EXCLUSIVE_LOCK};
+            use windows_sys::Win32::System::IO::OVERLAPPED;
+            let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+            let locked = unsafe {
+                LockFileEx(file.as_raw_handle() as _, LOCKFILE_EXCLUSIVE_LOCK, 0, u32::MAX, u32::MAX, &mut overlapped)
+            };
+            if locked == 0 {
+                return Err(std::io::Error::last_os_error())
+                    .with_context(|| format!("Failed to lock {}", lock_path.display()));
+            }
+        }
+
+        Ok(Self { _file: file })
+    }
+}
+
+/// A file left in the shared temp directory by older clauditor versions
+///
+/// Only regular files owned by the current user are returned, so another
+/// user can't plant one for us to read or remove.
+pub fn legacy_file(name: &str) -> Option<PathBuf> {
+    let path = std::env::temp_dir().join(name);
+    let metadata = fs::symlink_metadata(&path).ok()?;
+    (metadata.is_file() && owned_by_current_user(&metadata)).then_some(path)
+}
+
+#[cfg(unix)]
+fn owned_by_current_user(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.uid() == unsafe { libc::getuid() }
+}
+
+#[cfg(not(unix))]
+fn owned_by_current_user(_metadata: &fs::Metadata) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_state_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("state").join("positions.json");
+        let data: HashMap<String, u64> = [("a".to_string(), 42)].into_iter().collect();
+
+        write_state(&path, &data).unwrap();
+        let loaded: HashMap<String, u64> = read_state(&path).unwrap().unwrap();
+        assert_eq!(loaded, data);
+
+        // Only the state file is left behind
+        let names: Vec<_> = fs::read_dir(path.parent().unwrap()).unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["positions.json"]);
+    }
+
+    #[test]
+    fn test_other_versions_are_ignored() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("positions.json");
+
+        // Unversioned files from older releases, and newer formats
+        fs::write(&path, r#"{"/a.jsonl": 100}"#).unwrap();
+        assert!(read_state::<HashMap<String, u64>>(&path).unwrap().is_none());
+        fs::write(&path, r#"{"version": 99, "data": {"/a.jsonl": {"offset": 1}}}"#).unwrap();
+        assert!(read_state::<HashMap<String, u64>>(&path).unwrap().is_none());
+
+        assert!(read_state::<HashMap<String, u64>>(&dir.path().join("missing.json")).unwrap().is_none());
+        fs::write(&path, "{oops").unwrap();
+        assert!(read_state::<HashMap<String, u64>>(&path).is_err());
+    }
+
+    #[test]
+    fn test_lock_serializes_writers() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("counter.json");
+        write_state(&path, &0u64).unwrap();
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..10 {
+                        let _lock = StateLock::acquire(&path).unwrap();
+                        let count: u64 = read_state(&path).unwrap().unwrap();
+                        write_state(&path, &(count + 1)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(read_state::<u64>(&path).unwrap(), Some(80));
+    }
+}
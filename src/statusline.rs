@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::coordinator::get_active_billing_window;
+use crate::display::{
+    extract_display_name, format_cost, format_duration_compact, format_number_compact, format_time,
+};
+use crate::plan::LimitStatus;
+use crate::state::{read_state, write_state, StateLock};
+use crate::types::SessionBlock;
+
+mod payload;
+mod template;
+
+pub use payload::{project_from_payload, read_stdin_payload};
+#[allow(unused_imports)]
+pub use template::{check_template, StatuslineConfig, PLACEHOLDERS};
+use template::fill_template;
+
+/// Status line cache file name in the state directory
+const STATUSLINE_FILE: &str = "statusline.json";
+
+/// Active window totals kept for one project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectStatus {
+    pub name: String,
+    pub tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// What the status line shows about the active window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowStatus {
+    pub end_time: DateTime<Utc>,
+    pub tokens: u64,
+    pub cost_usd: f64,
+    /// Tokens per minute
+    pub burn_rate: f64,
+    /// Share of the plan's limit used, when a plan is set
+    pub percent_used: Option<f64>,
+    pub projects: Vec<ProjectStatus>,
+}
+
+/// The active window as of `generated_at`, cached between status line runs
+///
+/// Computing the window means reading session files, which is far too slow
+/// for a prompt. The status line prints this summary instead and has a fresh
+/// one computed in the background once it's older than `max_age_secs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusSnapshot {
+    pub generated_at: DateTime<Utc>,
+    /// `Config::usage_key` of the settings the summary was computed with
+    pub config_key: String,
+    pub window: Option<WindowStatus>,
+}
+
+impl StatusSnapshot {
+    /// Summarize the active window
+    pub fn from_window(window: Option<&SessionBlock>, config: &Config, now: DateTime<Utc>) -> Self {
+        let window = window.map(|w| WindowStatus {
+            end_time: w.end_time,
+            tokens: w.token_counts.total(),
+            cost_usd: w.cost_usd,
+            burn_rate: w.burn_rate(),
+            percent_used: config.plan.map(|plan| LimitStatus::check(w, plan, now).percent_used),
+            projects: w.projects.iter()
+                .map(|project| ProjectStatus {
+                    name: project.name.clone(),
+                    tokens: project.token_counts.total(),
+                    cost_usd: project.cost_usd,
+                })
+                .collect(),
+        });
+        Self {
+            generated_at: now,
+            config_key: config.usage_key(),
+            window,
+        }
+    }
+
+    /// Read session files and summarize the active window
+    pub fn compute(config: &Config, now: DateTime<Utc>) -> Result<Self> {
+        let window = get_active_billing_window(config)?;
+        Ok(Self::from_window(window.as_ref(), config, now))
+    }
+
+    /// True once the summary is older than `max_age_secs` or its window has ended
+    pub fn is_stale(&self, config: &Config, now: DateTime<Utc>) -> bool {
+        let max_age = chrono::Duration::seconds(config.statusline.max_age_secs as i64);
+        now - self.generated_at >= max_age
+            || self.window.as_ref().is_some_and(|w| w.end_time <= now)
+    }
+
+    /// Fill in the configured template, or the idle template without an active window
+    ///
+    /// `project` is the name of the project the status line is shown for, if known.
+    pub fn render(&self, template: &str, idle_template: &str, project: Option<&str>, now: DateTime<Utc>) -> String {
+        let window = self.window.as_ref().filter(|w| w.end_time > now);
+        let project_status = window
+            .zip(project)
+            .and_then(|(w, name)| w.projects.iter().find(|p| p.name == name));
+
+        fill_template(if window.is_some() { template } else { idle_template }, |name| {
+            Some(match name {
+                "remaining" => format_duration_compact(window.map_or(chrono::Duration::zero(), |w| w.end_time - now)),
+                "ends" => window.map(|w| format_time(w.end_time)).unwrap_or_default(),
+                "tokens" => format_number_compact(window.map_or(0, |w| w.tokens)),
+                "cost" => format_cost(window.map_or(0.0, |w| w.cost_usd)),
+                "burn_rate" => format_number_compact(window.map_or(0.0, |w| w.burn_rate) as u64),
+                "percent" => window
+                    .and_then(|w| w.percent_used)
+                    .map(|percent| format!("{:.0}%", percent))
+                    .unwrap_or_default(),
+                "project" => project.map(extract_display_name).unwrap_or_default(),
+                "project_tokens" => format_number_compact(project_status.map_or(0, |p| p.tokens)),
+                "project_cost" => format_cost(project_status.map_or(0.0, |p| p.cost_usd)),
+                _ => return None,
+            })
+        })
+    }
+}
+
+/// Cache location in the configured state directory
+pub fn cache_path(config: &Config) -> Option<PathBuf> {
+    config.state_file(STATUSLINE_FILE)
+}
+
+/// The cached summary, if there is one for these settings
+pub fn read_cached(cache_file: &Path, config: &Config) -> Option<StatusSnapshot> {
+    read_state::<StatusSnapshot>(cache_file).ok().flatten()
+        .filter(|snapshot| snapshot.config_key == config.usage_key())
+}
+
+/// Compute a fresh summary and cache it, unless another run just did
+pub fn refresh(cache_file: &Path, config: &Config) -> Result<StatusSnapshot> {
+    let _lock = StateLock::acquire(cache_file)?;
+    let now = Utc::now();
+    if let Some(snapshot) = read_cached(cache_file, config).filter(|s| !s.is_stale(config, now)) {
+        return Ok(snapshot);
+    }
+
+    let snapshot = StatusSnapshot::compute(config, now)?;
+    write_state(cache_file, &snapshot)?;
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn snapshot(config: &Config) -> StatusSnapshot {
+        StatusSnapshot {
+            generated_at: Utc.with_ymd_and_hms(2026, 3, 2, 14, 0, 0).unwrap(),
+            config_key: config.usage_key(),
+            window: Some(WindowStatus {
+                end_time: Utc.with_ymd_and_hms(2026, 3, 2, 17, 0, 0).unwrap(),
+                tokens: 1_234_567,
+                cost_usd: 3.4,
+                burn_rate: 45_300.0,
+                percent_used: Some(62.4),
+                projects: vec![ProjectStatus {
+                    name: "/home/me/shop".to_string(),
+                    tokens: 20_000,
+                    cost_usd: 0.5,
+                }],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_render_default_template() {
+        let config = Config::default();
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 14, 46, 0).unwrap();
+        let line = snapshot(&config).render(
+            &config.statusline.template,
+            &config.statusline.idle_template,
+            None,
+            now,
+        );
+        assert_eq!(line, "⏳2h14m 1.2M tok $3.40 🔥45k/min");
+    }
+
+    #[test]
+    fn test_render_project_and_idle() {
+        let config = Config::default();
+        let snapshot = snapshot(&config);
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 14, 46, 0).unwrap();
+        let template = "{project}: {project_tokens} ({project_cost}) {percent} {json}";
+
+        assert_eq!(
+            snapshot.render(template, "idle", Some("/home/me/shop"), now),
+            "shop: 20k ($0.50) 62% {json}"
+        );
+        assert_eq!(
+            snapshot.render(template, "idle", Some("/home/me/other"), now),
+            "other: 0 ($0.00) 62% {json}"
+        );
+
+        // The cached window has ended since
+        let later = Utc.with_ymd_and_hms(2026, 3, 2, 17, 0, 0).unwrap();
+        assert_eq!(snapshot.render(template, "idle {tokens}", None, later), "idle 0");
+        assert!(snapshot.is_stale(&config, later));
+    }
+
+    #[test]
+    fn test_cache_is_tied_to_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STATUSLINE_FILE);
+        let config = Config::default();
+        write_state(&path, &snapshot(&config)).unwrap();
+
+        assert_eq!(read_cached(&path, &config), Some(snapshot(&config)));
+        let other = Config { hours_back: 48, ..Config::default() };
+        assert_eq!(read_cached(&path, &other), None);
+    }
+}
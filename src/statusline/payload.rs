@@ -0,0 +1,82 @@
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::scanner::{extract_project_name, project_name_for_dir};
+
+/// How long to wait for a JSON payload on stdin before carrying on without one
+const STDIN_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// The parts of the JSON Claude Code pipes to its status line command
+#[derive(Debug, Default, Deserialize)]
+struct Payload {
+    transcript_path: Option<PathBuf>,
+    cwd: Option<PathBuf>,
+    workspace: Option<Workspace>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Workspace {
+    project_dir: Option<PathBuf>,
+    current_dir: Option<PathBuf>,
+}
+
+/// Project a Claude Code status line payload is for
+///
+/// The transcript's directory names the project exactly; the working
+/// directories are a fallback for payloads without one.
+pub fn project_from_payload(payload: &str) -> Option<String> {
+    let payload: Payload = serde_json::from_str(payload).ok()?;
+    if let Some(transcript) = payload.transcript_path.filter(|path| path.parent().is_some()) {
+        return Some(extract_project_name(&transcript));
+    }
+
+    let workspace = payload.workspace.unwrap_or_default();
+    workspace.project_dir
+        .or(workspace.current_dir)
+        .or(payload.cwd)
+        .map(|dir| project_name_for_dir(&dir))
+}
+
+/// Read the payload piped on stdin, if one arrives promptly
+///
+/// tmux and most prompts leave stdin empty or closed, but one left open
+/// without data mustn't hold up the prompt.
+pub fn read_stdin_payload() -> Option<String> {
+    if io::stdin().is_terminal() {
+        return None;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut payload = String::new();
+        if io::stdin().read_to_string(&mut payload).is_ok() {
+            let _ = tx.send(payload);
+        }
+    });
+    rx.recv_timeout(STDIN_TIMEOUT).ok().filter(|payload| !payload.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_from_payload() {
+        let payload = r#"{
+            "session_id": "abc",
+            "transcript_path": "/home/me/.claude/projects/-home-me-my-app/abc.jsonl",
+            "cwd": "/home/me/my-app/src",
+            "workspace": {"current_dir": "/home/me/my-app/src", "project_dir": "/home/me/my-app"}
+        }"#;
+        assert_eq!(project_from_payload(payload).as_deref(), Some("/home/me/my/app"));
+
+        let payload = r#"{"cwd": "/home/me/my-app/src", "workspace": {"project_dir": "/home/me/my_app"}}"#;
+        assert_eq!(project_from_payload(payload).as_deref(), Some("/home/me/my/app"));
+        assert_eq!(project_from_payload(r#"{"cwd": "/srv"}"#).as_deref(), Some("/srv"));
+        assert_eq!(project_from_payload("not json"), None);
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+/// Placeholders a status line template may use
+pub const PLACEHOLDERS: &[&str] = &[
+    "remaining",
+    "ends",
+    "tokens",
+    "cost",
+    "burn_rate",
+    "percent",
+    "project",
+    "project_tokens",
+    "project_cost",
+];
+
+/// Status line settings from the `[statusline]` section of the config file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatuslineConfig {
+    /// Printed while a window is active
+    pub template: String,
+    /// Printed when there is no active window
+    pub idle_template: String,
+    /// Seconds the cached summary is shown before it is refreshed in the background
+    pub max_age_secs: u64,
+}
+
+impl Default for StatuslineConfig {
+    fn default() -> Self {
+        Self {
+            template: "⏳{remaining} {tokens} tok {cost} 🔥{burn_rate}/min".to_string(),
+            idle_template: "no active window".to_string(),
+            max_age_secs: 30,
+        }
+    }
+}
+
+impl StatuslineConfig {
+    /// Check that both templates only use known placeholders
+    pub fn validate(&self) -> Result<()> {
+        check_template(&self.template)?;
+        check_template(&self.idle_template)
+    }
+}
+
+/// Reject a template that uses a placeholder not in `PLACEHOLDERS`
+pub fn check_template(template: &str) -> Result<()> {
+    let mut unknown = None;
+    fill_template(template, |name| {
+        if !PLACEHOLDERS.contains(&name) && unknown.is_none() {
+            unknown = Some(name.to_string());
+        }
+        Some(String::new())
+    });
+    match unknown {
+        Some(name) => bail!(
+            "unknown placeholder {{{}}} in status line template (expected one of: {})",
+            name,
+            PLACEHOLDERS.join(", ")
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Replace each `{name}` in `template` with `lookup(name)`
+///
+/// Braces around anything but a lowercase name, or a name `lookup` returns
+/// `None` for, are left as they are.
+pub(super) fn fill_template(template: &str, mut lookup: impl FnMut(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let name_len = after.find(|c: char| !(c.is_ascii_lowercase() || c == '_')).unwrap_or(after.len());
+        match (after[name_len..].starts_with('}') && name_len > 0)
+            .then(|| lookup(&after[..name_len]))
+            .flatten()
+        {
+            Some(value) => {
+                out.push_str(&value);
+                rest = &after[name_len + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates_are_checked() {
+        assert!(check_template("{tokens} {cost} { not a placeholder }").is_ok());
+        let err = check_template("{tokens} {tokns}").unwrap_err().to_string();
+        assert!(err.contains("{tokns}"), "{}", err);
+    }
+}
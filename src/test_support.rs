@@ -0,0 +1,7 @@
+/// A usage line as Claude Code writes it, for a message with id `id`
+pub(crate) fn usage_line(id: &str) -> String {
+    format!(
+        r#"{{"timestamp":"2025-01-12T16:03:28.593Z","message":{{"id":"{}","type":"message","role":"assistant","model":"claude-opus-4-20250514","usage":{{"input_tokens":10,"output_tokens":5}}}},"requestId":"req_{}","version":"1.0.51"}}"#,
+        id, id
+    )
+}
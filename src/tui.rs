@@ -0,0 +1,77 @@
+use std::sync::mpsc::Sender;
+use anyhow::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+use ratatui::DefaultTerminal;
+
+use crate::display::DisplayOptions;
+use crate::events::WatchEvent;
+
+mod input;
+mod render;
+
+pub use input::{DashboardState, KeyOutcome, ProjectSort, View};
+pub use render::{render, Snapshot};
+
+/// How many minutes of burn rate the sparkline shows
+pub const SPARKLINE_MINUTES: usize = 60;
+
+/// Full-screen watch mode dashboard
+///
+/// Takes over the terminal's alternate screen in raw mode until dropped.
+/// Frames are diffed against the previous one, so redraws don't flicker.
+pub struct Dashboard {
+    terminal: DefaultTerminal,
+    state: DashboardState,
+}
+
+impl Dashboard {
+    /// Switch the terminal to the dashboard
+    pub fn open(options: &DisplayOptions) -> Result<Self> {
+        let terminal = ratatui::try_init().context("Failed to set up the terminal")?;
+        Ok(Self {
+            terminal,
+            state: DashboardState {
+                show_sessions: options.show_sessions,
+                ..DashboardState::default()
+            },
+        })
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> KeyOutcome {
+        self.state.handle_key(key)
+    }
+
+    pub fn draw(&mut self, snapshot: &Snapshot) -> Result<()> {
+        let state = &self.state;
+        self.terminal.draw(|frame| render(frame, state, snapshot))
+            .context("Failed to draw the dashboard")?;
+        Ok(())
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+/// Forward key presses and terminal resizes to the watch loop
+///
+/// In raw mode Ctrl+C arrives as a key press rather than a signal.
+pub fn forward_input(events: Sender<WatchEvent>) {
+    std::thread::spawn(move || {
+        while let Ok(input) = event::read() {
+            let event = match input {
+                Event::Key(key) if key.kind == KeyEventKind::Press => WatchEvent::Key(key),
+                Event::Resize(_, _) => WatchEvent::Resize,
+                _ => continue,
+            };
+            if events.send(event).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests;
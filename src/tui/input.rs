@@ -0,0 +1,145 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::display::extract_display_name;
+use crate::types::{ProjectUsage, SessionBlock};
+
+/// What the dashboard's main area shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    /// Header, projects, models and burn rate together
+    Overview,
+    /// The project table on its own
+    Projects,
+    /// The model table and a taller burn rate chart
+    Models,
+}
+
+impl View {
+    pub(super) const ALL: [View; 3] = [View::Overview, View::Projects, View::Models];
+
+    pub(super) fn title(self) -> &'static str {
+        match self {
+            View::Overview => "Overview",
+            View::Projects => "Projects",
+            View::Models => "Models",
+        }
+    }
+
+    pub(super) fn index(self) -> usize {
+        Self::ALL.iter().position(|view| *view == self).unwrap_or(0)
+    }
+}
+
+/// Column the project table is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectSort {
+    Tokens,
+    Cost,
+    Entries,
+    Name,
+}
+
+impl ProjectSort {
+    fn next(self) -> Self {
+        match self {
+            ProjectSort::Tokens => ProjectSort::Cost,
+            ProjectSort::Cost => ProjectSort::Entries,
+            ProjectSort::Entries => ProjectSort::Name,
+            ProjectSort::Name => ProjectSort::Tokens,
+        }
+    }
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            ProjectSort::Tokens => "tokens",
+            ProjectSort::Cost => "cost",
+            ProjectSort::Entries => "entries",
+            ProjectSort::Name => "name",
+        }
+    }
+}
+
+/// What a key press did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOutcome {
+    /// The dashboard changed and should be drawn again
+    Redraw,
+    Quit,
+    Ignored,
+}
+
+/// View and sort order chosen with the keyboard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DashboardState {
+    pub view: View,
+    pub sort: ProjectSort,
+    /// Largest first; names sort A to Z when this is false
+    pub descending: bool,
+    /// List each project's sessions under it
+    pub show_sessions: bool,
+}
+
+impl Default for DashboardState {
+    fn default() -> Self {
+        Self {
+            view: View::Overview,
+            sort: ProjectSort::Tokens,
+            descending: true,
+            show_sessions: false,
+        }
+    }
+}
+
+impl DashboardState {
+    /// Apply a key press
+    ///
+    /// `q`, `Esc` and `Ctrl+C` quit, `Tab` and `1`-`3` switch views, `s`
+    /// changes the sort column, `r` reverses the order and `e` expands
+    /// projects into their sessions.
+    pub fn handle_key(&mut self, key: KeyEvent) -> KeyOutcome {
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => KeyOutcome::Quit,
+            KeyCode::Char('q') | KeyCode::Esc => KeyOutcome::Quit,
+            KeyCode::Tab => self.show(View::ALL[(self.view.index() + 1) % View::ALL.len()]),
+            KeyCode::BackTab => self.show(View::ALL[(self.view.index() + View::ALL.len() - 1) % View::ALL.len()]),
+            KeyCode::Char(c @ '1'..='3') => self.show(View::ALL[c as usize - '1' as usize]),
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                // Numbers read best largest first, names A to Z
+                self.descending = self.sort != ProjectSort::Name;
+                KeyOutcome::Redraw
+            }
+            KeyCode::Char('r') => {
+                self.descending = !self.descending;
+                KeyOutcome::Redraw
+            }
+            KeyCode::Char('e') => {
+                self.show_sessions = !self.show_sessions;
+                KeyOutcome::Redraw
+            }
+            _ => KeyOutcome::Ignored,
+        }
+    }
+
+    fn show(&mut self, view: View) -> KeyOutcome {
+        self.view = view;
+        KeyOutcome::Redraw
+    }
+
+    /// The window's projects in the chosen order
+    pub fn sorted_projects<'a>(&self, window: &'a SessionBlock) -> Vec<&'a ProjectUsage> {
+        let mut projects: Vec<&ProjectUsage> = window.projects.iter().collect();
+        projects.sort_by(|a, b| {
+            let order = match self.sort {
+                ProjectSort::Tokens => a.token_counts.total().cmp(&b.token_counts.total()),
+                ProjectSort::Cost => a.cost_usd.total_cmp(&b.cost_usd),
+                ProjectSort::Entries => a.entry_count.cmp(&b.entry_count),
+                ProjectSort::Name => extract_display_name(&a.name).cmp(&extract_display_name(&b.name)),
+            };
+            // Ties keep a stable order by name
+            let order = order.then_with(|| a.name.cmp(&b.name));
+            if self.descending { order.reverse() } else { order }
+        });
+        projects
+    }
+}
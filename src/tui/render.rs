@@ -0,0 +1,297 @@
+use chrono::{DateTime, Utc};
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Gauge, Paragraph, Row, Sparkline, Table, Tabs};
+use ratatui::Frame;
+
+use super::{DashboardState, ProjectSort, View};
+use crate::display::{
+    burn_rate_tone, extract_display_name, format_cost, format_duration_plain, format_model_mix,
+    format_model_name, format_number, format_time, limit_tone, percent_of, short_session_id,
+    time_remaining_tone, DisplayOptions, Tone,
+};
+use crate::plan::LimitStatus;
+use crate::types::SessionBlock;
+
+/// Everything one frame of the dashboard shows
+pub struct Snapshot<'a> {
+    /// The active window, if there is one
+    pub window: Option<&'a SessionBlock>,
+    /// Tokens used in each recent minute, oldest first
+    pub tokens_per_minute: &'a [u64],
+    pub options: &'a DisplayOptions,
+    pub now: DateTime<Utc>,
+    /// Latest error, shown in the footer
+    pub status: Option<&'a str>,
+}
+
+/// Draw one frame of the dashboard
+pub fn render(frame: &mut Frame, state: &DashboardState, snapshot: &Snapshot) {
+    let [tabs_area, main_area, footer_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ]).areas(frame.area());
+
+    render_tabs(frame, tabs_area, state);
+    render_footer(frame, footer_area, state, snapshot.status);
+
+    let Some(window) = snapshot.window else {
+        let message = Paragraph::new("No active billing window")
+            .alignment(Alignment::Center)
+            .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(message, main_area);
+        return;
+    };
+
+    let header_height = header_lines(window, snapshot).len() as u16 + 2;
+    let gauges_height = if snapshot.options.plan.is_some() { 2 } else { 1 };
+    let [header_area, gauges_area, body_area] = Layout::vertical([
+        Constraint::Length(header_height),
+        Constraint::Length(gauges_height),
+        Constraint::Min(0),
+    ]).areas(main_area);
+
+    render_header(frame, header_area, window, snapshot);
+    render_gauges(frame, gauges_area, window, snapshot);
+
+    match state.view {
+        View::Overview => {
+            let [tables_area, sparkline_area] = Layout::vertical([
+                Constraint::Min(0),
+                Constraint::Length(6),
+            ]).areas(body_area);
+            let [projects_area, models_area] = Layout::horizontal([
+                Constraint::Percentage(60),
+                Constraint::Percentage(40),
+            ]).areas(tables_area);
+            render_projects(frame, projects_area, window, state);
+            render_models(frame, models_area, window);
+            render_sparkline(frame, sparkline_area, snapshot);
+        }
+        View::Projects => render_projects(frame, body_area, window, state),
+        View::Models => {
+            let [models_area, sparkline_area] = Layout::vertical([
+                Constraint::Min(0),
+                Constraint::Percentage(50),
+            ]).areas(body_area);
+            render_models(frame, models_area, window);
+            render_sparkline(frame, sparkline_area, snapshot);
+        }
+    }
+}
+
+fn tone_color(tone: Option<Tone>) -> Style {
+    match tone {
+        Some(Tone::Green) => Style::default().fg(Color::Green),
+        Some(Tone::Yellow) => Style::default().fg(Color::Yellow),
+        Some(Tone::Orange) => Style::default().fg(Color::Indexed(208)),
+        Some(Tone::Red) => Style::default().fg(Color::Red),
+        None => Style::default(),
+    }
+}
+
+fn render_tabs(frame: &mut Frame, area: Rect, state: &DashboardState) {
+    let titles = View::ALL.iter()
+        .enumerate()
+        .map(|(i, view)| format!("{} {}", i + 1, view.title()));
+    let tabs = Tabs::new(titles)
+        .select(state.view.index())
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED));
+    frame.render_widget(tabs, area);
+}
+
+fn render_footer(frame: &mut Frame, area: Rect, state: &DashboardState, status: Option<&str>) {
+    let line = match status {
+        Some(status) => Line::styled(status.to_string(), Style::default().fg(Color::Red)),
+        None => Line::styled(
+            format!(
+                "Tab/1-3 view · s sort ({} {}) · r reverse · e sessions · q quit",
+                state.sort.label(),
+                if state.descending { "↓" } else { "↑" }
+            ),
+            Style::default().add_modifier(Modifier::DIM),
+        ),
+    };
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+fn header_lines<'a>(window: &SessionBlock, snapshot: &Snapshot<'a>) -> Vec<Line<'a>> {
+    let options = snapshot.options;
+    let remaining = window.time_remaining(snapshot.now);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw(format!("Started {}, ends {} · ", format_time(window.start_time), format_time(window.end_time))),
+            Span::styled(
+                format!("{} left", format_duration_plain(remaining)),
+                tone_color(time_remaining_tone(remaining.num_minutes(), &options.time_remaining)),
+            ),
+        ]),
+        Line::from(vec![
+            Span::raw(format!("Total: {} tokens · ", format_number(window.token_counts.total()))),
+            Span::styled(
+                format!("{} tokens/min", format_number(window.burn_rate() as u64)),
+                tone_color(burn_rate_tone(window.burn_rate(), &options.burn_rate)),
+            ),
+            Span::raw(format!(" · {} ({}/hr)", format_cost(window.cost_usd), format_cost(window.cost_burn_rate()))),
+        ]),
+    ];
+
+    if let Some(plan) = options.plan {
+        let status = LimitStatus::check(window, plan, snapshot.now);
+        if status.is_exhausted() {
+            lines.push(Line::styled("⚠ Plan limit reached for this window", Style::default().fg(Color::Red)));
+        } else if let Some(exhaustion) = status.projected_exhaustion.filter(|_| status.exhausts_before_reset) {
+            lines.push(Line::styled(
+                format!("⚠ Projected to hit the plan limit at {}", format_time(exhaustion)),
+                Style::default().fg(Color::Red),
+            ));
+        }
+    }
+
+    lines
+}
+
+fn render_header(frame: &mut Frame, area: Rect, window: &SessionBlock, snapshot: &Snapshot) {
+    let header = Paragraph::new(header_lines(window, snapshot))
+        .block(Block::default().borders(Borders::ALL).title(" Active billing window "));
+    frame.render_widget(header, area);
+}
+
+fn render_gauges(frame: &mut Frame, area: Rect, window: &SessionBlock, snapshot: &Snapshot) {
+    let [window_area, limit_area] = Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).areas(area);
+
+    let length = (window.end_time - window.start_time).num_seconds().max(1) as f64;
+    let elapsed = (snapshot.now - window.start_time).num_seconds() as f64;
+    let remaining = window.time_remaining(snapshot.now);
+    let progress = Gauge::default()
+        .ratio((elapsed / length).clamp(0.0, 1.0))
+        .label(format!("{} left, until {}", format_duration_plain(remaining), format_time(window.end_time)))
+        .gauge_style(tone_color(time_remaining_tone(remaining.num_minutes(), &snapshot.options.time_remaining)).bg(Color::DarkGray));
+    frame.render_widget(progress, window_area);
+
+    if let Some(plan) = snapshot.options.plan {
+        let status = LimitStatus::check(window, plan, snapshot.now);
+        let limit = Gauge::default()
+            .ratio((status.percent_used / 100.0).clamp(0.0, 1.0))
+            .label(format!(
+                "{:.0}% of {} tokens ({} plan)",
+                status.percent_used,
+                format_number(status.token_limit),
+                status.plan
+            ))
+            .gauge_style(tone_color(limit_tone(status.percent_used)).bg(Color::DarkGray));
+        frame.render_widget(limit, limit_area);
+    }
+}
+
+fn render_projects(frame: &mut Frame, area: Rect, window: &SessionBlock, state: &DashboardState) {
+    let total_tokens = window.token_counts.total();
+    let rows = state.sorted_projects(window).into_iter().flat_map(|project| {
+        let tokens = project.token_counts.total();
+        let project_row = Row::new(vec![
+            Cell::from(extract_display_name(&project.name)),
+            numeric(format_number(tokens)),
+            numeric(format!("{}%", percent_of(tokens, total_tokens))),
+            numeric(format_cost(project.cost_usd)),
+            numeric(format_number(project.entry_count as u64)),
+        ]);
+        let sessions = project.sessions.iter()
+            .filter(|_| state.show_sessions)
+            .map(move |session| {
+                let tokens = session.token_counts.total();
+                Row::new(vec![
+                    Cell::from(format!(
+                        "  {} {}-{} {}",
+                        short_session_id(&session.session_id),
+                        format_time(session.first_activity),
+                        format_time(session.last_activity),
+                        format_model_mix(session)
+                    )),
+                    numeric(format_number(tokens)),
+                    numeric(format!("{}%", percent_of(tokens, total_tokens))),
+                    numeric(format_cost(session.cost_usd)),
+                    numeric(format_number(session.entry_count as u64)),
+                ]).style(Style::default().add_modifier(Modifier::DIM))
+            });
+        std::iter::once(project_row).chain(sessions)
+    });
+
+    let header = ["Project", "Tokens", "%", "Cost", "Entries"].into_iter().map(|title| {
+        let is_sorted = match state.sort {
+            ProjectSort::Name => title == "Project",
+            sort => title.eq_ignore_ascii_case(sort.label()),
+        };
+        let cell = if title == "Project" { Cell::from(title) } else { numeric(title.to_string()) };
+        if is_sorted { cell.style(Style::default().add_modifier(Modifier::UNDERLINED)) } else { cell }
+    });
+
+    let table = Table::new(rows, [
+        Constraint::Min(10),
+        Constraint::Length(13),
+        Constraint::Length(4),
+        Constraint::Length(10),
+        Constraint::Length(7),
+    ])
+        .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title(" Projects "));
+    frame.render_widget(table, area);
+}
+
+fn render_models(frame: &mut Frame, area: Rect, window: &SessionBlock) {
+    let total_tokens = window.token_counts.total();
+    let mut models: Vec<_> = window.models.iter().collect();
+    models.sort_by_key(|m| std::cmp::Reverse(m.token_counts.total()));
+
+    let rows = models.into_iter().map(|model| {
+        let tokens = model.token_counts.total();
+        Row::new(vec![
+            Cell::from(format_model_name(&model.model)),
+            numeric(format_number(tokens)),
+            numeric(format!("{}%", percent_of(tokens, total_tokens))),
+            numeric(format_cost(model.cost_usd)),
+        ])
+    });
+
+    let table = Table::new(rows, [
+        Constraint::Min(8),
+        Constraint::Length(13),
+        Constraint::Length(4),
+        Constraint::Length(10),
+    ])
+        .header(Row::new([
+            Cell::from("Model"),
+            numeric("Tokens".to_string()),
+            numeric("%".to_string()),
+            numeric("Cost".to_string()),
+        ]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title(" Models "));
+    frame.render_widget(table, area);
+}
+
+/// A right-aligned table cell
+fn numeric<'a>(text: String) -> Cell<'a> {
+    Cell::from(Line::from(text).alignment(Alignment::Right))
+}
+
+fn render_sparkline(frame: &mut Frame, area: Rect, snapshot: &Snapshot) {
+    let rates = snapshot.tokens_per_minute;
+    let latest = rates.last().copied().unwrap_or(0);
+    let peak = rates.iter().copied().max().unwrap_or(0);
+    let title = format!(
+        " Burn rate, last {} min: {} tokens/min now, {} peak ",
+        rates.len(),
+        format_number(latest),
+        format_number(peak)
+    );
+
+    // Newest on the right, dropping the oldest minutes that don't fit
+    let width = area.width.saturating_sub(2) as usize;
+    let visible = &rates[rates.len().saturating_sub(width)..];
+    let sparkline = Sparkline::default()
+        .data(visible)
+        .style(tone_color(burn_rate_tone(latest as f64, &snapshot.options.burn_rate)))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(sparkline, area);
+}
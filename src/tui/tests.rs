@@ -0,0 +1,142 @@
+use super::*;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+use crate::display::extract_display_name;
+use crate::types::{ModelUsage, ProjectUsage, SessionBlock, SessionUsage, TokenCounts};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+fn project(name: &str, tokens: u64, cost_usd: f64, entry_count: usize) -> ProjectUsage {
+    ProjectUsage {
+        name: name.to_string(),
+        token_counts: TokenCounts { input_tokens: tokens, ..Default::default() },
+        cost_usd,
+        entry_count,
+        sessions: Vec::new(),
+    }
+}
+
+fn window() -> SessionBlock {
+    SessionBlock {
+        start_time: "2025-01-13T14:00:00Z".parse().unwrap(),
+        end_time: "2025-01-13T19:00:00Z".parse().unwrap(),
+        last_activity: "2025-01-13T15:00:00Z".parse().unwrap(),
+        projects: vec![
+            project("/home/me/alpha", 1_000, 3.0, 5),
+            project("/home/me/beta", 5_000, 1.0, 2),
+            project("/home/me/gamma", 2_000, 2.0, 9),
+        ],
+        models: vec![ModelUsage {
+            model: "claude-opus-4-20250514".to_string(),
+            token_counts: TokenCounts { input_tokens: 8_000, ..Default::default() },
+            cost_usd: 6.0,
+            entry_count: 16,
+        }],
+        token_counts: TokenCounts { input_tokens: 8_000, ..Default::default() },
+        cost_usd: 6.0,
+        is_active: true,
+    }
+}
+
+fn names(state: &DashboardState, window: &SessionBlock) -> Vec<String> {
+    state.sorted_projects(window).iter().map(|p| extract_display_name(&p.name)).collect()
+}
+
+fn draw(state: &DashboardState, window: Option<&SessionBlock>) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+    let options = DisplayOptions::default();
+    let rates: Vec<u64> = (0..SPARKLINE_MINUTES as u64).collect();
+    let snapshot = Snapshot {
+        window,
+        tokens_per_minute: &rates,
+        options: &options,
+        now: "2025-01-13T15:30:00Z".parse().unwrap(),
+        status: None,
+    };
+    terminal.draw(|frame| render(frame, state, &snapshot)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    buffer.content.chunks(buffer.area.width as usize)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_keys_change_view_and_sort() {
+    let mut state = DashboardState::default();
+    let window = window();
+    assert_eq!(names(&state, &window), ["beta", "gamma", "alpha"]);
+
+    assert_eq!(state.handle_key(key(KeyCode::Char('s'))), KeyOutcome::Redraw);
+    assert_eq!(state.sort, ProjectSort::Cost);
+    assert_eq!(names(&state, &window), ["alpha", "gamma", "beta"]);
+
+    state.handle_key(key(KeyCode::Char('s')));
+    state.handle_key(key(KeyCode::Char('s')));
+    assert_eq!(state.sort, ProjectSort::Name);
+    assert_eq!(names(&state, &window), ["alpha", "beta", "gamma"]);
+    state.handle_key(key(KeyCode::Char('r')));
+    assert_eq!(names(&state, &window), ["gamma", "beta", "alpha"]);
+
+    assert_eq!(state.handle_key(key(KeyCode::Tab)), KeyOutcome::Redraw);
+    assert_eq!(state.view, View::Projects);
+    state.handle_key(key(KeyCode::BackTab));
+    state.handle_key(key(KeyCode::BackTab));
+    assert_eq!(state.view, View::Models);
+    state.handle_key(key(KeyCode::Char('1')));
+    assert_eq!(state.view, View::Overview);
+
+    assert_eq!(state.handle_key(key(KeyCode::Char('x'))), KeyOutcome::Ignored);
+    assert_eq!(state.handle_key(key(KeyCode::Char('q'))), KeyOutcome::Quit);
+    assert_eq!(state.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)), KeyOutcome::Quit);
+}
+
+#[test]
+fn test_overview_shows_every_panel() {
+    let screen = draw(&DashboardState::default(), Some(&window()));
+
+    assert!(screen.contains("Active billing window"), "{}", screen);
+    assert!(screen.contains("8,000 tokens"), "{}", screen);
+    assert!(screen.contains("3h 30m left"), "{}", screen);
+    assert!(screen.contains("beta"), "{}", screen);
+    assert!(screen.contains("opus-4"), "{}", screen);
+    assert!(screen.contains("59 tokens/min now"), "{}", screen);
+    assert!(screen.contains("s sort (tokens ↓)"), "{}", screen);
+}
+
+#[test]
+fn test_views_and_empty_window() {
+    let state = DashboardState { view: View::Projects, ..Default::default() };
+    let screen = draw(&state, Some(&window()));
+    assert!(screen.contains("gamma"));
+    assert!(!screen.contains("Burn rate"));
+
+    let screen = draw(&DashboardState::default(), None);
+    assert!(screen.contains("No active billing window"));
+}
+
+#[test]
+fn test_sessions_expand_under_projects() {
+    let mut window = window();
+    window.projects[1].sessions.push(SessionUsage {
+        session_id: "3f2a9c1e-5b7d-4e8f-9a0b-1c2d3e4f5a6b".to_string(),
+        token_counts: TokenCounts { input_tokens: 4_000, ..Default::default() },
+        cost_usd: 0.8,
+        entry_count: 2,
+        first_activity: "2025-01-13T14:05:00Z".parse().unwrap(),
+        last_activity: "2025-01-13T14:50:00Z".parse().unwrap(),
+        models: window.models.clone(),
+    });
+
+    let mut state = DashboardState { view: View::Projects, ..Default::default() };
+    assert!(!draw(&state, Some(&window)).contains("3f2a9c1e"));
+
+    assert_eq!(state.handle_key(key(KeyCode::Char('e'))), KeyOutcome::Redraw);
+    let screen = draw(&state, Some(&window));
+    let row = screen.lines().find(|line| line.contains("3f2a9c1e")).expect(&screen);
+    assert!(row.contains("opus-4") && row.contains("4,000") && row.contains("50%"), "{}", row);
+}
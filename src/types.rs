@@ -1,5 +1,6 @@
 use chrono::{DateTime, Duration, Timelike, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 /// Token usage information from Claude Code
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +13,13 @@ pub struct TokenUsage {
     pub cache_read_input_tokens: u64,
 }
 
+impl TokenUsage {
+    /// Total tokens (all types combined)
+    pub fn total(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+    }
+}
+
 /// Message information from JSONL entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
@@ -33,10 +41,30 @@ pub struct UsageEntry {
     #[serde(rename = "requestId")]
     pub request_id: String,
     pub version: String,
+    /// Fields clauditor doesn't model, kept as they appeared
+    #[serde(default, skip_serializing_if = "UnknownFields::is_empty")]
+    pub unknown: UnknownFields,
+}
+
+/// Fields of a JSONL entry that aren't part of `UsageEntry`, grouped by where they appeared
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UnknownFields {
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub entry: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub message: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub usage: Map<String, Value>,
+}
+
+impl UnknownFields {
+    pub fn is_empty(&self) -> bool {
+        self.entry.is_empty() && self.message.is_empty() && self.usage.is_empty()
+    }
 }
 
 /// Aggregated token counts
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TokenCounts {
     pub input_tokens: u64,
     pub output_tokens: u64,
@@ -71,13 +99,16 @@ pub struct SessionFile {
 }
 
 /// A 5-hour billing window containing usage data
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionBlock {
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
     pub last_activity: DateTime<Utc>,
     pub projects: Vec<ProjectUsage>,
+    pub models: Vec<ModelUsage>,
     pub token_counts: TokenCounts,
+    /// Estimated dollar cost of all usage in the window
+    pub cost_usd: f64,
     pub is_active: bool,
 }
 
@@ -93,6 +124,17 @@ impl SessionBlock {
         }
     }
     
+    /// Calculate cost burn rate in USD per hour
+    pub fn cost_burn_rate(&self) -> f64 {
+        let duration = self.last_activity - self.start_time;
+        let hours = duration.num_seconds() as f64 / 3600.0;
+        if hours > 0.0 {
+            self.cost_usd / hours
+        } else {
+            0.0
+        }
+    }
+    
     /// Time remaining until window ends
     pub fn time_remaining(&self, now: DateTime<Utc>) -> Duration {
         self.end_time - now
@@ -100,10 +142,41 @@ impl SessionBlock {
 }
 
 /// Usage data for a specific project within a session block
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectUsage {
     pub name: String,
     pub token_counts: TokenCounts,
+    /// Estimated dollar cost of this project's usage
+    pub cost_usd: f64,
+    pub entry_count: usize,
+    /// Usage by conversation, highest first
+    #[serde(default)]
+    pub sessions: Vec<SessionUsage>,
+}
+
+/// Usage data for one conversation (session file) within a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionUsage {
+    pub session_id: String,
+    pub token_counts: TokenCounts,
+    /// Estimated dollar cost of this session's usage
+    pub cost_usd: f64,
+    pub entry_count: usize,
+    /// First and last usage entries of the session within the window
+    pub first_activity: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    /// Usage by model within the session, highest first
+    pub models: Vec<ModelUsage>,
+}
+
+/// Usage data for a specific model within a session block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelUsage {
+    /// Model id as reported by Claude Code (e.g. `claude-opus-4-20250514`)
+    pub model: String,
+    pub token_counts: TokenCounts,
+    /// Estimated dollar cost of this model's usage
+    pub cost_usd: f64,
     pub entry_count: usize,
 }
 
@@ -140,19 +213,24 @@ pub fn floor_to_hour(timestamp: DateTime<Utc>) -> DateTime<Utc> {
 /// - At 6:30 PM with last activity at 6:00 PM: Active (both conditions met)
 /// - At 6:30 PM with last activity at 1:00 PM: Inactive (>5 hours since activity)
 /// - At 7:30 PM with recent activity: Inactive (past window end time)
+/// 
+/// The inactivity limit is the window's own length, so configured window
+/// durations other than 5 hours work unchanged.
 pub fn is_block_active(block: &SessionBlock, now: DateTime<Utc>) -> bool {
-    let five_hours = Duration::hours(5);
+    let session_duration = block.end_time - block.start_time;
     let time_since_last = now - block.last_activity;
     let time_until_end = block.end_time - now;
     
-    time_since_last < five_hours && time_until_end > Duration::zero()
+    time_since_last < session_duration && time_until_end > Duration::zero()
 }
 
-/// Entry with its associated project information
+/// Entry with its associated project and session information
 #[derive(Debug, Clone)]
 pub struct EntryWithProject {
     pub entry: UsageEntry,
     pub project: String,
+    /// Id of the session file the entry was read from
+    pub session_id: String,
 }
 
 #[cfg(test)]
@@ -192,6 +270,8 @@ mod tests {
             last_activity,
             projects: vec![],
             token_counts: TokenCounts::default(),
+            cost_usd: 0.0,
+            models: vec![],
             is_active: false,
         };
         
@@ -227,6 +307,8 @@ mod tests {
             last_activity,
             projects: vec![],
             token_counts: TokenCounts::default(),
+            cost_usd: 0.0,
+            models: vec![],
             is_active: false,
         };
         
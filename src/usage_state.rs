@@ -0,0 +1,229 @@
+use std::collections::{BTreeMap, HashSet};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::Config;
+use crate::filter::ProjectFilter;
+use crate::types::{floor_to_hour, is_block_active, EntryWithProject, SessionBlock};
+use crate::window::{
+    find_window_periods, group_into_single_window_with_duration, group_subset_into_account_window,
+    WindowTotals, ACTIVE_WINDOW_LOOKBACK,
+};
+
+/// The last window period and its running totals
+#[derive(Debug, Clone)]
+struct CurrentWindow {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    /// Latest entry of any project, which decides whether the window is active
+    last_activity: DateTime<Utc>,
+    /// Latest entry that is counted in the totals
+    last_counted: Option<DateTime<Utc>>,
+    totals: WindowTotals,
+}
+
+impl CurrentWindow {
+    fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            end,
+            last_activity: start,
+            last_counted: None,
+            totals: WindowTotals::default(),
+        }
+    }
+
+    fn add(&mut self, entry: &EntryWithProject, counted: bool) {
+        let timestamp = entry.entry.timestamp;
+        self.last_activity = self.last_activity.max(timestamp);
+        if counted {
+            self.last_counted = Some(self.last_counted.map_or(timestamp, |last| last.max(timestamp)));
+            self.totals.add(entry);
+        }
+    }
+}
+
+/// Usage entries of the current and recent billing windows, kept up to date
+/// as new entries arrive
+///
+/// Appending entries updates the last window's totals in place, or starts a
+/// new window once an entry falls past its end. The window returned is always
+/// the one a full regroup of every entry added so far would give, with
+/// duplicates dropped (the first copy wins) and the project filter applied as
+/// in `coordinator::load_and_group_sessions`. Entries that arrive out of order,
+/// and entries ageing out of the lookback, cause the last window to be worked
+/// out again from the entries held.
+#[derive(Debug)]
+pub struct UsageState {
+    session_duration: Duration,
+    filter: ProjectFilter,
+    /// Take window boundaries from every project, but only count matching ones
+    account_window: bool,
+    seen: HashSet<(String, String)>,
+    /// Entries that may still affect the active window, keyed by timestamp
+    /// and arrival order
+    entries: BTreeMap<(DateTime<Utc>, u64), EntryWithProject>,
+    next_seq: u64,
+    /// Entries older than this can no longer affect the active window
+    lookback_start: Option<DateTime<Utc>>,
+    /// The last window period of `entries`, valid unless `stale`
+    current: Option<CurrentWindow>,
+    stale: bool,
+}
+
+impl UsageState {
+    pub fn new(session_duration: Duration, filter: ProjectFilter, account_window: bool) -> Self {
+        Self {
+            session_duration,
+            filter,
+            account_window,
+            seen: HashSet::new(),
+            entries: BTreeMap::new(),
+            next_seq: 0,
+            lookback_start: None,
+            current: None,
+            stale: false,
+        }
+    }
+
+    /// An empty state for the configured window length and project filter
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(Self::new(
+            config.session_duration(),
+            ProjectFilter::from_config(&config.filter)?,
+            config.filter.account_window,
+        ))
+    }
+
+    /// Forget every entry, keeping the settings
+    pub fn clear(&mut self) {
+        *self = Self::new(self.session_duration, self.filter.clone(), self.account_window);
+    }
+
+    /// Add new entries, in O(1) each unless they arrive out of order
+    pub fn add_entries(&mut self, entries: impl IntoIterator<Item = EntryWithProject>) {
+        for entry in entries {
+            self.add_entry(entry);
+        }
+    }
+
+    fn add_entry(&mut self, entry: EntryWithProject) {
+        let message_id = &entry.entry.message.id;
+        if !message_id.is_empty() && !self.seen.insert((message_id.clone(), entry.entry.request_id.clone())) {
+            return;
+        }
+
+        let counted = self.filter.matches(&entry.project);
+        let timestamp = entry.entry.timestamp;
+        if (!counted && !self.account_window) || self.lookback_start.is_some_and(|start| timestamp < start) {
+            return;
+        }
+
+        if !self.stale {
+            match &mut self.current {
+                // May move the boundaries of every later window
+                Some(window) if timestamp < window.start => self.stale = true,
+                Some(window) if timestamp < window.end => window.add(&entry, counted),
+                // The first entry, or one past the end of the last window, starts a new window
+                _ => {
+                    let start = floor_to_hour(timestamp);
+                    let mut window = CurrentWindow::new(start, start + self.session_duration);
+                    window.add(&entry, counted);
+                    self.current = Some(window);
+                }
+            }
+        }
+
+        self.entries.insert((timestamp, self.next_seq), entry);
+        self.next_seq += 1;
+    }
+
+    /// The billing window active at `now`, as `group_into_single_window_with_duration` finds it
+    ///
+    /// `now` must not go backwards between calls.
+    pub fn window(&mut self, now: DateTime<Utc>) -> Option<SessionBlock> {
+        self.prune(now);
+        if self.stale {
+            self.recompute();
+        }
+
+        let current = self.current.as_ref()?;
+        if current.last_activity >= now - self.session_duration && now < current.end {
+            return Some(self.block(current, now));
+        }
+
+        // Only an earlier window can still be active, which takes entries
+        // timestamped in the future; regroup rather than track that case
+        let entries: Vec<EntryWithProject> = self.entries.values().cloned().collect();
+        if self.account_window {
+            group_subset_into_account_window(entries, now, self.session_duration, |e| self.filter.matches(&e.project))
+        } else {
+            group_into_single_window_with_duration(entries, now, self.session_duration)
+        }
+    }
+
+    /// Tokens used in each of the `minutes` minutes before `now`, oldest first
+    ///
+    /// Only usage that counts toward the window totals is included.
+    pub fn tokens_per_minute(&self, now: DateTime<Utc>, minutes: usize) -> Vec<u64> {
+        let mut buckets = vec![0; minutes];
+        let start = now - Duration::minutes(minutes as i64);
+        for ((timestamp, _), entry) in self.entries.range((start, 0)..) {
+            if *timestamp >= now {
+                break;
+            }
+            if let Some(usage) = entry.entry.message.usage.as_ref().filter(|_| self.filter.matches(&entry.project)) {
+                let minute = ((*timestamp - start).num_seconds() / 60) as usize;
+                buckets[minute] += usage.total();
+            }
+        }
+        buckets
+    }
+
+    fn block(&self, current: &CurrentWindow, now: DateTime<Utc>) -> SessionBlock {
+        if !self.account_window {
+            let mut block = current.totals.to_block(current.start, current.end, current.last_activity);
+            block.is_active = is_block_active(&block, now);
+            return block;
+        }
+
+        let last_activity = current.last_counted.unwrap_or(current.start);
+        let mut block = current.totals.to_block(current.start, current.end, last_activity);
+        block.is_active = true;
+        block
+    }
+
+    /// Drop entries that have aged out of the active window lookback
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let lookback_start = now - self.session_duration * ACTIVE_WINDOW_LOOKBACK;
+        self.lookback_start = Some(lookback_start);
+        while let Some(entry) = self.entries.first_entry() {
+            if entry.key().0 >= lookback_start {
+                break;
+            }
+            entry.remove();
+            // Window boundaries are found from the oldest entry on, so they may move
+            self.stale = true;
+        }
+    }
+
+    /// Work out the last window period and its totals from every entry held
+    fn recompute(&mut self) {
+        self.stale = false;
+        self.current = find_window_periods(self.entries.values(), self.session_duration)
+            .pop()
+            .map(|(start, end, _)| {
+                let mut window = CurrentWindow::new(start, end);
+                for entry in self.entries.range((start, 0)..).map(|(_, entry)| entry) {
+                    if entry.entry.timestamp >= end {
+                        break;
+                    }
+                    window.add(entry, self.filter.matches(&entry.project));
+                }
+                window
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,186 @@
+use super::*;
+use crate::coordinator::dedup_entries;
+use crate::types::{Message, TokenUsage, UsageEntry};
+use proptest::prelude::*;
+
+const PROJECTS: [&str; 3] = ["/work/client-a", "/work/client-b", "/home/scratch"];
+const MODELS: [&str; 2] = ["claude-opus-4-20250514", "claude-sonnet-4-20250514"];
+
+fn base_time() -> DateTime<Utc> {
+    "2025-01-13T00:00:00Z".parse().unwrap()
+}
+
+fn entry(minute: i64, id: u8, project: usize, model: usize, tokens: Option<u64>) -> EntryWithProject {
+    EntryWithProject {
+        entry: UsageEntry {
+            timestamp: base_time() + Duration::minutes(minute),
+            message: Message {
+                id: if id == 0 { String::new() } else { format!("msg_{}", id) },
+                msg_type: "message".to_string(),
+                role: "assistant".to_string(),
+                model: MODELS[model].to_string(),
+                usage: tokens.map(|tokens| TokenUsage {
+                    input_tokens: tokens,
+                    output_tokens: tokens / 2,
+                    cache_creation_input_tokens: tokens / 3,
+                    cache_read_input_tokens: tokens * 4,
+                }),
+            },
+            cost_usd: None,
+            request_id: format!("req_{}", id),
+            version: "1.0.51".to_string(),
+            unknown: Default::default(),
+        },
+        project: PROJECTS[project].to_string(),
+        session_id: "session".to_string(),
+    }
+}
+
+/// What a full reload gives: dedup, filter, then group everything
+fn regroup(entries: &[EntryWithProject], filter: &ProjectFilter, account_window: bool, now: DateTime<Utc>) -> Option<SessionBlock> {
+    let entries = dedup_entries(entries.to_vec());
+    let duration = Duration::hours(5);
+    if account_window {
+        group_subset_into_account_window(entries, now, duration, |e| filter.matches(&e.project))
+    } else {
+        group_into_single_window_with_duration(filter.apply(entries), now, duration)
+    }
+}
+
+/// A block's contents in a comparable form, with costs checked separately
+type Summary = (DateTime<Utc>, DateTime<Utc>, DateTime<Utc>, bool, u64, Vec<(String, u64, usize)>, Vec<(String, u64, usize)>);
+
+fn summarize(block: &SessionBlock) -> Summary {
+    let mut projects: Vec<_> = block.projects.iter()
+        .map(|p| (p.name.clone(), p.token_counts.total(), p.entry_count))
+        .collect();
+    projects.sort();
+    let mut models: Vec<_> = block.models.iter()
+        .map(|m| (m.model.clone(), m.token_counts.total(), m.entry_count))
+        .collect();
+    models.sort();
+    (block.start_time, block.end_time, block.last_activity, block.is_active, block.token_counts.total(), projects, models)
+}
+
+fn assert_same_window(incremental: Option<SessionBlock>, full: Option<SessionBlock>) {
+    match (incremental, full) {
+        (None, None) => {}
+        (Some(incremental), Some(full)) => {
+            assert_eq!(summarize(&incremental), summarize(&full));
+            assert!((incremental.cost_usd - full.cost_usd).abs() < 1e-9);
+        }
+        (incremental, full) => panic!("incremental {:?} but full {:?}", incremental, full),
+    }
+}
+
+fn arb_entry() -> impl Strategy<Value = EntryWithProject> {
+    (0..36 * 60i64, 0..40u8, 0..PROJECTS.len(), 0..MODELS.len(), prop::option::weighted(0.9, 1..5000u64))
+        .prop_map(|(minute, id, project, model, tokens)| entry(minute, id, project, model, tokens))
+}
+
+/// Batches of entries, each followed by a check at a later time
+fn arb_batches(max_skew: i64) -> impl Strategy<Value = Vec<(Vec<EntryWithProject>, i64)>> {
+    prop::collection::vec((prop::collection::vec(arb_entry(), 0..8), 0..180i64), 1..20)
+        .prop_map(move |batches| {
+            // Mostly in order: each batch is around the time of its check
+            let mut minute = 0;
+            batches.into_iter()
+                .map(|(entries, step)| {
+                    minute += step;
+                    let entries = entries.into_iter()
+                        .map(|mut e| {
+                            let offset = (e.entry.timestamp - base_time()).num_minutes() % (max_skew + 1);
+                            e.entry.timestamp = base_time() + Duration::minutes(minute - offset);
+                            e
+                        })
+                        .collect();
+                    (entries, minute)
+                })
+                .collect()
+        })
+}
+
+fn check_against_full_regroup(batches: Vec<(Vec<EntryWithProject>, i64)>, filter: ProjectFilter, account_window: bool) {
+    let mut state = UsageState::new(Duration::hours(5), filter.clone(), account_window);
+    let mut all = Vec::new();
+    for (entries, minute) in batches {
+        all.extend(entries.iter().cloned());
+        state.add_entries(entries);
+        let now = base_time() + Duration::minutes(minute);
+        assert_same_window(state.window(now), regroup(&all, &filter, account_window, now));
+    }
+}
+
+fn arb_filter() -> impl Strategy<Value = (ProjectFilter, bool)> {
+    prop_oneof![
+        Just((ProjectFilter::new(&[], &[]).unwrap(), false)),
+        Just((ProjectFilter::new(&["/work/*".to_string()], &[]).unwrap(), false)),
+        Just((ProjectFilter::new(&[], &["*scratch*".to_string()]).unwrap(), true)),
+        Just((ProjectFilter::new(&["*client-b".to_string()], &[]).unwrap(), true)),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn prop_appended_entries_match_full_regroup(batches in arb_batches(10), (filter, account_window) in arb_filter()) {
+        check_against_full_regroup(batches, filter, account_window);
+    }
+
+    #[test]
+    fn prop_late_entries_match_full_regroup(batches in arb_batches(8 * 60), (filter, account_window) in arb_filter()) {
+        check_against_full_regroup(batches, filter, account_window);
+    }
+
+    #[test]
+    fn prop_arbitrary_order_matches_full_regroup(
+        entries in prop::collection::vec(arb_entry(), 0..60),
+        checks in prop::collection::vec(0..48 * 60i64, 1..6),
+        (filter, account_window) in arb_filter(),
+    ) {
+        let mut checks = checks;
+        checks.sort();
+        let mut state = UsageState::new(Duration::hours(5), filter.clone(), account_window);
+        state.add_entries(entries.iter().cloned());
+        for minute in checks {
+            let now = base_time() + Duration::minutes(minute);
+            assert_same_window(state.window(now), regroup(&entries, &filter, account_window, now));
+        }
+    }
+}
+
+#[test]
+fn test_tokens_per_minute() {
+    let filter = ProjectFilter::new(&[], &["*scratch*".to_string()]).unwrap();
+    let mut state = UsageState::new(Duration::hours(5), filter, true);
+    state.add_entries([
+        entry(55, 1, 0, 0, Some(10)),
+        entry(58, 2, 0, 0, Some(10)),
+        entry(58, 3, 1, 1, Some(20)),
+        entry(59, 4, 2, 0, Some(1000)),
+        entry(60, 5, 0, 0, Some(10)),
+    ]);
+
+    // Scratch is filtered out, and the entry at `now` falls after the last minute
+    let total = |tokens: u64| tokens + tokens / 2 + tokens / 3 + tokens * 4;
+    let now = base_time() + Duration::minutes(60);
+    assert_eq!(state.tokens_per_minute(now, 3), [0, total(10) + total(20), 0]);
+    assert_eq!(state.tokens_per_minute(now, 0), Vec::<u64>::new());
+}
+
+#[test]
+fn test_window_rolls_over() {
+    let mut state = UsageState::new(Duration::hours(5), ProjectFilter::new(&[], &[]).unwrap(), false);
+    state.add_entries([entry(30, 1, 0, 0, Some(100)), entry(90, 2, 1, 0, Some(100))]);
+
+    let window = state.window(base_time() + Duration::hours(2)).unwrap();
+    assert_eq!(window.start_time, base_time());
+    assert_eq!(window.projects.len(), 2);
+
+    // Past the end nothing is active until the next entry starts a new window
+    assert!(state.window(base_time() + Duration::hours(5)).is_none());
+    state.add_entries([entry(5 * 60 + 20, 3, 0, 1, Some(100))]);
+    let window = state.window(base_time() + Duration::minutes(5 * 60 + 30)).unwrap();
+    assert_eq!(window.start_time, base_time() + Duration::hours(5));
+    assert_eq!(window.token_counts.input_tokens, 100);
+    assert!(!state.stale);
+}
@@ -1,19 +1,80 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use notify::event::{ModifyKind, RenameMode};
 use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use anyhow::{Context, Result};
 
 /// File system event that we care about
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FileEvent {
-    Modified(()),
-    Created(()),
+    Modified(PathBuf),
+    Created(PathBuf),
+    Removed(PathBuf),
+    /// Renamed within the watched directories; either side may not be a JSONL file
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+/// What happened to a file over a burst of events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathChange {
+    /// Created or written; needs re-reading
+    Updated,
+    /// Removed or renamed away
+    Removed,
+}
+
+/// File events coalesced per path, keeping only each path's latest state
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSet {
+    changes: HashMap<PathBuf, PathChange>,
+}
+
+impl ChangeSet {
+    pub fn add(&mut self, event: FileEvent) {
+        match event {
+            FileEvent::Modified(path) | FileEvent::Created(path) => self.set(path, PathChange::Updated),
+            FileEvent::Removed(path) => self.set(path, PathChange::Removed),
+            FileEvent::Renamed { from, to } => {
+                self.set(from, PathChange::Removed);
+                self.set(to, PathChange::Updated);
+            }
+        }
+    }
+    
+    fn set(&mut self, path: PathBuf, change: PathChange) {
+        if is_jsonl_file(&path) {
+            self.changes.insert(path, change);
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+    
+    /// Paths that were created or written, in no particular order
+    pub fn updated(&self) -> impl Iterator<Item = &Path> {
+        self.paths_with(PathChange::Updated)
+    }
+    
+    /// Paths that no longer exist, in no particular order
+    pub fn removed(&self) -> impl Iterator<Item = &Path> {
+        self.paths_with(PathChange::Removed)
+    }
+    
+    fn paths_with(&self, wanted: PathChange) -> impl Iterator<Item = &Path> {
+        self.changes.iter()
+            .filter(move |(_, change)| **change == wanted)
+            .map(|(path, _)| path.as_path())
+    }
 }
 
 /// Watch Claude session directories for JSONL file changes
 pub struct SessionWatcher {
-    _watcher: RecommendedWatcher,
-    receiver: Receiver<FileEvent>,
+    _watcher: Arc<Mutex<RecommendedWatcher>>,
+    /// `None` when events go to a handler instead
+    receiver: Option<Receiver<FileEvent>>,
 }
 
 impl SessionWatcher {
@@ -21,82 +82,216 @@ impl SessionWatcher {
     pub fn new(paths: Vec<PathBuf>) -> Result<Self> {
         let (tx, rx) = channel();
         
-        // Create the file system watcher
-        let mut watcher = RecommendedWatcher::new(
-            move |res: Result<Event, notify::Error>| {
-                if let Ok(event) = res {
-                    // Filter for JSONL files and relevant events
-                    if let Some(file_event) = filter_event(event) {
-                        // Ignore send errors (receiver might be dropped)
-                        let _ = tx.send(file_event);
-                    }
-                }
-            },
-            Config::default(),
-        ).context("Failed to create file watcher")?;
-        
-        // Watch each projects directory
-        for base_path in &paths {
-            let projects_dir = base_path.join("projects");
-            if projects_dir.exists() {
-                watcher.watch(&projects_dir, RecursiveMode::Recursive)
-                    .with_context(|| format!("Failed to watch directory: {}", projects_dir.display()))?;
-            }
-        }
+        let watcher = watch_projects(&paths, move |file_event| {
+            // Ignore send errors (receiver might be dropped)
+            let _ = tx.send(file_event);
+        })?;
         
         Ok(Self {
             _watcher: watcher,
-            receiver: rx,
+            receiver: Some(rx),
+        })
+    }
+    
+    /// Watch the given paths, calling `handler` from the watcher thread for every event
+    /// 
+    /// Lets callers feed file events into their own channel instead of polling.
+    /// Nothing is delivered through `poll_events`.
+    pub fn with_handler<F>(paths: Vec<PathBuf>, handler: F) -> Result<Self>
+    where
+        F: Fn(FileEvent) + Send + 'static,
+    {
+        Ok(Self {
+            _watcher: watch_projects(&paths, handler)?,
+            receiver: None,
         })
     }
     
     /// Check for file events (non-blocking)
+    #[allow(dead_code)]
     pub fn poll_events(&self) -> Vec<FileEvent> {
         let mut events = Vec::new();
         
         // Drain all pending events
-        while let Ok(event) = self.receiver.try_recv() {
-            events.push(event);
+        if let Some(receiver) = &self.receiver {
+            while let Ok(event) = receiver.try_recv() {
+                events.push(event);
+            }
         }
         
         events
     }
     
     /// Create a watcher with default Claude paths
+    #[allow(dead_code)]
     pub fn with_default_paths() -> Result<Self> {
-        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
-        let paths = vec![
-            PathBuf::from(&home).join(".claude"),
-            PathBuf::from(&home).join(".config/claude"),
-        ];
-        
-        Self::new(paths)
+        Self::new(crate::config::default_claude_paths())
     }
 }
 
-/// Filter file system events to only JSONL file modifications and creations
-fn filter_event(event: Event) -> Option<FileEvent> {
-    match event.kind {
-        EventKind::Modify(_) => {
-            // File was modified
-            for path in &event.paths {
-                if is_jsonl_file(path) {
-                    return Some(FileEvent::Modified(()));
+/// Watch each base path's projects directory, passing relevant events to `handler`
+///
+/// A projects directory that doesn't exist yet is waited for by watching its
+/// nearest existing ancestor; once it appears it is watched instead, and any
+/// session files already in it are reported as created. If it is removed
+/// later, the ancestor is watched again.
+fn watch_projects<F>(paths: &[PathBuf], handler: F) -> Result<Arc<Mutex<RecommendedWatcher>>>
+where
+    F: Fn(FileEvent) + Send + 'static,
+{
+    // Watches can't be changed from inside notify's callback, so raw events
+    // are handled on a thread of our own
+    let (raw_tx, raw_rx) = channel();
+    let watcher = RecommendedWatcher::new(raw_tx, Config::default())
+        .context("Failed to create file watcher")?;
+    let watcher = Arc::new(Mutex::new(watcher));
+    
+    let mut watches = Watches::new(paths.iter().map(|base| base.join("projects")).collect());
+    watches.sync(&mut watcher.lock().unwrap())?;
+    
+    // The thread only holds a weak reference, so dropping the watcher closes
+    // the raw channel and ends it
+    let weak_watcher = Arc::downgrade(&watcher);
+    std::thread::spawn(move || {
+        for event in raw_rx.into_iter().flatten() {
+            let Some(watcher) = weak_watcher.upgrade() else {
+                break;
+            };
+            
+            let moves_watches = watches.moves_watches(&event);
+            if watches.covers(&event) {
+                for file_event in filter_event(event) {
+                    handler(file_event);
+                }
+            }
+            if moves_watches {
+                match watches.sync(&mut watcher.lock().unwrap()) {
+                    Ok(files) => files.into_iter().for_each(|path| handler(FileEvent::Created(path))),
+                    Err(e) => eprintln!("Warning: {:#}", e),
                 }
             }
         }
-        EventKind::Create(_) => {
-            // File was created
-            for path in &event.paths {
-                if is_jsonl_file(path) {
-                    return Some(FileEvent::Created(()));
+    });
+    
+    Ok(watcher)
+}
+
+/// The directories watched for a set of projects directories
+///
+/// Each projects directory is watched recursively once it exists, and until
+/// then its nearest existing ancestor is watched non-recursively. Several
+/// projects directories may wait on the same ancestor.
+#[derive(Debug)]
+struct Watches {
+    projects_dirs: Vec<PathBuf>,
+    /// Watched directories, and whether each is watched recursively
+    active: HashMap<PathBuf, bool>,
+}
+
+impl Watches {
+    fn new(projects_dirs: Vec<PathBuf>) -> Self {
+        Self { projects_dirs, active: HashMap::new() }
+    }
+    
+    /// The directories that should be watched right now
+    fn targets(&self) -> HashMap<PathBuf, bool> {
+        let mut targets = HashMap::new();
+        for projects_dir in &self.projects_dirs {
+            if projects_dir.is_dir() {
+                targets.insert(projects_dir.clone(), true);
+            } else if let Some(ancestor) = projects_dir.ancestors().skip(1).find(|dir| dir.is_dir()) {
+                targets.entry(ancestor.to_path_buf()).or_insert(false);
+            }
+        }
+        targets
+    }
+    
+    /// Move the watches to the current targets, returning the session files in
+    /// projects directories that are newly watched
+    fn sync(&mut self, watcher: &mut RecommendedWatcher) -> Result<Vec<PathBuf>> {
+        let mut found = Vec::new();
+        loop {
+            let targets = self.targets();
+            if targets == self.active {
+                return Ok(found);
+            }
+            
+            for (dir, recursive) in &self.active {
+                if targets.get(dir) != Some(recursive) {
+                    // Fails if the directory is gone, which removed the watch anyway
+                    let _ = watcher.unwatch(dir);
+                }
+            }
+            self.active.retain(|dir, recursive| targets.get(dir) == Some(recursive));
+            
+            for (dir, recursive) in targets {
+                if self.active.contains_key(&dir) {
+                    continue;
+                }
+                let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+                watcher.watch(&dir, mode)
+                    .with_context(|| format!("Failed to watch directory: {}", dir.display()))?;
+                if recursive {
+                    collect_jsonl_files(&dir, &mut found);
                 }
+                self.active.insert(dir, recursive);
             }
+            // Directories may have appeared or gone since the targets were
+            // worked out, so check again
         }
-        _ => {}
     }
     
-    None
+    /// Whether `event` touches a projects directory or a directory on the way to one
+    fn moves_watches(&self, event: &Event) -> bool {
+        event.paths.iter().any(|path| {
+            self.projects_dirs.iter().any(|projects_dir| projects_dir.starts_with(path))
+        })
+    }
+    
+    /// Whether `event` is about files in a watched projects directory
+    fn covers(&self, event: &Event) -> bool {
+        event.paths.iter().any(|path| {
+            self.active.iter().any(|(dir, recursive)| *recursive && path.starts_with(dir))
+        })
+    }
+}
+
+/// Add every JSONL file under `dir` to `files`
+fn collect_jsonl_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_jsonl_files(&path, files);
+        } else if is_jsonl_file(&path) {
+            files.push(path);
+        }
+    }
+}
+
+/// Turn a file system event into the JSONL file events it represents
+fn filter_event(event: Event) -> Vec<FileEvent> {
+    let jsonl_paths = || event.paths.iter().filter(|path| is_jsonl_file(path)).cloned();
+    
+    match event.kind {
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            let (from, to) = (&event.paths[0], &event.paths[1]);
+            if is_jsonl_file(from) || is_jsonl_file(to) {
+                vec![FileEvent::Renamed { from: from.clone(), to: to.clone() }]
+            } else {
+                Vec::new()
+            }
+        }
+        // Only one side of a rename is inside the watched directories
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => jsonl_paths().map(FileEvent::Removed).collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => jsonl_paths().map(FileEvent::Created).collect(),
+        EventKind::Modify(_) => jsonl_paths().map(FileEvent::Modified).collect(),
+        EventKind::Create(_) => jsonl_paths().map(FileEvent::Created).collect(),
+        EventKind::Remove(_) => jsonl_paths().map(FileEvent::Removed).collect(),
+        _ => Vec::new(),
+    }
 }
 
 /// Check if a path is a JSONL file
@@ -152,10 +347,173 @@ mod tests {
         
         // Verify we got a creation event for our file
         let has_creation = events.iter().any(|e| {
-            matches!(e, FileEvent::Created(_))
+            matches!(e, FileEvent::Created(path) if path.ends_with("test.jsonl"))
         });
         assert!(has_creation, "Should have creation event for test.jsonl");
         
         Ok(())
     }
+    
+    fn sorted(paths: impl Iterator<Item = impl AsRef<Path>>) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = paths.map(|p| p.as_ref().to_path_buf()).collect();
+        paths.sort();
+        paths
+    }
+    
+    #[test]
+    fn test_change_set_coalesces_per_path() {
+        let mut changes = ChangeSet::default();
+        changes.add(FileEvent::Modified(PathBuf::from("/p/a.jsonl")));
+        changes.add(FileEvent::Modified(PathBuf::from("/p/a.jsonl")));
+        changes.add(FileEvent::Created(PathBuf::from("/p/b.jsonl")));
+        changes.add(FileEvent::Removed(PathBuf::from("/p/b.jsonl")));
+        changes.add(FileEvent::Renamed { from: PathBuf::from("/p/a.jsonl"), to: PathBuf::from("/p/c.jsonl") });
+        changes.add(FileEvent::Renamed { from: PathBuf::from("/p/d.tmp"), to: PathBuf::from("/p/d.jsonl") });
+        
+        assert_eq!(sorted(changes.updated()), [PathBuf::from("/p/c.jsonl"), PathBuf::from("/p/d.jsonl")]);
+        assert_eq!(sorted(changes.removed()), [PathBuf::from("/p/a.jsonl"), PathBuf::from("/p/b.jsonl")]);
+    }
+    
+    #[test]
+    fn test_filter_event() {
+        let rename = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(PathBuf::from("/p/a.jsonl"))
+            .add_path(PathBuf::from("/p/b.jsonl"));
+        assert_eq!(
+            filter_event(rename),
+            [FileEvent::Renamed { from: PathBuf::from("/p/a.jsonl"), to: PathBuf::from("/p/b.jsonl") }]
+        );
+        
+        let moved_out = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::From)))
+            .add_path(PathBuf::from("/p/a.jsonl"));
+        assert_eq!(filter_event(moved_out), [FileEvent::Removed(PathBuf::from("/p/a.jsonl"))]);
+        
+        let removed = Event::new(EventKind::Remove(notify::event::RemoveKind::File))
+            .add_path(PathBuf::from("/p/a.jsonl"))
+            .add_path(PathBuf::from("/p/notes.txt"));
+        assert_eq!(filter_event(removed), [FileEvent::Removed(PathBuf::from("/p/a.jsonl"))]);
+    }
+    
+    #[test]
+    fn test_watcher_reports_removals_and_renames() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let projects_dir = temp_dir.path().join("projects");
+        fs::create_dir_all(&projects_dir)?;
+        let first = projects_dir.join("first.jsonl");
+        let second = projects_dir.join("second.jsonl");
+        fs::write(&first, "{}\n")?;
+        fs::write(&second, "{}\n")?;
+        
+        let watcher = SessionWatcher::new(vec![temp_dir.path().to_path_buf()])?;
+        thread::sleep(Duration::from_millis(100));
+        
+        fs::remove_file(&first)?;
+        fs::rename(&second, projects_dir.join("renamed.jsonl"))?;
+        thread::sleep(Duration::from_millis(100));
+        
+        let mut changes = ChangeSet::default();
+        for event in watcher.poll_events() {
+            changes.add(event);
+        }
+        assert_eq!(sorted(changes.removed()), [first, second]);
+        assert_eq!(sorted(changes.updated()), [projects_dir.join("renamed.jsonl")]);
+        
+        Ok(())
+    }
+    
+    /// Collect events until one matches `wanted`, or give up after two seconds
+    fn wait_for(watcher: &SessionWatcher, wanted: impl Fn(&FileEvent) -> bool) -> Vec<FileEvent> {
+        let mut events = Vec::new();
+        for _ in 0..40 {
+            events.extend(watcher.poll_events());
+            if events.iter().any(&wanted) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        events
+    }
+    
+    fn created(path: &Path) -> impl Fn(&FileEvent) -> bool + '_ {
+        move |event| matches!(event, FileEvent::Created(p) | FileEvent::Modified(p) if p == path)
+    }
+    
+    #[test]
+    fn test_watches_move_to_targets() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let first = temp_dir.path().join("a").join("projects");
+        let second = temp_dir.path().join("b").join("projects");
+        let watches = Watches::new(vec![first.clone(), second.clone()]);
+        
+        // Both wait on the shared ancestor
+        let expected: HashMap<PathBuf, bool> = [(temp_dir.path().to_path_buf(), false)].into_iter().collect();
+        assert_eq!(watches.targets(), expected);
+        
+        fs::create_dir_all(&first)?;
+        fs::create_dir_all(temp_dir.path().join("b"))?;
+        let expected: HashMap<PathBuf, bool> = [(first, true), (temp_dir.path().join("b"), false)].into_iter().collect();
+        assert_eq!(watches.targets(), expected);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_watcher_waits_for_projects_dir() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let base = temp_dir.path().join("config").join("claude");
+        let watcher = SessionWatcher::new(vec![base.clone()])?;
+        thread::sleep(Duration::from_millis(100));
+        
+        // Created in one go, as `mkdir -p` would
+        let project_dir = base.join("projects").join("-home-me-proj");
+        fs::create_dir_all(&project_dir)?;
+        let session = project_dir.join("session.jsonl");
+        fs::write(&session, "{}\n")?;
+        
+        let events = wait_for(&watcher, created(&session));
+        assert!(events.iter().any(created(&session)), "got {:?}", events);
+        
+        // Later sessions are picked up by the projects directory's own watch
+        let later = project_dir.join("later.jsonl");
+        fs::write(&later, "{}\n")?;
+        let events = wait_for(&watcher, created(&later));
+        assert!(events.iter().any(created(&later)), "got {:?}", events);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_watcher_survives_projects_dir_recreation() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let projects_dir = temp_dir.path().join("projects");
+        fs::create_dir_all(&projects_dir)?;
+        let watcher = SessionWatcher::new(vec![temp_dir.path().to_path_buf()])?;
+        thread::sleep(Duration::from_millis(100));
+        
+        fs::remove_dir(&projects_dir)?;
+        thread::sleep(Duration::from_millis(100));
+        fs::create_dir(&projects_dir)?;
+        thread::sleep(Duration::from_millis(100));
+        
+        let session = projects_dir.join("session.jsonl");
+        fs::write(&session, "{}\n")?;
+        let events = wait_for(&watcher, created(&session));
+        assert!(events.iter().any(created(&session)), "got {:?}", events);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_files_outside_projects_dirs_are_ignored() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let watcher = SessionWatcher::new(vec![temp_dir.path().join("claude")])?;
+        thread::sleep(Duration::from_millis(100));
+        
+        // In the watched ancestor, but not a session file
+        fs::write(temp_dir.path().join("stray.jsonl"), "{}\n")?;
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(watcher.poll_events(), []);
+        
+        Ok(())
+    }
 }
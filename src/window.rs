@@ -1,13 +1,13 @@
 use chrono::{DateTime, Duration, Utc};
 use std::collections::HashMap;
 
+use crate::config::DEFAULT_SESSION_DURATION_HOURS;
+use crate::pricing::entry_cost;
 use crate::types::{
-    UsageEntry, SessionBlock, ProjectUsage, TokenCounts, 
+    UsageEntry, SessionBlock, ProjectUsage, ModelUsage, SessionUsage, TokenCounts, TokenUsage,
     floor_to_hour, is_block_active, EntryWithProject
 };
 
-const SESSION_DURATION_HOURS: i64 = 5;
-
 /// Group usage entries into a single account-wide billing window
 /// 
 /// This implements the core billing window algorithm based on Claude Code's actual model:
@@ -35,7 +35,7 @@ pub fn group_into_single_window(entries: Vec<UsageEntry>) -> Option<SessionBlock
     // Find the earliest timestamp - this starts our single window
     let earliest_entry = sorted_entries.first()?;
     let window_start = floor_to_hour(earliest_entry.timestamp);
-    let window_end = window_start + Duration::hours(SESSION_DURATION_HOURS);
+    let window_end = window_start + Duration::hours(DEFAULT_SESSION_DURATION_HOURS);
     
     // Filter entries that fall within this 5-hour window
     let window_entries: Vec<UsageEntry> = sorted_entries
@@ -67,12 +67,14 @@ fn create_window(start_time: DateTime<Utc>, entries: &[UsageEntry]) -> Option<Se
         return None;
     }
     
-    let end_time = start_time + Duration::hours(SESSION_DURATION_HOURS);
+    let end_time = start_time + Duration::hours(DEFAULT_SESSION_DURATION_HOURS);
     let last_activity = entries.last()?.timestamp;
     
     // Group entries by project
     let mut project_map: HashMap<String, ProjectUsage> = HashMap::new();
+    let mut model_map: HashMap<String, ModelUsage> = HashMap::new();
     let mut total_tokens = TokenCounts::default();
+    let mut total_cost = 0.0;
     
     for entry in entries {
         // Extract project name from request ID or use "unknown"
@@ -80,32 +82,57 @@ fn create_window(start_time: DateTime<Utc>, entries: &[UsageEntry]) -> Option<Se
         let project_name = extract_project_name(entry);
         
         if let Some(usage) = &entry.message.usage {
+            let cost = entry_cost(entry);
             total_tokens.add_usage(usage);
+            total_cost += cost;
             
             let project = project_map.entry(project_name.clone())
                 .or_insert_with(|| ProjectUsage {
                     name: project_name,
                     token_counts: TokenCounts::default(),
+                    cost_usd: 0.0,
                     entry_count: 0,
+                    sessions: Vec::new(),
                 });
             
             project.token_counts.add_usage(usage);
+            project.cost_usd += cost;
             project.entry_count += 1;
+            
+            add_model_usage(&mut model_map, &entry.message.model, usage, cost);
         }
     }
     
     let projects: Vec<ProjectUsage> = project_map.into_values().collect();
+    let models: Vec<ModelUsage> = model_map.into_values().collect();
     
     Some(SessionBlock {
         start_time,
         end_time,
         last_activity,
         projects,
+        models,
         token_counts: total_tokens,
+        cost_usd: total_cost,
         is_active: false, // Will be updated by caller
     })
 }
 
+/// Add one entry's usage to the per-model breakdown
+fn add_model_usage(model_map: &mut HashMap<String, ModelUsage>, model: &str, usage: &TokenUsage, cost: f64) {
+    let model_usage = model_map.entry(model.to_string())
+        .or_insert_with(|| ModelUsage {
+            model: model.to_string(),
+            token_counts: TokenCounts::default(),
+            cost_usd: 0.0,
+            entry_count: 0,
+        });
+    
+    model_usage.token_counts.add_usage(usage);
+    model_usage.cost_usd += cost;
+    model_usage.entry_count += 1;
+}
+
 /// Extract project name from entry (placeholder implementation)
 #[allow(dead_code)]
 fn extract_project_name(entry: &UsageEntry) -> String {
@@ -123,51 +150,75 @@ pub fn is_window_active(window: &SessionBlock) -> bool {
     window.is_active
 }
 
+/// How many window lengths before now entries are considered when looking for the active window
+pub const ACTIVE_WINDOW_LOOKBACK: i32 = 3;
+
 /// Find the currently active billing window period based on recent activity
 /// 
 /// Returns Some((start_time, end_time)) if there's an active window, None otherwise.
 /// This uses chronological processing to correctly identify which window entries belong to.
-pub fn find_active_window_period(entries: &[EntryWithProject], now: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
-    if entries.is_empty() {
-        return None;
-    }
-    
-    // Look back 15 hours to catch windows that might have started earlier
-    let fifteen_hours_ago = now - Duration::hours(SESSION_DURATION_HOURS * 3);
-    
-    // Get entries from the last 15 hours and sort chronologically (oldest first)
-    let mut recent_entries: Vec<&EntryWithProject> = entries
+pub fn find_active_window_period(
+    entries: &[EntryWithProject],
+    now: DateTime<Utc>,
+    session_duration: Duration,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    // Look back a few window lengths to catch windows that might have started earlier
+    let lookback_start = now - session_duration * ACTIVE_WINDOW_LOOKBACK;
+    
+    let recent_entries: Vec<&EntryWithProject> = entries
         .iter()
-        .filter(|e| e.entry.timestamp >= fifteen_hours_ago)
+        .filter(|e| e.entry.timestamp >= lookback_start)
         .collect();
     
-    if recent_entries.is_empty() {
-        return None;
+    let windows = find_window_periods(recent_entries, session_duration);
+    
+    // Find the active window: has recent activity and hasn't ended
+    let inactivity_cutoff = now - session_duration;
+    
+    for (start, end, last_activity) in windows.iter().rev() {
+        // Window is active if:
+        // 1. Last activity was within one window length
+        // 2. Window end time hasn't passed
+        
+        if *last_activity >= inactivity_cutoff && now < *end {
+            return Some((*start, *end));
+        }
     }
     
+    None
+}
+
+/// Find every billing window period in a set of entries
+/// 
+/// Returns (start_time, end_time, last_activity) for each window, oldest first.
+/// Entries are processed chronologically: an entry starts a new window (at its
+/// floored hour) whenever it falls `session_duration` or more after the current window start.
+/// Because new windows start on the hour after the previous one ended, periods
+/// never overlap.
+pub fn find_window_periods<'a, I>(entries: I, session_duration: Duration) -> Vec<(DateTime<Utc>, DateTime<Utc>, DateTime<Utc>)>
+where
+    I: IntoIterator<Item = &'a EntryWithProject>,
+{
     // Sort by timestamp (oldest first) - this is KEY for correct window assignment
-    recent_entries.sort_by_key(|e| e.entry.timestamp);
-    
+    let mut timestamps: Vec<DateTime<Utc>> = entries.into_iter().map(|e| e.entry.timestamp).collect();
+    timestamps.sort();
     
-    // Process entries chronologically to find windows
     let mut windows: Vec<(DateTime<Utc>, DateTime<Utc>, DateTime<Utc>)> = Vec::new();
     let mut current_window_start: Option<DateTime<Utc>> = None;
     let mut last_activity: Option<DateTime<Utc>> = None;
     
-    for entry in recent_entries {
-        let entry_time = entry.entry.timestamp;
-        
+    for entry_time in timestamps {
         if let Some(window_start) = current_window_start {
             let time_since_window_start = entry_time - window_start;
             
             // Check if this entry belongs to the current window
-            if time_since_window_start < Duration::hours(SESSION_DURATION_HOURS) {
+            if time_since_window_start < session_duration {
                 // Update last activity in current window
                 last_activity = Some(entry_time);
             } else {
                 // Entry is beyond current window - save current window and start new one
                 if let Some(last_act) = last_activity {
-                    let window_end = window_start + Duration::hours(SESSION_DURATION_HOURS);
+                    let window_end = window_start + session_duration;
                     windows.push((window_start, window_end, last_act));
                 }
                 
@@ -184,25 +235,35 @@ pub fn find_active_window_period(entries: &[EntryWithProject], now: DateTime<Utc
     
     // Don't forget the last window
     if let (Some(window_start), Some(last_act)) = (current_window_start, last_activity) {
-        let window_end = window_start + Duration::hours(SESSION_DURATION_HOURS);
+        let window_end = window_start + session_duration;
         windows.push((window_start, window_end, last_act));
     }
     
-    
-    // Find the active window: has recent activity and hasn't ended
-    let five_hours_ago = now - Duration::hours(SESSION_DURATION_HOURS);
-    
-    for (start, end, last_activity) in windows.iter().rev() {
-        // Window is active if:
-        // 1. Last activity was within 5 hours
-        // 2. Window end time hasn't passed
-        
-        if *last_activity >= five_hours_ago && now < *end {
-            return Some((*start, *end));
+    windows
+}
+
+/// Split entries into the billing windows they belong to
+/// 
+/// Returns each window's start time with its entries (sorted chronologically), oldest first.
+pub fn split_into_windows(
+    mut entries: Vec<EntryWithProject>,
+    session_duration: Duration,
+) -> Vec<(DateTime<Utc>, Vec<EntryWithProject>)> {
+    let periods = find_window_periods(&entries, session_duration);
+    entries.sort_by_key(|e| e.entry.timestamp);
+    
+    let mut windows = Vec::with_capacity(periods.len());
+    let mut remaining = entries.into_iter().peekable();
+    
+    for (start, end, _) in periods {
+        let mut window_entries = Vec::new();
+        while let Some(entry) = remaining.next_if(|e| e.entry.timestamp < end) {
+            window_entries.push(entry);
         }
+        windows.push((start, window_entries));
     }
     
-    None
+    windows
 }
 
 /// Group usage entries with project info into a single account-wide billing window
@@ -213,6 +274,7 @@ pub fn find_active_window_period(entries: &[EntryWithProject], now: DateTime<Utc
 /// - Multiple projects can contribute usage within the same window
 /// - Token usage is correctly attributed to each project
 /// - The window is based on RECENT activity (within last 5 hours)
+#[allow(dead_code)]
 pub fn group_into_single_window_with_projects(entries: Vec<EntryWithProject>) -> Option<SessionBlock> {
     group_into_single_window_with_projects_at_time(entries, Utc::now())
 }
@@ -220,21 +282,29 @@ pub fn group_into_single_window_with_projects(entries: Vec<EntryWithProject>) ->
 /// Group usage entries with project info into a single account-wide billing window at a specific time
 /// 
 /// This version accepts a "now" parameter for testing with historical data.
+#[allow(dead_code)]
 pub fn group_into_single_window_with_projects_at_time(
     entries: Vec<EntryWithProject>,
     now: DateTime<Utc>
+) -> Option<SessionBlock> {
+    group_into_single_window_with_duration(entries, now, Duration::hours(DEFAULT_SESSION_DURATION_HOURS))
+}
+
+/// Group entries into the billing window active at `now`, for a configured window length
+pub fn group_into_single_window_with_duration(
+    entries: Vec<EntryWithProject>,
+    now: DateTime<Utc>,
+    session_duration: Duration,
 ) -> Option<SessionBlock> {
     if entries.is_empty() {
         return None;
     }
     
     // Find the active window period based on recent activity
-    let window_period = find_active_window_period(&entries, now);
+    let window_period = find_active_window_period(&entries, now, session_duration);
     
     match window_period {
-        None => {
-            return None;
-        }
+        None => None,
         Some((window_start, window_end)) => {
             // Filter entries that fall within the active window
             let window_entries: Vec<EntryWithProject> = entries
@@ -243,7 +313,7 @@ pub fn group_into_single_window_with_projects_at_time(
                 .collect();
             
             // Create the single window with all entries in the active period
-            let mut window = create_window_with_projects(window_start, &window_entries)?;
+            let mut window = create_window_with_projects(window_start, &window_entries, session_duration)?;
             
             // Update active status
             window.is_active = is_block_active(&window, now);
@@ -253,47 +323,156 @@ pub fn group_into_single_window_with_projects_at_time(
     }
 }
 
+/// Group a subset of entries into the account-wide billing window active at `now`
+/// 
+/// Window boundaries and active status come from all `entries`, but only the
+/// ones `keep` accepts are counted. If none of them fall in the active window,
+/// an empty window is returned so the boundaries can still be shown.
+pub fn group_subset_into_account_window<F>(
+    entries: Vec<EntryWithProject>,
+    now: DateTime<Utc>,
+    session_duration: Duration,
+    keep: F,
+) -> Option<SessionBlock>
+where
+    F: Fn(&EntryWithProject) -> bool,
+{
+    let (window_start, window_end) = find_active_window_period(&entries, now, session_duration)?;
+    
+    let window_entries: Vec<EntryWithProject> = entries
+        .into_iter()
+        .filter(|e| e.entry.timestamp >= window_start && e.entry.timestamp < window_end && keep(e))
+        .collect();
+    
+    let mut window = create_window_with_projects(window_start, &window_entries, session_duration)
+        .unwrap_or_else(|| SessionBlock {
+            start_time: window_start,
+            end_time: window_end,
+            last_activity: window_start,
+            projects: Vec::new(),
+            models: Vec::new(),
+            token_counts: TokenCounts::default(),
+            cost_usd: 0.0,
+            is_active: false,
+        });
+    window.is_active = true;
+    
+    Some(window)
+}
+
 /// Create a SessionBlock from entries with project info
-fn create_window_with_projects(start_time: DateTime<Utc>, entries: &[EntryWithProject]) -> Option<SessionBlock> {
+pub fn create_window_with_projects(
+    start_time: DateTime<Utc>,
+    entries: &[EntryWithProject],
+    session_duration: Duration,
+) -> Option<SessionBlock> {
     if entries.is_empty() {
         return None;
     }
     
-    let end_time = start_time + Duration::hours(SESSION_DURATION_HOURS);
-    let last_activity = entries.last()?.entry.timestamp;
-    
-    // Group entries by project
-    let mut project_map: HashMap<String, ProjectUsage> = HashMap::new();
-    let mut total_tokens = TokenCounts::default();
+    let end_time = start_time + session_duration;
+    let last_activity = entries.iter().map(|e| e.entry.timestamp).max()?;
     
+    let mut totals = WindowTotals::default();
     for entry_with_project in entries {
+        totals.add(entry_with_project);
+    }
+    
+    Some(totals.to_block(start_time, end_time, last_activity))
+}
+
+/// Running usage totals of a window, overall and by project, session and model
+/// 
+/// Entries can be added one at a time, so a window can be kept up to date as
+/// new entries arrive instead of being rebuilt from all of them.
+#[derive(Debug, Clone, Default)]
+pub struct WindowTotals {
+    projects: HashMap<String, ProjectUsage>,
+    /// Keyed by project and session id; a session's models are kept with it
+    sessions: HashMap<(String, String), (SessionUsage, HashMap<String, ModelUsage>)>,
+    models: HashMap<String, ModelUsage>,
+    token_counts: TokenCounts,
+    cost_usd: f64,
+}
+
+impl WindowTotals {
+    /// Count one entry; entries without usage don't change the totals
+    pub fn add(&mut self, entry_with_project: &EntryWithProject) {
+        let Some(usage) = &entry_with_project.entry.message.usage else {
+            return;
+        };
         let project_name = &entry_with_project.project;
+        let cost = entry_cost(&entry_with_project.entry);
+        self.token_counts.add_usage(usage);
+        self.cost_usd += cost;
+        
+        let project = self.projects.entry(project_name.clone())
+            .or_insert_with(|| ProjectUsage {
+                name: project_name.clone(),
+                token_counts: TokenCounts::default(),
+                cost_usd: 0.0,
+                entry_count: 0,
+                sessions: Vec::new(),
+            });
+        
+        project.token_counts.add_usage(usage);
+        project.cost_usd += cost;
+        project.entry_count += 1;
+        
+        let timestamp = entry_with_project.entry.timestamp;
+        let (session, session_models) = self.sessions
+            .entry((project_name.clone(), entry_with_project.session_id.clone()))
+            .or_insert_with(|| (SessionUsage {
+                session_id: entry_with_project.session_id.clone(),
+                token_counts: TokenCounts::default(),
+                cost_usd: 0.0,
+                entry_count: 0,
+                first_activity: timestamp,
+                last_activity: timestamp,
+                models: Vec::new(),
+            }, HashMap::new()));
+        
+        session.token_counts.add_usage(usage);
+        session.cost_usd += cost;
+        session.entry_count += 1;
+        session.first_activity = session.first_activity.min(timestamp);
+        session.last_activity = session.last_activity.max(timestamp);
+        
+        let model = &entry_with_project.entry.message.model;
+        add_model_usage(session_models, model, usage, cost);
+        add_model_usage(&mut self.models, model, usage, cost);
+    }
+    
+    /// A window with these totals; `is_active` is left for the caller to set
+    pub fn to_block(&self, start_time: DateTime<Utc>, end_time: DateTime<Utc>, last_activity: DateTime<Utc>) -> SessionBlock {
+        let mut projects = self.projects.clone();
+        for ((project, _), (session, models)) in &self.sessions {
+            let mut session = session.clone();
+            session.models = models.values().cloned().collect();
+            session.models.sort_by(|a, b| {
+                b.token_counts.total().cmp(&a.token_counts.total()).then_with(|| a.model.cmp(&b.model))
+            });
+            if let Some(project) = projects.get_mut(project) {
+                project.sessions.push(session);
+            }
+        }
+        for project in projects.values_mut() {
+            project.sessions.sort_by(|a, b| {
+                b.token_counts.total().cmp(&a.token_counts.total()).then_with(|| a.session_id.cmp(&b.session_id))
+            });
+        }
         
-        if let Some(usage) = &entry_with_project.entry.message.usage {
-            total_tokens.add_usage(usage);
-            
-            let project = project_map.entry(project_name.clone())
-                .or_insert_with(|| ProjectUsage {
-                    name: project_name.clone(),
-                    token_counts: TokenCounts::default(),
-                    entry_count: 0,
-                });
-            
-            project.token_counts.add_usage(usage);
-            project.entry_count += 1;
+        SessionBlock {
+            start_time,
+            end_time,
+            last_activity,
+            projects: projects.into_values().collect(),
+            models: self.models.values().cloned().collect(),
+            token_counts: self.token_counts.clone(),
+            cost_usd: self.cost_usd,
+            is_active: false,
         }
     }
-    
-    let projects: Vec<ProjectUsage> = project_map.into_values().collect();
-    
-    Some(SessionBlock {
-        start_time,
-        end_time,
-        last_activity,
-        projects,
-        token_counts: total_tokens,
-        is_active: false, // Will be updated by caller
-    })
 }
 
 #[cfg(test)]
@@ -319,6 +498,7 @@ mod tests {
             cost_usd: None,
             request_id: format!("req_{}", timestamp),
             version: "1.0.51".to_string(),
+            unknown: Default::default(),
         }
     }
     
@@ -415,47 +595,48 @@ mod tests {
     
     #[test]
     fn test_different_models_create_projects() {
-        let mut entries = vec![];
-        
-        // Opus model entries
-        entries.push(UsageEntry {
-            timestamp: "2025-01-12T14:00:00Z".parse().unwrap(),
-            message: Message {
-                id: "msg_1".to_string(),
-                msg_type: "message".to_string(),
-                role: "assistant".to_string(),
-                model: "claude-opus-4-20250514".to_string(),
-                usage: Some(TokenUsage {
-                    input_tokens: 100,
-                    output_tokens: 50,
-                    cache_creation_input_tokens: 0,
-                    cache_read_input_tokens: 0,
-                }),
+        let entries = vec![
+            // Opus model entries
+            UsageEntry {
+                timestamp: "2025-01-12T14:00:00Z".parse().unwrap(),
+                message: Message {
+                    id: "msg_1".to_string(),
+                    msg_type: "message".to_string(),
+                    role: "assistant".to_string(),
+                    model: "claude-opus-4-20250514".to_string(),
+                    usage: Some(TokenUsage {
+                        input_tokens: 100,
+                        output_tokens: 50,
+                        cache_creation_input_tokens: 0,
+                        cache_read_input_tokens: 0,
+                    }),
+                },
+                cost_usd: None,
+                request_id: "req_1".to_string(),
+                version: "1.0.51".to_string(),
+                unknown: Default::default(),
             },
-            cost_usd: None,
-            request_id: "req_1".to_string(),
-            version: "1.0.51".to_string(),
-        });
-        
-        // Sonnet model entries
-        entries.push(UsageEntry {
-            timestamp: "2025-01-12T14:15:00Z".parse().unwrap(),
-            message: Message {
-                id: "msg_2".to_string(),
-                msg_type: "message".to_string(),
-                role: "assistant".to_string(),
-                model: "claude-sonnet-4-20250514".to_string(),
-                usage: Some(TokenUsage {
-                    input_tokens: 200,
-                    output_tokens: 100,
-                    cache_creation_input_tokens: 0,
-                    cache_read_input_tokens: 0,
-                }),
+            // Sonnet model entries
+            UsageEntry {
+                timestamp: "2025-01-12T14:15:00Z".parse().unwrap(),
+                message: Message {
+                    id: "msg_2".to_string(),
+                    msg_type: "message".to_string(),
+                    role: "assistant".to_string(),
+                    model: "claude-sonnet-4-20250514".to_string(),
+                    usage: Some(TokenUsage {
+                        input_tokens: 200,
+                        output_tokens: 100,
+                        cache_creation_input_tokens: 0,
+                        cache_read_input_tokens: 0,
+                    }),
+                },
+                cost_usd: None,
+                request_id: "req_2".to_string(),
+                version: "1.0.51".to_string(),
+                unknown: Default::default(),
             },
-            cost_usd: None,
-            request_id: "req_2".to_string(),
-            version: "1.0.51".to_string(),
-        });
+        ];
         
         let window = group_into_single_window(entries).unwrap();
         assert_eq!(window.projects.len(), 2);
@@ -514,14 +695,16 @@ mod tests {
             EntryWithProject {
                 entry: create_test_entry("2025-01-14T18:00:00Z", 100, 50), // 6:00 PM
                 project: "test-project".to_string(),
+                session_id: "session".to_string(),
             },
             EntryWithProject {
                 entry: create_test_entry("2025-01-14T19:15:00Z", 200, 100), // 7:15 PM
                 project: "test-project".to_string(),
+                session_id: "session".to_string(),
             },
         ];
         
-        let window_period = find_active_window_period(&entries, now);
+        let window_period = find_active_window_period(&entries, now, Duration::hours(5));
         assert!(window_period.is_some());
         
         let (start, end) = window_period.unwrap();
@@ -540,20 +723,23 @@ mod tests {
             EntryWithProject {
                 entry: create_test_entry("2025-01-14T10:30:00Z", 100, 50),
                 project: "project1".to_string(),
+                session_id: "session".to_string(),
             },
             EntryWithProject {
                 entry: create_test_entry("2025-01-14T14:00:00Z", 200, 100),
                 project: "project1".to_string(),
+                session_id: "session".to_string(),
             },
             // Gap > 5 hours
             // Second window: 8:00 PM - 1:00 AM (active)
             EntryWithProject {
                 entry: create_test_entry("2025-01-14T20:15:00Z", 300, 150),
                 project: "project2".to_string(),
+                session_id: "session".to_string(),
             },
         ];
         
-        let window_period = find_active_window_period(&entries, now);
+        let window_period = find_active_window_period(&entries, now, Duration::hours(5));
         assert!(window_period.is_some());
         
         let (start, end) = window_period.unwrap();
@@ -583,7 +769,7 @@ mod tests {
     
     #[test]
     fn test_entries_without_usage_skipped() {
-        let mut entry1 = create_test_entry("2025-01-12T14:00:00Z", 100, 50);
+        let entry1 = create_test_entry("2025-01-12T14:00:00Z", 100, 50);
         let mut entry2 = create_test_entry("2025-01-12T14:30:00Z", 0, 0);
         entry2.message.usage = None; // No usage data
         let entry3 = create_test_entry("2025-01-12T15:00:00Z", 200, 100);
@@ -593,4 +779,181 @@ mod tests {
         // Only entries with usage should contribute to totals
         assert_eq!(window.token_counts.total(), 450);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_cost_aggregated_per_project() {
+        let now = "2025-01-12T15:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        
+        // Opus: 1000 input + 500 output = $0.015 + $0.0375
+        let opus = create_test_entry("2025-01-12T14:00:00Z", 1000, 500);
+        // Unknown model falls back to the recorded costUSD
+        let mut unknown = create_test_entry("2025-01-12T14:30:00Z", 1000, 500);
+        unknown.message.model = "some-future-model".to_string();
+        unknown.cost_usd = Some(0.5);
+        
+        let entries = vec![
+            EntryWithProject { entry: opus, project: "a".to_string(), session_id: "session".to_string() },
+            EntryWithProject { entry: unknown, project: "b".to_string(), session_id: "session".to_string() },
+        ];
+        
+        let window = group_into_single_window_with_projects_at_time(entries, now).unwrap();
+        let project_a = window.projects.iter().find(|p| p.name == "a").unwrap();
+        let project_b = window.projects.iter().find(|p| p.name == "b").unwrap();
+        
+        assert!((project_a.cost_usd - 0.0525).abs() < 1e-9);
+        assert!((project_b.cost_usd - 0.5).abs() < 1e-9);
+        assert!((window.cost_usd - 0.5525).abs() < 1e-9);
+        // Half an hour of activity
+        assert!((window.cost_burn_rate() - 1.105).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_sessions_grouped_within_projects() {
+        let now = "2025-01-12T15:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let entry = |timestamp: &str, tokens: u64, project: &str, session_id: &str| EntryWithProject {
+            entry: create_test_entry(timestamp, tokens, 0),
+            project: project.to_string(),
+            session_id: session_id.to_string(),
+        };
+        
+        let mut sonnet = entry("2025-01-12T14:40:00Z", 100, "a", "s1");
+        sonnet.entry.message.model = "claude-sonnet-4-20250514".to_string();
+        let entries = vec![
+            entry("2025-01-12T14:05:00Z", 300, "a", "s1"),
+            entry("2025-01-12T14:10:00Z", 1000, "a", "s2"),
+            sonnet,
+            // Same session id in another project is a separate session
+            entry("2025-01-12T14:20:00Z", 50, "b", "s1"),
+        ];
+        
+        let window = group_into_single_window_with_projects_at_time(entries, now).unwrap();
+        let project_a = window.projects.iter().find(|p| p.name == "a").unwrap();
+        let ids: Vec<_> = project_a.sessions.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, ["s2", "s1"], "highest usage first");
+        
+        let s1 = &project_a.sessions[1];
+        assert_eq!(s1.entry_count, 2);
+        assert_eq!(s1.token_counts.total(), 400);
+        assert_eq!(s1.first_activity, "2025-01-12T14:05:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(s1.last_activity, "2025-01-12T14:40:00Z".parse::<DateTime<Utc>>().unwrap());
+        let models: Vec<_> = s1.models.iter().map(|m| (m.model.as_str(), m.entry_count)).collect();
+        assert_eq!(models, [("claude-opus-4-20250514", 1), ("claude-sonnet-4-20250514", 1)]);
+        
+        let project_b = window.projects.iter().find(|p| p.name == "b").unwrap();
+        assert_eq!(project_b.sessions.len(), 1);
+        assert_eq!(project_b.sessions[0].token_counts.total(), 50);
+    }
+    
+    #[test]
+    fn test_models_aggregated_across_projects() {
+        let now = "2025-01-12T15:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        
+        let opus_a = create_test_entry("2025-01-12T14:00:00Z", 100, 50);
+        let opus_b = create_test_entry("2025-01-12T14:10:00Z", 200, 100);
+        let mut sonnet = create_test_entry("2025-01-12T14:20:00Z", 1000, 500);
+        sonnet.message.model = "claude-sonnet-4-20250514".to_string();
+        
+        let entries = vec![
+            EntryWithProject { entry: opus_a, project: "a".to_string(), session_id: "session".to_string() },
+            EntryWithProject { entry: opus_b, project: "b".to_string(), session_id: "session".to_string() },
+            EntryWithProject { entry: sonnet, project: "a".to_string(), session_id: "session".to_string() },
+        ];
+        
+        let window = group_into_single_window_with_projects_at_time(entries, now).unwrap();
+        assert_eq!(window.models.len(), 2);
+        
+        let opus = window.models.iter().find(|m| m.model == "claude-opus-4-20250514").unwrap();
+        let sonnet = window.models.iter().find(|m| m.model == "claude-sonnet-4-20250514").unwrap();
+        
+        assert_eq!(opus.entry_count, 2);
+        assert_eq!(opus.token_counts.total(), 450);
+        assert_eq!(sonnet.entry_count, 1);
+        assert_eq!(sonnet.token_counts.total(), 1500);
+        
+        let model_total: u64 = window.models.iter().map(|m| m.token_counts.total()).sum();
+        assert_eq!(model_total, window.token_counts.total());
+    }
+    
+    #[test]
+    fn test_split_into_windows() {
+        let entry = |timestamp: &str| EntryWithProject {
+            entry: create_test_entry(timestamp, 100, 50),
+            project: "test-project".to_string(),
+            session_id: "session".to_string(),
+        };
+        
+        // Deliberately out of order
+        let entries = vec![
+            entry("2025-01-14T20:15:00Z"),
+            entry("2025-01-14T10:30:00Z"),
+            entry("2025-01-14T14:00:00Z"),
+            entry("2025-01-14T15:10:00Z"),
+        ];
+        
+        let windows = split_into_windows(entries, Duration::hours(5));
+        assert_eq!(windows.len(), 3);
+        
+        let starts: Vec<DateTime<Utc>> = windows.iter().map(|(start, _)| *start).collect();
+        assert_eq!(starts, vec![
+            "2025-01-14T10:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            "2025-01-14T15:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            "2025-01-14T20:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+        ]);
+        
+        let counts: Vec<usize> = windows.iter().map(|(_, entries)| entries.len()).collect();
+        assert_eq!(counts, vec![2, 1, 1]);
+    }
+    
+    #[test]
+    fn test_configured_session_duration() {
+        let entry = |timestamp: &str| EntryWithProject {
+            entry: create_test_entry(timestamp, 100, 50),
+            project: "test-project".to_string(),
+            session_id: "session".to_string(),
+        };
+        
+        // 13:00 is inside a 5-hour window started at 10:00, but not a 3-hour one
+        let entries = vec![
+            entry("2025-01-14T10:30:00Z"),
+            entry("2025-01-14T13:10:00Z"),
+        ];
+        let now: DateTime<Utc> = "2025-01-14T13:30:00Z".parse().unwrap();
+        
+        let window = group_into_single_window_with_duration(entries, now, Duration::hours(3)).unwrap();
+        assert_eq!(window.start_time, "2025-01-14T13:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(window.end_time, "2025-01-14T16:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(window.token_counts.total(), 150);
+    }
+    
+    #[test]
+    fn test_group_subset_keeps_account_window_boundaries() {
+        let entry = |timestamp: &str, project: &str| EntryWithProject {
+            entry: create_test_entry(timestamp, 100, 50),
+            project: project.to_string(),
+            session_id: "session".to_string(),
+        };
+        
+        let entries = vec![
+            entry("2025-01-14T10:30:00Z", "/work/other"),
+            entry("2025-01-14T12:10:00Z", "/work/client"),
+        ];
+        let now: DateTime<Utc> = "2025-01-14T13:00:00Z".parse().unwrap();
+        let is_client = |e: &EntryWithProject| e.project == "/work/client";
+        
+        // Filtering first would start the window at 12:00
+        let filtered: Vec<EntryWithProject> = entries.iter().filter(|e| is_client(e)).cloned().collect();
+        let window = group_into_single_window_with_duration(filtered, now, Duration::hours(5)).unwrap();
+        assert_eq!(window.start_time, "2025-01-14T12:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        
+        let window = group_subset_into_account_window(entries.clone(), now, Duration::hours(5), is_client).unwrap();
+        assert_eq!(window.start_time, "2025-01-14T10:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!(window.token_counts.total(), 150);
+        assert_eq!(window.projects.len(), 1);
+        assert!(window.is_active);
+        
+        // No matching usage still reports the account window
+        let window = group_subset_into_account_window(entries, now, Duration::hours(5), |_| false).unwrap();
+        assert_eq!(window.token_counts.total(), 0);
+        assert_eq!(window.end_time, "2025-01-14T15:00:00Z".parse::<DateTime<Utc>>().unwrap());
+    }
+}
@@ -1,7 +1,26 @@
+use std::fs;
 use std::path::Path;
+use clauditor::config::Config;
+use clauditor::coordinator;
+use clauditor::diagnostics;
+use clauditor::export::{self, ExportFormat, ExportWriter};
 use clauditor::parser;
+use clauditor::schema::{self, SchemaVersion};
+use clauditor::types::{EntryWithProject, SessionBlock, UsageEntry};
 use clauditor::window;
-use chrono::Timelike;
+use chrono::{DateTime, Timelike, Utc};
+
+/// Group fixture entries as clauditor would see them at `now`
+/// 
+/// Fixture data is historical, so entries after `now` are dropped to simulate
+/// running the tool at that moment.
+fn window_at(entries: Vec<EntryWithProject>, now: &str) -> Option<SessionBlock> {
+    let now: DateTime<Utc> = now.parse().unwrap();
+    let visible = entries.into_iter()
+        .filter(|e| e.entry.timestamp <= now)
+        .collect();
+    window::group_into_single_window_with_projects_at_time(visible, now)
+}
 
 #[test]
 fn test_edge_cases_jsonl() {
@@ -9,7 +28,7 @@ fn test_edge_cases_jsonl() {
     let entries = parser::parse_file(path).expect("Failed to parse edge_cases.jsonl");
     
     // Should parse some valid entries despite malformed lines
-    assert!(entries.len() > 0, "Should parse some valid entries");
+    assert!(!entries.is_empty(), "Should parse some valid entries");
     
     // Check that cache tokens are parsed correctly
     let entry_with_cache = entries.iter()
@@ -22,12 +41,13 @@ fn test_edge_cases_jsonl() {
     assert!(usage.cache_creation_input_tokens > 0 || usage.cache_read_input_tokens > 0);
     
     // Group into single window
-    let window = window::group_into_single_window_with_projects(entries.into_iter().map(|e| {
-        clauditor::types::EntryWithProject {
+    let window = window_at(entries.into_iter().map(|e| {
+        EntryWithProject {
             entry: e,
             project: "test-project".to_string(),
+            session_id: "session".to_string(),
         }
-    }).collect()).expect("Should create a window");
+    }).collect(), "2025-01-13T13:00:00Z").expect("Should create a window");
     
     // Check totals include cache tokens
     assert!(window.token_counts.cache_creation_tokens > 0 || window.token_counts.cache_read_tokens > 0,
@@ -43,23 +63,24 @@ fn test_multiple_windows_jsonl() {
     let path = Path::new("test_data/multiple_windows_with_gaps.jsonl");
     let entries = parser::parse_file(path).expect("Failed to parse multiple_windows_with_gaps.jsonl");
     
-    let window = window::group_into_single_window_with_projects(entries.into_iter().map(|e| {
+    let window = window_at(entries.into_iter().map(|e| {
         let project = match e.message.model.as_str() {
             "claude-opus-4-20250514" => "project-opus",
             "claude-sonnet-4-20250514" => "project-sonnet",
             _ => "unknown",
         };
-        clauditor::types::EntryWithProject {
+        EntryWithProject {
             entry: e,
             project: project.to_string(),
+            session_id: "session".to_string(),
         }
-    }).collect()).expect("Should create a window");
+    }).collect(), "2025-01-13T11:30:00Z").expect("Should create a window");
     
     // Should create single window starting from earliest entry
     assert_eq!(window.start_time.hour(), 9, "Window should start at 9:00");
     
     // Different models should create different projects within same window
-    assert!(window.projects.len() >= 1, "Window should have at least one project");
+    assert!(!window.projects.is_empty(), "Window should have at least one project");
 }
 
 #[test] 
@@ -67,12 +88,13 @@ fn test_continuous_session_jsonl() {
     let path = Path::new("test_data/single_session_continuous.jsonl");
     let entries = parser::parse_file(path).expect("Failed to parse single_session_continuous.jsonl");
     
-    let window = window::group_into_single_window_with_projects(entries.into_iter().map(|e| {
-        clauditor::types::EntryWithProject {
+    let window = window_at(entries.into_iter().map(|e| {
+        EntryWithProject {
             entry: e,
             project: "test-project".to_string(),
+            session_id: "session".to_string(),
         }
-    }).collect()).expect("Should create a window");
+    }).collect(), "2025-01-13T18:59:30Z").expect("Should create a window");
     
     assert_eq!(window.start_time.hour(), 14);
     assert_eq!(window.end_time.hour(), 19);
@@ -97,23 +119,24 @@ fn test_empty_and_malformed_jsonl() {
 
 #[test]
 fn test_single_account_wide_window() {
-    use chrono::{Utc, Duration};
+    use chrono::Utc;
     use clauditor::types::is_block_active;
     
     let path = Path::new("test_data/multiple_active_sessions.jsonl");
     let entries = parser::parse_file(path).expect("Failed to parse multiple_active_sessions.jsonl");
     
-    let window = window::group_into_single_window_with_projects(entries.into_iter().map(|e| {
+    let window = window_at(entries.into_iter().map(|e| {
         let project = match e.message.model.as_str() {
             "claude-opus-4-20250514" => "project-opus",
             "claude-sonnet-4-20250514" => "project-sonnet",
             _ => "unknown",
         };
-        clauditor::types::EntryWithProject {
+        EntryWithProject {
             entry: e,
             project: project.to_string(),
+            session_id: "session".to_string(),
         }
-    }).collect()).expect("Should create a window");
+    }).collect(), "2025-01-13T13:45:00Z").expect("Should create a window");
     
     // Should create single window starting from earliest entry
     assert_eq!(window.start_time.hour(), 9, "Window should start at 9:00 (earliest entry)");
@@ -125,7 +148,8 @@ fn test_single_account_wide_window() {
         .collect();
     
     assert!(project_names.contains(&"project-opus"), "Should have opus project");
-    assert!(project_names.contains(&"project-sonnet"), "Should have sonnet project");
+    // Sonnet activity starts at 15:00, after this window ends, so it belongs to the next window
+    assert!(!project_names.contains(&"project-sonnet"), "Should not have sonnet project");
     
     // Test active status
     let mock_now = "2025-01-13T13:00:00Z".parse::<chrono::DateTime<Utc>>().unwrap();
@@ -135,30 +159,242 @@ fn test_single_account_wide_window() {
 
 #[test]
 fn test_single_window_with_overlapping_sessions() {
-    use chrono::{Utc, Duration};
-    
     let path = Path::new("test_data/overlapping_active_windows.jsonl");
     let entries = parser::parse_file(path).expect("Failed to parse overlapping_active_windows.jsonl");
     
-    let window = window::group_into_single_window_with_projects(entries.into_iter().map(|e| {
+    let window = window_at(entries.into_iter().map(|e| {
         let project = match e.message.model.as_str() {
             "claude-opus-4-20250514" => "project-opus",
             "claude-sonnet-4-20250514" => "project-sonnet",
             _ => "unknown",
         };
-        clauditor::types::EntryWithProject {
+        EntryWithProject {
             entry: e,
             project: project.to_string(),
+            session_id: "session".to_string(),
         }
-    }).collect()).expect("Should create a window");
+    }).collect(), "2025-01-13T18:50:00Z").expect("Should create a window");
     
     // Should create single window starting from earliest entry
     assert_eq!(window.start_time.hour(), 14, "Window should start at 14:00 (earliest entry)");
     assert_eq!(window.end_time.hour(), 19, "Window should end at 19:00 (5 hours later)");
     
     // All activity from both "sessions" should be in the same window
-    assert!(window.projects.len() >= 1, "Should have projects from overlapping sessions");
+    assert!(!window.projects.is_empty(), "Should have projects from overlapping sessions");
     
     // Token counts should include all activity within the 5-hour window
     assert!(window.token_counts.total() > 0, "Window should have token usage");
-}
\ No newline at end of file
+}
+
+/// Copy the resumed-session fixtures into a Claude data directory as two
+/// session files of the same project, the way a resumed session looks on disk
+fn resumed_session_fixture() -> (tempfile::TempDir, Config) {
+    let dir = tempfile::tempdir().unwrap();
+    let project_dir = dir.path().join("projects").join("-home-me-proj");
+    fs::create_dir_all(&project_dir).unwrap();
+    fs::copy("test_data/resumed_session_original.jsonl", project_dir.join("original.jsonl")).unwrap();
+    fs::copy("test_data/resumed_session_fork.jsonl", project_dir.join("fork.jsonl")).unwrap();
+    
+    let config = Config {
+        claude_paths: vec![dir.path().to_path_buf()],
+        state_dir: Some(dir.path().join("state")),
+        ..Config::default()
+    };
+    (dir, config)
+}
+
+#[test]
+fn test_duplicates_across_session_files_are_dropped() {
+    let (_dir, config) = resumed_session_fixture();
+    
+    let entries = coordinator::load_entries(&config).unwrap();
+    assert_eq!(entries.len(), 5, "3 original + 2 new in the fork");
+    let report = diagnostics::run_diagnostics(&config).unwrap();
+    assert_eq!(report.totals.duplicates_dropped, 2);
+    
+    // Same message id but a different request is a separate API call
+    let msg_r003: Vec<_> = entries.iter()
+        .filter(|e| e.entry.message.id == "msg_r003")
+        .collect();
+    assert_eq!(msg_r003.len(), 2);
+    
+    let window = window_at(entries, "2025-01-14T11:00:00Z").unwrap();
+    assert_eq!(window.token_counts.total(), 1500 + 2800 + 2100 + 1000 + 500);
+}
+
+#[test]
+fn test_resumed_session_is_split_into_sessions() {
+    let (_dir, config) = resumed_session_fixture();
+    
+    let entries = coordinator::load_entries(&config).unwrap();
+    let window = window_at(entries, "2025-01-14T11:00:00Z").unwrap();
+    let project = &window.projects[0];
+    
+    // Copied messages count once, toward whichever session file was read first
+    let mut ids: Vec<_> = project.sessions.iter().map(|s| s.session_id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, ["fork", "original"]);
+    assert_eq!(project.sessions.iter().map(|s| s.entry_count).sum::<usize>(), 5);
+    
+    let session_total: u64 = project.sessions.iter().map(|s| s.token_counts.total()).sum();
+    assert_eq!(session_total, project.token_counts.total());
+}
+
+#[test]
+fn test_export_skips_duplicates() {
+    let (_dir, config) = resumed_session_fixture();
+    
+    let mut writer = ExportWriter::new(Vec::new(), ExportFormat::Ndjson).unwrap();
+    export::export_entries(
+        &config,
+        "2025-01-14T00:00:00Z".parse().unwrap(),
+        "2025-01-15T00:00:00Z".parse().unwrap(),
+        &mut writer,
+    ).unwrap();
+    
+    assert_eq!(writer.finish().unwrap(), (5, 2));
+}
+
+/// Parse one of the per-release fixtures, asserting every usage line was
+/// understood and checked against `layout`
+fn parse_version_fixture(version: &str, layout: SchemaVersion) -> Vec<UsageEntry> {
+    let path = format!("test_data/claude_code_versions/{}.jsonl", version);
+    let (entries, diagnostics) = parser::parse_file_with_diagnostics(Path::new(&path))
+        .unwrap_or_else(|e| panic!("Failed to parse {}: {}", path, e));
+    
+    assert!(!diagnostics.has_problems(), "{} had problems: {:?}", version, diagnostics);
+    for entry in &entries {
+        assert_eq!(SchemaVersion::detect(Some(&entry.version)), layout, "{}", entry.version);
+    }
+    entries
+}
+
+/// The first usage line of a per-release fixture, as JSON
+fn version_fixture_line(version: &str) -> serde_json::Value {
+    let path = format!("test_data/claude_code_versions/{}.jsonl", version);
+    fs::read_to_string(path).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|value| value.pointer("/message/usage").is_some())
+        .unwrap()
+}
+
+#[test]
+fn test_claude_code_0_2_entries_without_request_id() {
+    let entries = parse_version_fixture("0.2.x", SchemaVersion::V0_2);
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.request_id.is_empty()));
+    assert_eq!(entries[0].cost_usd, Some(0.0123));
+    
+    // Cache counts were optional in this release
+    let usage = entries[1].message.usage.as_ref().unwrap();
+    assert_eq!((usage.input_tokens, usage.cache_read_input_tokens), (300, 0));
+}
+
+#[test]
+fn test_claude_code_1_0_17_entries() {
+    let entries = parse_version_fixture("1.0.17", SchemaVersion::V1_0);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].request_id, "req_b1");
+    assert_eq!(entries[0].message.usage.as_ref().unwrap().cache_read_input_tokens, 12000);
+    assert_eq!(entries[0].unknown.entry["cwd"], "/home/me/proj");
+    assert_eq!(entries[0].unknown.message["stop_reason"], "end_turn");
+}
+
+#[test]
+fn test_claude_code_1_0_51_entries_without_cost() {
+    let entries = parse_version_fixture("1.0.51", SchemaVersion::V1_0_40);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].cost_usd, None);
+    assert_eq!(entries[0].unknown.usage["service_tier"], "standard");
+}
+
+#[test]
+fn test_claude_code_1_0_90_entries_with_extended_usage() {
+    let entries = parse_version_fixture("1.0.90", SchemaVersion::V1_0_40);
+    assert_eq!(entries.len(), 2);
+    
+    let usage = entries[0].message.usage.as_ref().unwrap();
+    assert_eq!(usage.cache_read_input_tokens, 0, "null cache counts become zero");
+    assert_eq!(entries[0].unknown.usage["server_tool_use"]["web_search_requests"], 1);
+    assert_eq!(entries[0].unknown.usage["cache_creation"]["ephemeral_5m_input_tokens"], 1000);
+    
+    // API error messages have no model or id
+    assert_eq!(entries[1].message.model, schema::SYNTHETIC_MODEL);
+    assert!(entries[1].message.id.is_empty());
+    assert_eq!(entries[1].unknown.entry["isApiErrorMessage"], true);
+}
+
+#[test]
+fn test_entries_are_checked_against_their_release() {
+    // 1.0.17 always wrote a request id, so one without it is a format change
+    let mut entry = version_fixture_line("1.0.17");
+    entry.as_object_mut().unwrap().remove("requestId");
+    let err = schema::parse_entry(entry.clone()).unwrap_err();
+    assert!(err.to_string().contains("requestId"), "{}", err);
+    
+    // 0.2.x never wrote one, but always wrote a cost
+    let mut entry_0_2 = version_fixture_line("0.2.x");
+    assert!(entry_0_2.get("requestId").is_none());
+    assert!(schema::parse_entry(entry_0_2.clone()).is_ok());
+    entry_0_2.as_object_mut().unwrap().remove("costUSD");
+    assert!(schema::parse_entry(entry_0_2).is_err());
+    
+    // Without a version there is nothing to check against
+    entry.as_object_mut().unwrap().remove("version");
+    assert!(schema::parse_entry(entry).is_ok());
+}
+
+/// Append `count` entries in small fragments, flushing after each one, so a
+/// concurrent reader regularly sees half-written lines
+fn write_entries_in_fragments(path: &Path, count: usize, line_ending: &str) {
+    use std::io::Write;
+
+    let template = fs::read_to_string("test_data/sample.jsonl").unwrap();
+    let line = template.lines().next().unwrap();
+    let mut file = fs::OpenOptions::new().append(true).open(path).unwrap();
+
+    for i in 0..count {
+        let record = format!("{}{}", line.replace("msg_001", &format!("msg_race_{}", i)), line_ending);
+        for fragment in record.as_bytes().chunks(37) {
+            file.write_all(fragment).unwrap();
+            file.flush().unwrap();
+            std::thread::yield_now();
+        }
+    }
+}
+
+/// Poll a file the way the watcher does until `expected` entries were read
+fn read_incrementally_until(path: &Path, expected: usize) -> (Vec<UsageEntry>, u64) {
+    let mut entries = Vec::new();
+    let mut position = 0;
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+
+    while entries.len() < expected && std::time::Instant::now() < deadline {
+        let (mut new_entries, new_position) = parser::parse_file_from_position(path, position).unwrap();
+        assert!(new_position >= position);
+        entries.append(&mut new_entries);
+        position = new_position;
+    }
+    (entries, position)
+}
+
+#[test]
+fn test_incremental_reads_racing_with_writes() {
+    for line_ending in ["\n", "\r\n"] {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        fs::write(&path, "").unwrap();
+
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || write_entries_in_fragments(&writer_path, 200, line_ending));
+        let (entries, position) = read_incrementally_until(&path, 200);
+        writer.join().unwrap();
+
+        // Every entry is read exactly once, in order, and the offset lands on the end
+        let ids: Vec<String> = entries.iter().map(|e| e.message.id.clone()).collect();
+        let expected: Vec<String> = (0..200).map(|i| format!("msg_race_{}", i)).collect();
+        assert_eq!(ids, expected, "line ending {:?}", line_ending);
+        assert_eq!(position, fs::metadata(&path).unwrap().len());
+    }
+}